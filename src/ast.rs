@@ -1,6 +1,7 @@
 use crate::literal::Literal;
 use crate::expr::{ExprVisitor, Expr};
 use crate::stmt::{StmtVisitor, Stmt};
+use crate::token::Token;
 
 /// Returns a string representation of the expression in paranthesize.
 macro_rules! parenthesize {
@@ -116,6 +117,78 @@ impl ExprVisitor<String> for ASTPrinter {
         string += ")";
         string
     }
+
+    fn visit_lambda_expr(&mut self, expr: &Expr) -> String {
+        let Expr::Lambda(data) = expr else { unreachable!() };
+        format!("(fun {})", self.function(&data.params, &data.body))
+    }
+
+    fn visit_list_expr(&mut self, expr: &Expr) -> String {
+        let Expr::List(data) = expr else { unreachable!() };
+        let mut string = String::new();
+        string += "(list";
+        for element in &data.elements {
+            string += " ";
+            string += &element.accept(self);
+        }
+        string += ")";
+        string
+    }
+
+    fn visit_map_expr(&mut self, expr: &Expr) -> String {
+        let Expr::Map(data) = expr else { unreachable!() };
+        let mut string = String::new();
+        string += "(map";
+        for (key, value) in &data.entries {
+            string += " (";
+            string += &key.accept(self);
+            string += " ";
+            string += &value.accept(self);
+            string += ")";
+        }
+        string += ")";
+        string
+    }
+
+    fn visit_index_expr(&mut self, expr: &Expr) -> String {
+        let Expr::Index(data) = expr else { unreachable!() };
+        parenthesize!(self, "index", data.object, data.index)
+    }
+
+    fn visit_index_set_expr(&mut self, expr: &Expr) -> String {
+        let Expr::IndexSet(data) = expr else { unreachable!() };
+        parenthesize!(self, "index-set", data.object, data.index, data.value)
+    }
+}
+
+impl ASTPrinter {
+    /// Prints the parameters and the body of a function.
+    fn function(&mut self, params: &[Token], body: &[Stmt]) -> String {
+        let mut string = String::from("(");
+        for param in params {
+            string += &param.lexeme;
+            string += " ";
+        }
+        string = string.trim_end().to_string();
+        string += ") { ";
+        for body in body {
+            string += &body.accept(self);
+        }
+        string += " }";
+        string
+    }
+
+    /// Prints the statements of a block.
+    fn block(&mut self, statements: &[Stmt]) -> String {
+        let mut string = String::new();
+        string += "{";
+        for stmt in statements {
+            string += " ";
+            string += &stmt.accept(self);
+        }
+        string += " }";
+        string
+    }
 }
 
 impl StmtVisitor<String> for ASTPrinter {
@@ -129,17 +202,9 @@ impl StmtVisitor<String> for ASTPrinter {
         let mut string = String::new();
         string += "(fun ";
         string += &data.name.lexeme;
-        string += " (";
-        for param in &data.params {
-            string += &param.lexeme;
-            string += " ";
-        }
-        string = string.trim_end().to_string();
-        string += ") { ";
-        for body in &data.body {
-            string += &body.accept(self);
-        }
-        string += " })";
+        string += " ";
+        string += &self.function(&data.params, &data.body);
+        string += ")";
         string
     }
 
@@ -184,24 +249,26 @@ impl StmtVisitor<String> for ASTPrinter {
     fn visit_while_stmt(&mut self, stmt: &Stmt) -> String {
         let Stmt::While(data) = stmt else { unreachable!() };
         let mut string = String::new();
-        string += "(while ";
+        string += "(";
+        if let Some(label) = &data.label {
+            string += &label.lexeme;
+            string += ": ";
+        }
+        string += "while ";
         string += &data.condition.accept(self);
         string += " ";
         string += &data.body.accept(self);
+        if let Some(increment) = &data.increment {
+            string += " ";
+            string += &increment.accept(self);
+        }
         string += ")";
         string
     }
 
     fn visit_block_stmt(&mut self, stmt: &Stmt) -> String {
         let Stmt::Block(data) = stmt else { unreachable!() };
-        let mut string = String::new();
-        string += "{";
-        for stmt in &data.statements {
-            string += " ";
-            string += &stmt.accept(self);
-        }
-        string += " }";
-        string
+        self.block(&data.statements)
     }
 
     fn visit_class_stmt(&mut self, stmt: &Stmt) -> String {
@@ -222,8 +289,57 @@ impl StmtVisitor<String> for ASTPrinter {
     }
 
     fn visit_break_stmt(&mut self, stmt: &Stmt) -> String {
-        let Stmt::Break(_) = stmt else { unreachable!() };
-        "break".to_string()
+        let Stmt::Break(data) = stmt else { unreachable!() };
+        match &data.label {
+            Some(label) => format!("(break {})", label.lexeme),
+            None => "break".to_string(),
+        }
+    }
+
+    fn visit_import_stmt(&mut self, stmt: &Stmt) -> String {
+        let Stmt::Import(data) = stmt else { unreachable!() };
+        match &data.alias {
+            Some(alias) => format!("(import {} as {})", data.path.lexeme, alias.lexeme),
+            None => {
+                let names = data.names.iter()
+                    .map(|name| name.lexeme.clone())
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                format!("(from {} import {})", data.path.lexeme, names)
+            },
+        }
+    }
+
+    fn visit_throw_stmt(&mut self, stmt: &Stmt) -> String {
+        let Stmt::Throw(data) = stmt else { unreachable!() };
+        format!("(throw {})", data.value.accept(self))
+    }
+
+    fn visit_try_stmt(&mut self, stmt: &Stmt) -> String {
+        let Stmt::Try(data) = stmt else { unreachable!() };
+        let mut string = String::new();
+        string += "(try ";
+        string += &self.block(&data.body);
+        if let Some(catch) = &data.catch {
+            string += &format!(" (catch {} ", catch.name.lexeme);
+            string += &self.block(&catch.body);
+            string += ")";
+        }
+        if let Some(finally) = &data.finally {
+            string += " (finally ";
+            string += &self.block(finally);
+            string += ")";
+        }
+        string += ")";
+        string
+    }
+
+    fn visit_continue_stmt(&mut self, stmt: &Stmt) -> String {
+        let Stmt::Continue(data) = stmt else { unreachable!() };
+        match &data.label {
+            Some(label) => format!("(continue {})", label.lexeme),
+            None => "continue".to_string(),
+        }
     }
 }
 
@@ -425,4 +541,75 @@ mod tests {
             .collect::<String>();
         assert_eq!(ast, "(class a < b (fun init () { (call (super init) ()) }))");
     }
+
+    #[test]
+    fn test_ast_printer_with_list() {
+        let source = "[1, 2][0] = [];";
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan_tokens();
+        let mut parser = Parser::new(tokens);
+        let statements = parser.parse();
+        let mut printer = ASTPrinter {};
+        let ast = statements.iter()
+            .map(|stmt| { stmt.accept(&mut printer) })
+            .collect::<String>();
+        assert_eq!(ast, "(index-set (list 1 2) 0 (list))");
+    }
+
+    #[test]
+    fn test_ast_printer_with_map() {
+        let source = "print {\"a\": 1, 2: true}[\"a\"];";
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan_tokens();
+        let mut parser = Parser::new(tokens);
+        let statements = parser.parse();
+        let mut printer = ASTPrinter {};
+        let ast = statements.iter()
+            .map(|stmt| { stmt.accept(&mut printer) })
+            .collect::<String>();
+        assert_eq!(ast, "(print (index (map (a 1) (2 true)) a))");
+    }
+
+    #[test]
+    fn test_ast_printer_with_lambda() {
+        let source = "var f = fun (a, b) => a + b;";
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan_tokens();
+        let mut parser = Parser::new(tokens);
+        let statements = parser.parse();
+        let mut printer = ASTPrinter {};
+        let ast = statements.iter()
+            .map(|stmt| { stmt.accept(&mut printer) })
+            .collect::<String>();
+        assert_eq!(ast, "(var f = (fun (a b) { (return (+ a b)) }))");
+    }
+
+    #[test]
+    fn test_ast_printer_with_import() {
+        let source = "import \"lib.rocks\" as lib; from \"lib.rocks\" import a, b;";
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan_tokens();
+        let mut parser = Parser::new(tokens);
+        let statements = parser.parse();
+        let mut printer = ASTPrinter {};
+        let ast = statements.iter()
+            .map(|stmt| { stmt.accept(&mut printer) })
+            .collect::<Vec<String>>()
+            .join(" ");
+        assert_eq!(ast, "(import lib.rocks as lib) (from lib.rocks import a b)");
+    }
+
+    #[test]
+    fn test_ast_printer_with_try() {
+        let source = "try { throw 1; } catch (e) { print e; } finally { print 2; }";
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan_tokens();
+        let mut parser = Parser::new(tokens);
+        let statements = parser.parse();
+        let mut printer = ASTPrinter {};
+        let ast = statements.iter()
+            .map(|stmt| { stmt.accept(&mut printer) })
+            .collect::<String>();
+        assert_eq!(ast, "(try { (throw 1) } (catch e { (print e) }) (finally { (print 2) }))");
+    }
 }
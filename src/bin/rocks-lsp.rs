@@ -0,0 +1,7 @@
+use std::io;
+
+use rocks_lang::lsp;
+
+fn main() {
+    lsp::serve(io::stdin().lock(), io::stdout());
+}
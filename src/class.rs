@@ -1,11 +1,13 @@
 use std::collections::HashMap;
 use std::fmt::{Debug, Display};
 use std::cell::RefCell;
+use std::mem;
 use std::rc::Rc;
 
-use crate::error::RuntimeError;
+use crate::error::{self, RuntimeError};
 use crate::function::Function;
-use crate::object::{Callable, Object};
+use crate::gc::{Collect, Trace, Tracer};
+use crate::object::{Arity, Callable, Object};
 use crate::interpreter::Interpreter;
 use crate::token::Token;
 
@@ -39,6 +41,11 @@ impl Class {
         Class { name, superclass, methods }
     }
 
+    /// Returns the name of the class.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     /// Returns the method with the given name. If the method is not defined, it will return `None`.
     /// If the method is not defined in this class, it will inherit the method from its superclass
     /// (if any).
@@ -51,6 +58,17 @@ impl Class {
             return None;
         }
     }
+
+    /// Returns the names of the methods of the class, including the inherited ones.
+    pub fn method_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.methods.keys().cloned().collect();
+
+        if let Some(Object::Class(ref superclass)) = self.superclass {
+            names.extend(superclass.borrow().method_names());
+        }
+
+        names
+    }
 }
 
 impl Display for Class {
@@ -68,11 +86,11 @@ impl PartialEq for Class {
 impl Callable for Class {
     /// Returns the arity of the `init` method of the class. If the `init` method is not defined,
     /// it will return 0.
-    fn arity(&self) -> usize {
+    fn arity(&self) -> Arity {
         if let Some(initializer) = self.get_method("init") {
             initializer.arity()
         } else {
-            0
+            Arity::Fixed(0)
         }
     }
 
@@ -80,10 +98,13 @@ impl Callable for Class {
     /// If the `init` method is not defined, it will inherit the `init` method of its superclass
     /// (if any).
     fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, RuntimeError> {
-        let instance = Object::from(Instance::from(&Rc::new(RefCell::new(self.clone()))));
+        let class = Rc::new(RefCell::new(self.clone()));
+        let instance = Object::from(Instance::from(&class));
+        interpreter.track(&Object::Class(class));
+        interpreter.track(&instance);
 
         if let Some(mut initializer) = self.get_method("init") {
-            initializer.bind(instance.clone()).call(interpreter, arguments)?;
+            initializer.bind(instance.clone(), interpreter).call(interpreter, arguments)?;
         }
 
         return Ok(instance);
@@ -107,19 +128,35 @@ impl Instance {
     /// Returns the value of the field with the given name. If the field is not defined, it will
     /// return an error. If the field is not defined in this class, it will inherit the field from
     /// its superclass (if any).
-    pub fn get(&self, name: &Token, instance: &Object) -> Result<Object, RuntimeError> {
+    pub fn get(&self, name: &Token, instance: &Object, interpreter: &mut Interpreter) -> Result<Object, RuntimeError> {
         if let Some(field) = self.fields.get(&name.lexeme) {
             Ok(field.clone())
         } else if let Some(mut method) = self.class.borrow().get_method(&name.lexeme) {
-            Ok(Object::from(method.bind(instance.clone())))
+            Ok(Object::from(method.bind(instance.clone(), interpreter)))
         } else {
+            let methods = self.class.borrow().method_names();
+            let candidates = self.fields.keys().chain(&methods).map(String::as_str);
+
             Err(RuntimeError {
                 token: name.clone(),
                 message: format!("Undefined property '{}'", name.lexeme),
+                help: error::did_you_mean(&name.lexeme, candidates),
+                trace: vec![],
+                value: None,
             })
         }
     }
 
+    /// Returns the class of the instance.
+    pub fn class(&self) -> &Rc<RefCell<Class>> {
+        &self.class
+    }
+
+    /// Returns the names and values of the fields of the instance.
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &Object)> {
+        self.fields.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
     /// Sets the value of the field with the given name.
     pub fn set(&mut self, name: &Token, value: Object) {
         self.fields.insert(name.lexeme.clone(), value);
@@ -144,3 +181,41 @@ impl PartialEq for Instance {
         false
     }
 }
+
+impl Trace for RefCell<Class> {
+    fn trace(&self, tracer: &mut Tracer) {
+        let Ok(class) = self.try_borrow() else {
+            return tracer.pin();
+        };
+
+        if let Some(superclass) = &class.superclass {
+            superclass.trace(tracer);
+        }
+
+        class.methods.values().for_each(|method| method.trace(tracer));
+    }
+}
+
+impl Collect for RefCell<Class> {
+    fn clear(&self) {
+        let _members = self.try_borrow_mut()
+            .map(|mut class| (class.superclass.take(), mem::take(&mut class.methods)));
+    }
+}
+
+impl Trace for RefCell<Instance> {
+    fn trace(&self, tracer: &mut Tracer) {
+        let Ok(instance) = self.try_borrow() else {
+            return tracer.pin();
+        };
+
+        tracer.edge(&instance.class);
+        instance.fields.values().for_each(|field| field.trace(tracer));
+    }
+}
+
+impl Collect for RefCell<Instance> {
+    fn clear(&self) {
+        let _fields = self.try_borrow_mut().map(|mut instance| mem::take(&mut instance.fields));
+    }
+}
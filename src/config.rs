@@ -0,0 +1,215 @@
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+
+use crate::stdlib::Stdlib;
+
+/// Represents a capability of the host that scripts have to be granted to use it.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Capability {
+    /// Reading and writing files, which includes importing modules from files.
+    Filesystem,
+    /// Reading environment variables and exiting the host process.
+    Environment,
+    /// Reading from the standard input.
+    Stdin,
+    /// Reading the time and sleeping.
+    Clock,
+}
+
+impl Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Capability::Filesystem => "filesystem",
+            Capability::Environment => "environment",
+            Capability::Stdin => "stdin",
+            Capability::Clock => "clock",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// Represents the configuration of a backend.
+///
+/// The capability flags decide which natives are usable by the scripts. Natives that need a
+/// denied capability are still defined, so that calling them reports which capability is missing
+/// instead of an undefined variable. By default, every capability is granted; untrusted scripts
+/// should be run with [`InterpreterConfig::sandboxed`].
+/// ```
+/// use rocks_lang::rocks;
+/// use rocks_lang::config::InterpreterConfig;
+///
+/// let mut output = Vec::new();
+/// let config = InterpreterConfig { clock: true, ..InterpreterConfig::sandboxed() };
+/// let mut rocks = rocks::new(&mut output).with_config(config).with_reporter(|_: &_| {});
+///
+/// rocks.run("print clock() > 0;".to_string()).unwrap();
+///
+/// let diagnostics = rocks.run("input();".to_string()).unwrap_err();
+/// assert_eq!(
+///     diagnostics[0].to_string(),
+///     "[line 1:7] Error at ')': Cannot call 'input' without the 'stdin' capability",
+/// );
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct InterpreterConfig {
+    /// Grants [`Capability::Filesystem`].
+    pub filesystem: bool,
+    /// Grants [`Capability::Environment`].
+    pub environment: bool,
+    /// Grants [`Capability::Stdin`].
+    pub stdin: bool,
+    /// Grants [`Capability::Clock`].
+    pub clock: bool,
+    /// The native modules that the scripts can import.
+    pub stdlib: Stdlib,
+    /// The limits on the execution of the scripts.
+    pub limits: Limits,
+}
+
+impl InterpreterConfig {
+    /// Creates a configuration that denies every capability.
+    /// The modules of the standard library are still available, but only the functions that do
+    /// not need a capability can be called.
+    pub fn sandboxed() -> Self {
+        InterpreterConfig {
+            filesystem: false,
+            environment: false,
+            stdin: false,
+            clock: false,
+            stdlib: Stdlib::default(),
+            limits: Limits::default(),
+        }
+    }
+
+    /// Returns if the given capability is granted.
+    pub fn allows(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::Filesystem => self.filesystem,
+            Capability::Environment => self.environment,
+            Capability::Stdin => self.stdin,
+            Capability::Clock => self.clock,
+        }
+    }
+
+    /// Returns an error message if the given action needs a capability that is denied.
+    pub fn check(&self, capability: Capability, action: &str) -> Result<(), String> {
+        match self.allows(capability) {
+            true => Ok(()),
+            false => Err(format!("Cannot {action} without the '{capability}' capability")),
+        }
+    }
+}
+
+impl Default for InterpreterConfig {
+    fn default() -> Self {
+        InterpreterConfig {
+            filesystem: true,
+            environment: true,
+            stdin: true,
+            clock: true,
+            stdlib: Stdlib::default(),
+            limits: Limits::default(),
+        }
+    }
+}
+
+/// Represents the limits on the execution of the scripts.
+///
+/// Exceeding the call depth raises a "Stack overflow" error that scripts can catch. The
+/// instruction budget and the timeout apply to every run of the backend separately; exceeding
+/// them stops the run with an error that can not be caught. The same goes for the allocation cap,
+/// which is checked after the unreachable objects have been [`collected`](crate::gc).
+/// ```
+/// use std::time::Duration;
+///
+/// use rocks_lang::rocks;
+/// use rocks_lang::config::{InterpreterConfig, Limits};
+///
+/// let mut output = Vec::new();
+/// let config = InterpreterConfig {
+///     limits: Limits { timeout: Some(Duration::from_millis(10)), ..Limits::default() },
+///     ..InterpreterConfig::default()
+/// };
+/// let mut rocks = rocks::new(&mut output).with_config(config).with_reporter(|_: &_| {});
+///
+/// let diagnostics = rocks.run("while (true) {}".to_string()).unwrap_err();
+/// assert_eq!(diagnostics[0].to_string(), "[line 1:1] Error at 'while': Execution timed out after 10ms");
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Limits {
+    /// The maximum number of nested calls.
+    pub call_depth: usize,
+    /// The maximum number of statements and expressions (or instructions on the
+    /// [`vm`](crate::vm)) that a run can evaluate, unlimited if `None`.
+    pub instructions: Option<u64>,
+    /// The maximum time that a run can take, unlimited if `None`.
+    pub timeout: Option<Duration>,
+    /// The maximum number of heap objects (environments, closures, instances, classes, lists and
+    /// maps) that can be alive at the same time, unlimited if `None`.
+    pub allocations: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits { call_depth: 1024, instructions: None, timeout: None, allocations: None }
+    }
+}
+
+/// Measures a run against the instruction budget and the timeout of the [`Limits`].
+#[derive(Debug)]
+pub struct Meter {
+    limits: Limits,
+    instructions: u64,
+    start: Instant,
+    /// The error message of the exceeded limit, it stays set until the meter is restarted.
+    exceeded: Option<String>,
+}
+
+impl Meter {
+    /// The number of instructions between two checks of the clock.
+    const CLOCK_INTERVAL: u64 = 64;
+
+    pub fn new(limits: Limits) -> Self {
+        Meter { limits, instructions: 0, start: Instant::now(), exceeded: None }
+    }
+
+    /// Restarts the meter at the beginning of a run.
+    pub fn restart(&mut self) {
+        self.instructions = 0;
+        self.start = Instant::now();
+        self.exceeded = None;
+    }
+
+    /// Counts an instruction and returns an error message if a limit is exceeded.
+    /// Once a limit is exceeded, every following instruction fails until the meter is restarted.
+    pub fn tick(&mut self) -> Result<(), String> {
+        self.instructions += 1;
+
+        if self.exceeded.is_none() {
+            if let Some(budget) = self.limits.instructions {
+                if self.instructions > budget {
+                    self.exceeded = Some(format!("Instruction budget of {budget} exceeded"));
+                }
+            }
+        }
+
+        if self.exceeded.is_none() && self.instructions.is_multiple_of(Self::CLOCK_INTERVAL) {
+            if let Some(timeout) = self.limits.timeout {
+                if self.start.elapsed() > timeout {
+                    self.exceeded = Some(format!("Execution timed out after {timeout:?}"));
+                }
+            }
+        }
+
+        match &self.exceeded {
+            Some(message) => Err(message.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns if a limit was exceeded during the current run.
+    pub fn is_exceeded(&self) -> bool {
+        self.exceeded.is_some()
+    }
+}
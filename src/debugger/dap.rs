@@ -0,0 +1,697 @@
+//! A [Debug Adapter Protocol](https://microsoft.github.io/debug-adapter-protocol/) server that
+//! lets editors debug scripts.
+//!
+//! The server is started with `rocks dap` and talks to the editor over the standard input and
+//! output. It supports launching a script, line breakpoints, stepping into, over and out of
+//! functions, inspecting the call stack with the local and global variables of every frame, and
+//! evaluating expressions in a paused frame. The output of the script is sent to the editor as
+//! output events, and the script can not read from the standard input since it carries the
+//! protocol.
+//!
+//! The launch request takes the path of the script as `program`, the arguments that `os.args()`
+//! returns as `args` and whether to pause before the first statement as `stopOnEntry`. For
+//! example, with [nvim-dap](https://github.com/mfussenegger/nvim-dap):
+//! ```text
+//! dap.adapters.rocks = { type = 'executable', command = 'rocks', args = { 'dap' } }
+//! dap.configurations.rocks = {
+//!   { type = 'rocks', request = 'launch', name = 'Launch file', program = '${file}' },
+//! }
+//! ```
+//!
+//! Breakpoints can only be set in the launched script. Requests are read on their own thread,
+//! so a running script can still be paused or terminated.
+
+use std::cell::RefCell;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::mem;
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+
+use serde_json::{json, Value};
+
+use crate::config::InterpreterConfig;
+use crate::debugger::{locals, Pause, Stepper};
+use crate::environment::Environment;
+use crate::error::Diagnostic;
+use crate::interpreter::{Globals, Hook, Interpreter};
+use crate::literal::Literal;
+use crate::module::file_name;
+use crate::object::Object;
+use crate::rocks;
+use crate::stdlib::Stdlib;
+use crate::stmt::Stmt;
+
+/// The id of the only thread, scripts are single threaded.
+const THREAD: u64 = 1;
+
+/// Reads a request of the protocol, which is a JSON body that is preceded by a `Content-Length`
+/// header. Returns `None` if the input is closed or the request is malformed.
+fn read<R: BufRead>(input: &mut R) -> Option<Value> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        match input.read_line(&mut header) {
+            Ok(0) | Err(_) => return None,
+            Ok(_) => (),
+        }
+
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let mut body = vec![0; length.unwrap_or_default()];
+    input.read_exact(&mut body).ok().and_then(|_| serde_json::from_slice(&body).ok())
+}
+
+/// Reads the requests from the input and sends them to the session until the client
+/// disconnects or closes the input.
+fn forward<R: BufRead>(mut input: R, requests: Sender<Value>) {
+    while let Some(request) = read(&mut input) {
+        let disconnect = command(&request) == "disconnect";
+        if requests.send(request).is_err() || disconnect {
+            return;
+        }
+    }
+}
+
+/// Receives the requests of the client and writes the messages of the protocol.
+struct Connection<W: Write> {
+    /// The requests that were read from the input, in the order that they were sent.
+    requests: Receiver<Value>,
+    output: W,
+    /// The sequence number of the next message that is sent.
+    seq: u64,
+    /// If true, the client disconnected and no more requests will be read.
+    closed: bool,
+}
+
+impl<W: Write> Connection<W> {
+    /// Waits for the next request, or returns `None` if the client closed the connection.
+    fn read(&mut self) -> Option<Value> {
+        if self.closed {
+            return None;
+        }
+
+        let request = self.requests.recv().ok();
+        self.closed = request.is_none();
+        request
+    }
+
+    /// Returns the next request if one has already arrived.
+    fn poll(&mut self) -> Option<Value> {
+        if self.closed {
+            return None;
+        }
+
+        match self.requests.try_recv() {
+            Ok(request) => Some(request),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.closed = true;
+                None
+            },
+        }
+    }
+
+    fn send(&mut self, mut message: Value) {
+        message["seq"] = json!(self.seq);
+        self.seq += 1;
+
+        let body = message.to_string();
+        write!(self.output, "Content-Length: {}\r\n\r\n{body}", body.len()).expect("writer to not fail on write");
+        self.output.flush().expect("writer to not fail on flush");
+    }
+
+    fn respond(&mut self, request: &Value, body: Value) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": true,
+            "body": body,
+        }));
+    }
+
+    fn fail(&mut self, request: &Value, message: &str) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": false,
+            "message": message,
+        }));
+    }
+
+    fn event(&mut self, event: &str, body: Value) {
+        self.send(json!({ "type": "event", "event": event, "body": body }));
+    }
+}
+
+/// Represents the script that the client asked to run.
+struct Launch {
+    program: PathBuf,
+    args: Vec<String>,
+    stop_on_entry: bool,
+}
+
+/// Serves a debugging session over the given input and output until the client disconnects.
+/// ```
+/// use rocks_lang::debugger::dap;
+///
+/// let request = r#"{"seq": 1, "type": "request", "command": "initialize", "arguments": {}}"#;
+/// let input = format!("Content-Length: {}\r\n\r\n{request}", request.len());
+///
+/// let mut output = Vec::new();
+/// dap::serve(input.as_bytes(), &mut output);
+///
+/// let output = String::from_utf8(output).unwrap();
+/// assert!(output.contains(r#""command":"initialize""#));
+/// assert!(output.contains(r#""supportsConfigurationDoneRequest":true"#));
+/// ```
+pub fn serve<R: BufRead + Send, W: Write>(input: R, output: W) {
+    let (sender, requests) = mpsc::channel();
+
+    thread::scope(|scope| {
+        scope.spawn(move || forward(input, sender));
+        session(Connection { requests, output, seq: 1, closed: false });
+    });
+}
+
+/// Runs the session until the client disconnects.
+fn session<W: Write>(connection: Connection<W>) {
+    let connection = Rc::new(RefCell::new(connection));
+    let mut launch = None;
+    let mut breakpoints = vec![];
+
+    // Configuration, the client sends the breakpoints before the script runs.
+    loop {
+        let Some(request) = connection.borrow_mut().read() else { return };
+        let mut connection = connection.borrow_mut();
+
+        match command(&request) {
+            "initialize" => {
+                connection.respond(&request, json!({
+                    "supportsConfigurationDoneRequest": true,
+                    "supportsEvaluateForHovers": true,
+                    "supportsTerminateRequest": true,
+                }));
+            },
+            "launch" => match launch_arguments(&request) {
+                Ok(arguments) => {
+                    launch = Some(arguments);
+                    connection.respond(&request, json!({}));
+                    // Breakpoints can only be verified once the launched script is known, so the
+                    // client is asked for them after the launch.
+                    connection.event("initialized", json!({}));
+                },
+                Err(message) => connection.fail(&request, &message),
+            },
+            "setBreakpoints" => {
+                let (path, lines) = breakpoint_arguments(&request);
+                let program = launch.as_ref().is_some_and(|launch| launch.program == path);
+                connection.respond(&request, json!({ "breakpoints": verified(&lines, program) }));
+
+                if program {
+                    breakpoints = lines;
+                }
+            },
+            "configurationDone" => {
+                connection.respond(&request, json!({}));
+                break;
+            },
+            "terminate" => {
+                // The script is not run, the session ends like after the script terminated.
+                connection.respond(&request, json!({}));
+                launch = None;
+                break;
+            },
+            _ => idle(&mut connection, &request),
+        }
+    }
+
+    if let Some(launch) = launch {
+        let code = run(&connection, launch, breakpoints);
+
+        let mut connection = connection.borrow_mut();
+        connection.event("exited", json!({ "exitCode": code }));
+        connection.event("terminated", json!({}));
+    } else {
+        connection.borrow_mut().event("terminated", json!({}));
+    }
+
+    // The client disconnects once it has seen the script terminate.
+    let mut connection = connection.borrow_mut();
+    while let Some(request) = connection.read() {
+        idle(&mut connection, &request);
+    }
+}
+
+/// Runs the launched script and returns its exit code.
+fn run<W: Write>(connection: &Rc<RefCell<Connection<W>>>, launch: Launch, lines: Vec<usize>) -> i32 {
+    let mut stepper = Stepper::new(launch.stop_on_entry);
+    stepper.breakpoints.extend(lines.into_iter().map(|line| (Some(launch.program.clone()), line)));
+
+    let adapter = Adapter {
+        connection: Rc::clone(connection),
+        program: launch.program.clone(),
+        stepper,
+        entry: launch.stop_on_entry,
+        interrupted: false,
+        line: 0,
+        handles: vec![],
+    };
+
+    // The standard input carries the protocol, so scripts can not read from it.
+    let config = InterpreterConfig {
+        stdin: false,
+        stdlib: Stdlib::default().with_args(launch.args),
+        ..InterpreterConfig::default()
+    };
+
+    let mut output = Output { connection: Rc::clone(connection), buffer: vec![] };
+    let reporter = {
+        let connection = Rc::clone(connection);
+        move |diagnostic: &Diagnostic| {
+            connection.borrow_mut().event("output", json!({ "category": "stderr", "output": format!("{diagnostic}\n") }));
+        }
+    };
+
+    let mut rocks = rocks::new(&mut output)
+        .with_config(config)
+        .with_hook(adapter)
+        .expect("interpreter to accept hooks")
+        .with_reporter(reporter);
+    let result = rocks.run_file(launch.program.to_string_lossy().to_string());
+    drop(rocks);
+    output.flush().expect("writer to not fail on flush");
+
+    match result {
+        Ok(termination) => termination.code(),
+        Err(_) => 65,
+    }
+}
+
+/// Answers the requests that do not need a script.
+fn idle<W: Write>(connection: &mut Connection<W>, request: &Value) {
+    match command(request) {
+        "threads" => connection.respond(request, threads()),
+        "disconnect" => {
+            connection.respond(request, json!({}));
+            connection.closed = true;
+        },
+        // The script has already terminated.
+        "terminate" => connection.respond(request, json!({})),
+        "setBreakpoints" => {
+            let (_, lines) = breakpoint_arguments(request);
+            connection.respond(request, json!({ "breakpoints": verified(&lines, false) }));
+        },
+        command => connection.fail(request, &format!("Cannot handle '{command}' while the script is not paused")),
+    }
+}
+
+fn command(request: &Value) -> &str {
+    request["command"].as_str().unwrap_or_default()
+}
+
+fn threads() -> Value {
+    json!({ "threads": [{ "id": THREAD, "name": "main" }] })
+}
+
+fn launch_arguments(request: &Value) -> Result<Launch, String> {
+    let arguments = &request["arguments"];
+
+    let Some(program) = arguments["program"].as_str() else {
+        return Err("Expected the path of the script as 'program'".to_string());
+    };
+
+    let program = fs::canonicalize(program).map_err(|err| format!("Could not read '{program}': {err}"))?;
+    let args = arguments["args"]
+        .as_array()
+        .map(|args| args.iter().filter_map(|arg| arg.as_str()).map(str::to_string).collect())
+        .unwrap_or_default();
+    let stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or_default();
+
+    Ok(Launch { program, args, stop_on_entry })
+}
+
+/// Returns the path of the source and the lines of the breakpoints of a `setBreakpoints`
+/// request.
+fn breakpoint_arguments(request: &Value) -> (PathBuf, Vec<usize>) {
+    let arguments = &request["arguments"];
+    let path = arguments["source"]["path"].as_str().unwrap_or_default();
+    let path = fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+
+    let lines = arguments["breakpoints"]
+        .as_array()
+        .map(|breakpoints| {
+            breakpoints.iter().filter_map(|breakpoint| breakpoint["line"].as_u64()).map(|line| line as usize).collect()
+        })
+        .unwrap_or_default();
+
+    (path, lines)
+}
+
+fn verified(lines: &[usize], verified: bool) -> Vec<Value> {
+    lines.iter().map(|line| {
+        match verified {
+            true => json!({ "verified": true, "line": line }),
+            false => json!({ "verified": false, "line": line, "message": "Breakpoints can only be set in the launched script" }),
+        }
+    }).collect()
+}
+
+/// Sends the output of the script to the client as output events, one line at a time.
+struct Output<W: Write> {
+    connection: Rc<RefCell<Connection<W>>>,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+
+        if let Some(end) = self.buffer.iter().rposition(|byte| *byte == b'\n') {
+            let lines: Vec<u8> = self.buffer.drain(..=end).collect();
+            let output = String::from_utf8_lossy(&lines);
+            self.connection.borrow_mut().event("output", json!({ "category": "stdout", "output": output }));
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if !self.buffer.is_empty() {
+            let output = String::from_utf8_lossy(&self.buffer).to_string();
+            self.buffer.clear();
+            self.connection.borrow_mut().event("output", json!({ "category": "stdout", "output": output }));
+        }
+
+        Ok(())
+    }
+}
+
+/// Represents something whose variables the client can expand.
+enum Handle {
+    /// The variables of a scope.
+    Scope(Vec<(String, Object)>),
+    /// The elements of a list, the entries of a map or the fields of an instance.
+    Object(Object),
+}
+
+/// The hook that pauses the script and answers the requests of the client while it is paused.
+struct Adapter<W: Write> {
+    connection: Rc<RefCell<Connection<W>>>,
+    program: PathBuf,
+    stepper: Stepper,
+    /// If true, the script has not paused yet and pausing before the first statement was
+    /// requested.
+    entry: bool,
+    /// If true, the client asked to pause the running script.
+    interrupted: bool,
+    /// The line of the statement that the script is paused at.
+    line: usize,
+    /// The variables that the client can expand during the current pause, the reference of a
+    /// handle is its index plus one.
+    handles: Vec<Handle>,
+}
+
+impl<W: Write> Adapter<W> {
+    fn respond(&self, request: &Value, body: Value) {
+        self.connection.borrow_mut().respond(request, body);
+    }
+
+    /// Answers a request that stops the script. Disconnecting also closes the connection.
+    fn stop(&self, request: &Value) -> ControlFlow<()> {
+        let mut connection = self.connection.borrow_mut();
+        connection.respond(request, json!({}));
+        connection.closed = command(request) == "disconnect";
+        ControlFlow::Break(())
+    }
+
+    fn set_breakpoints(&mut self, request: &Value) {
+        let (path, lines) = breakpoint_arguments(request);
+        let program = path == self.program;
+        if program {
+            self.stepper.breakpoints = lines.iter().map(|line| (Some(self.program.clone()), *line)).collect();
+        }
+
+        self.respond(request, json!({ "breakpoints": verified(&lines, program) }));
+    }
+
+    /// Answers the requests that arrived while the script was running.
+    /// Breaks if the client asked to stop the script or closed the connection.
+    fn poll(&mut self) -> ControlFlow<()> {
+        loop {
+            let request = self.connection.borrow_mut().poll();
+            let Some(request) = request else { break };
+
+            match command(&request) {
+                "pause" => {
+                    self.respond(&request, json!({}));
+                    self.interrupted = true;
+                },
+                "disconnect" | "terminate" => return self.stop(&request),
+                "threads" => self.respond(&request, threads()),
+                "setBreakpoints" => self.set_breakpoints(&request),
+                command => {
+                    let message = format!("Cannot handle '{command}' while the script is running");
+                    self.connection.borrow_mut().fail(&request, &message);
+                },
+            }
+        }
+
+        match self.connection.borrow().closed {
+            true => ControlFlow::Break(()),
+            false => ControlFlow::Continue(()),
+        }
+    }
+
+    /// Reads and answers requests until one of them resumes or stops the script.
+    fn pause(&mut self, interpreter: &mut Interpreter, reason: &str) -> ControlFlow<()> {
+        self.handles.clear();
+        self.connection.borrow_mut().event("stopped", json!({
+            "reason": reason,
+            "threadId": THREAD,
+            "allThreadsStopped": true,
+        }));
+
+        loop {
+            let Some(request) = self.connection.borrow_mut().read() else {
+                return ControlFlow::Break(());
+            };
+
+            match command(&request) {
+                "continue" => self.stepper.resume(),
+                "next" => self.stepper.step_over(interpreter.depth()),
+                "stepIn" => self.stepper.step_in(),
+                "stepOut" => self.stepper.step_out(interpreter.depth()),
+                "disconnect" | "terminate" => return self.stop(&request),
+                "threads" => {
+                    self.respond(&request, threads());
+                    continue;
+                },
+                "stackTrace" => {
+                    let frames = self.stack_trace(interpreter);
+                    self.respond(&request, json!({ "stackFrames": frames, "totalFrames": frames.len() }));
+                    continue;
+                },
+                "scopes" => {
+                    let scopes = self.scopes(interpreter, &request["arguments"]);
+                    self.respond(&request, json!({ "scopes": scopes }));
+                    continue;
+                },
+                "variables" => {
+                    let reference = request["arguments"]["variablesReference"].as_u64().unwrap_or_default();
+                    let variables = self.variables(reference as usize);
+                    self.respond(&request, json!({ "variables": variables }));
+                    continue;
+                },
+                "evaluate" => {
+                    self.evaluate(interpreter, &request);
+                    continue;
+                },
+                "setBreakpoints" => {
+                    self.set_breakpoints(&request);
+                    continue;
+                },
+                command => {
+                    self.connection.borrow_mut().fail(&request, &format!("Unsupported request '{command}'"));
+                    continue;
+                },
+            }
+
+            // Only the requests that resume the script get here.
+            self.respond(&request, json!({ "allThreadsContinued": true }));
+            return ControlFlow::Continue(());
+        }
+    }
+
+    /// Returns the frames of the call stack, the innermost one first.
+    /// The id of a frame is its distance from the innermost frame.
+    fn stack_trace(&self, interpreter: &Interpreter) -> Vec<Value> {
+        let frames = interpreter.frames();
+
+        (0..=frames.len()).map(|id| {
+            let function = match id < frames.len() {
+                true => frames[frames.len() - 1 - id].name.clone(),
+                false => "<script>".to_string(),
+            };
+
+            // Callers are paused at the call of the frame above them.
+            let (file, line) = match id {
+                0 => (interpreter.file(), self.line),
+                id => {
+                    let frame = &frames[frames.len() - id];
+                    (frame.file.as_deref(), frame.call.location.line + 1)
+                },
+            };
+
+            let path = file.unwrap_or(&self.program);
+            let source = json!({ "name": file_name(path), "path": path });
+
+            json!({ "id": id, "name": function, "source": source, "line": line, "column": 1 })
+        }).collect()
+    }
+
+    /// Returns the innermost local scope of the frame with the given id.
+    fn environment(&self, interpreter: &Interpreter, frame: usize) -> Option<Rc<RefCell<Environment>>> {
+        let frames = interpreter.frames();
+
+        match frame {
+            0 => interpreter.environment().cloned(),
+            id if id <= frames.len() => frames[frames.len() - id].environment.clone(),
+            _ => None,
+        }
+    }
+
+    /// Returns the globals of the file that the frame with the given id is in.
+    fn globals(&self, interpreter: &Interpreter, frame: usize) -> Globals {
+        let frames = interpreter.frames();
+
+        match frame {
+            id if id > 0 && id <= frames.len() => Rc::clone(&frames[frames.len() - id].globals),
+            _ => Rc::clone(interpreter.globals()),
+        }
+    }
+
+    fn scopes(&mut self, interpreter: &Interpreter, arguments: &Value) -> Vec<Value> {
+        let frame = arguments["frameId"].as_u64().unwrap_or_default() as usize;
+
+        let locals = locals(self.environment(interpreter, frame));
+        let mut globals: Vec<(String, Object)> = self.globals(interpreter, frame)
+            .borrow()
+            .iter()
+            .filter(|(_, value)| !matches!(value, Object::NativeFunction(_)))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        globals.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        vec![
+            json!({ "name": "Locals", "variablesReference": self.handle(Handle::Scope(locals)), "expensive": false }),
+            json!({ "name": "Globals", "variablesReference": self.handle(Handle::Scope(globals)), "expensive": false }),
+        ]
+    }
+
+    fn handle(&mut self, handle: Handle) -> usize {
+        self.handles.push(handle);
+        self.handles.len()
+    }
+
+    /// Returns the variables of the handle with the given reference.
+    fn variables(&mut self, reference: usize) -> Vec<Value> {
+        let variables: Vec<(String, Object)> = match reference.checked_sub(1).and_then(|index| self.handles.get(index)) {
+            Some(Handle::Scope(variables)) => variables.clone(),
+            Some(Handle::Object(Object::List(list))) => list.borrow()
+                .iter()
+                .enumerate()
+                .map(|(index, element)| (index.to_string(), element.clone()))
+                .collect(),
+            Some(Handle::Object(Object::Map(map))) => map.borrow()
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+            Some(Handle::Object(Object::Instance(instance))) => {
+                let mut fields: Vec<(String, Object)> = instance.borrow()
+                    .fields()
+                    .map(|(name, value)| (name.to_string(), value.clone()))
+                    .collect();
+                fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+                fields
+            },
+            _ => vec![],
+        };
+
+        variables.into_iter().map(|(name, value)| {
+            let (value, reference) = self.value(value);
+            json!({ "name": name, "value": value, "variablesReference": reference })
+        }).collect()
+    }
+
+    /// Returns how the given value is shown and the reference to expand it (or zero).
+    fn value(&mut self, value: Object) -> (String, usize) {
+        let shown = match &value {
+            Object::Literal(Literal::String(string)) => format!("{string:?}"),
+            value => value.to_string(),
+        };
+
+        match value {
+            Object::List(_) | Object::Map(_) | Object::Instance(_) => (shown, self.handle(Handle::Object(value))),
+            _ => (shown, 0),
+        }
+    }
+
+    fn evaluate(&mut self, interpreter: &mut Interpreter, request: &Value) {
+        let arguments = &request["arguments"];
+        let expression = arguments["expression"].as_str().unwrap_or_default();
+        let frame = arguments["frameId"].as_u64().unwrap_or_default() as usize;
+        let environment = self.environment(interpreter, frame);
+        let globals = self.globals(interpreter, frame);
+
+        match interpreter.evaluate_source(expression, environment, globals) {
+            Ok(value) => {
+                let (result, reference) = self.value(value);
+                self.respond(request, json!({ "result": result, "variablesReference": reference }));
+            },
+            Err(diagnostics) => {
+                let message = diagnostics.iter().map(Diagnostic::to_string).collect::<Vec<String>>().join("\n");
+                self.connection.borrow_mut().fail(request, &message);
+            },
+        }
+    }
+}
+
+impl<W: Write> Hook for Adapter<W> {
+    fn statement(&mut self, interpreter: &mut Interpreter, stmt: &Stmt) -> ControlFlow<()> {
+        let Some(token) = stmt.token() else {
+            return ControlFlow::Continue(());
+        };
+
+        if self.poll().is_break() {
+            return ControlFlow::Break(());
+        }
+
+        let line = token.location.line + 1;
+        let reason = match self.stepper.check(interpreter.file(), line, interpreter.depth()) {
+            _ if mem::take(&mut self.interrupted) => "pause",
+            None => return ControlFlow::Continue(()),
+            Some(_) if self.entry => "entry",
+            Some(Pause::Breakpoint) => "breakpoint",
+            Some(Pause::Step) => "step",
+        };
+
+        self.entry = false;
+        self.line = line;
+        self.pause(interpreter, reason)
+    }
+}
+
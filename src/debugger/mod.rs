@@ -0,0 +1,313 @@
+//! An interactive debugger for scripts that run on the [`Interpreter`].
+//!
+//! The [`Debugger`] is a [`Hook`] that pauses the script before its first statement, at
+//! breakpoints and after every step. While the script is paused, commands are read from the
+//! input of the debugger one line at a time:
+//! - `break <line>` (`b`) - Pauses whenever the given line of the current file is reached.
+//! - `delete <line>` (`d`) - Removes the breakpoint from the given line of the current file.
+//! - `continue` (`c`) - Runs until the next breakpoint.
+//! - `step` (`s`) - Runs until the next statement, stepping into calls.
+//! - `next` (`n`) - Runs until the next statement, stepping over calls.
+//! - `out` (`o`) - Runs until the current function returns.
+//! - `locals` (`l`) - Prints the local variables of every enclosing scope.
+//! - `print <expression>` (`p`) - Evaluates the expression in the paused scope and prints it.
+//! - `help` (`h`) - Prints the list of commands.
+//! - `quit` (`q`) - Stops the script.
+//!
+//! Breakpoints are set by line number in the file that the script is paused in, which is an
+//! imported module after stepping into it.
+//!
+//! Editors can debug scripts through the [`dap`] server instead.
+//! ```
+//! use rocks_lang::rocks;
+//! use rocks_lang::debugger::Debugger;
+//!
+//! let source = "var a = 1;\nvar b = a + 1;\nprint b;";
+//! let commands = "break 3\ncontinue\nprint a + b\ncontinue\n";
+//!
+//! let mut output = Vec::new();
+//! let mut log = Vec::new();
+//! let mut rocks = rocks::new(&mut output).with_hook(Debugger::new(source, commands.as_bytes(), &mut log)).unwrap();
+//!
+//! rocks.run(source.to_string()).unwrap();
+//! drop(rocks);
+//!
+//! let log = String::from_utf8(log).unwrap();
+//! assert!(log.contains("Paused at line 3: print b;"));
+//! assert!(log.contains("3\n"));
+//! ```
+
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Display;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::environment::Environment;
+use crate::interpreter::{Hook, Interpreter};
+use crate::module::file_name;
+use crate::object::Object;
+use crate::stmt::Stmt;
+
+pub mod dap;
+
+const HELP: &str = "\
+break <line>   (b) Pause whenever the line of the current file is reached
+delete <line>  (d) Remove the breakpoint from the line of the current file
+continue       (c) Run until the next breakpoint
+step           (s) Run until the next statement, stepping into calls
+next           (n) Run until the next statement, stepping over calls
+out            (o) Run until the current function returns
+locals         (l) Print the local variables
+print <expr>   (p) Evaluate the expression and print its value
+help           (h) Print this list
+quit           (q) Stop the script";
+
+/// Decides when the debugger pauses next, besides breakpoints.
+enum Mode {
+    /// Only pauses at breakpoints.
+    Continue,
+    /// Pauses at the next statement that runs at most the given number of calls deep.
+    Step(usize),
+}
+
+/// Represents the reason of a pause.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Pause {
+    Step,
+    Breakpoint,
+}
+
+/// Decides where a script pauses based on the breakpoints and the last command of the user.
+/// It is shared by the debuggers of every interface.
+struct Stepper {
+    /// The lines that have a breakpoint, along with the path of their file. Scripts that do not
+    /// run from a file have no path.
+    breakpoints: BTreeSet<(Option<PathBuf>, usize)>,
+    mode: Mode,
+    /// The file and the line of the previous statement. Breakpoints only pause when their line
+    /// is entered, not before every statement on it.
+    file: Option<PathBuf>,
+    line: usize,
+}
+
+impl Stepper {
+    /// Creates a stepper that pauses before the first statement if `entry` is true.
+    fn new(entry: bool) -> Self {
+        let mode = match entry {
+            true => Mode::Step(usize::MAX),
+            false => Mode::Continue,
+        };
+
+        Stepper { breakpoints: BTreeSet::new(), mode, file: None, line: 0 }
+    }
+
+    /// Returns the reason to pause before a statement on the given line of the given file that
+    /// runs the given number of calls deep (if the script has to pause).
+    fn check(&mut self, file: Option<&Path>, line: usize, depth: usize) -> Option<Pause> {
+        let entered = line != self.line || file != self.file.as_deref();
+
+        if entered {
+            self.file = file.map(Path::to_path_buf);
+            self.line = line;
+
+            if self.breakpoints.contains(&(self.file.clone(), line)) {
+                return Some(Pause::Breakpoint);
+            }
+        }
+
+        match self.mode {
+            Mode::Step(max) if depth <= max => Some(Pause::Step),
+            _ => None,
+        }
+    }
+
+    /// Runs until the next breakpoint.
+    fn resume(&mut self) {
+        self.mode = Mode::Continue;
+    }
+
+    /// Runs until the next statement.
+    fn step_in(&mut self) {
+        self.mode = Mode::Step(usize::MAX);
+    }
+
+    /// Runs until the next statement that is not in a function called from the given depth.
+    fn step_over(&mut self, depth: usize) {
+        self.mode = Mode::Step(depth);
+    }
+
+    /// Runs until the function at the given depth returns.
+    fn step_out(&mut self, depth: usize) {
+        match depth.checked_sub(1) {
+            Some(depth) => self.mode = Mode::Step(depth),
+            None => self.resume(),
+        }
+    }
+}
+
+/// Returns the local variables from the innermost scope outwards.
+/// Variables that are shadowed by an inner scope are skipped.
+fn locals(environment: Option<Rc<RefCell<Environment>>>) -> Vec<(String, Object)> {
+    let mut variables: Vec<(String, Object)> = vec![];
+    let mut environment = environment;
+
+    while let Some(scope) = environment {
+        let scope = scope.borrow();
+
+        for (name, value) in scope.variables() {
+            if !variables.iter().any(|(variable, _)| variable == name) {
+                variables.push((name.to_string(), value.clone()));
+            }
+        }
+
+        environment = scope.enclosing.clone();
+    }
+
+    variables
+}
+
+/// Represents an interactive debugger that reads commands from `R` and writes to `W`.
+pub struct Debugger<R: BufRead, W: Write> {
+    input: R,
+    output: W,
+    /// The lines of the script, used to show where the script is paused.
+    lines: Vec<String>,
+    /// The path of the script, which is known once it pauses for the first time. Scripts that do
+    /// not run from a file have no path.
+    script: Option<Option<PathBuf>>,
+    /// The lines of the imported modules that the script paused in.
+    modules: HashMap<PathBuf, Vec<String>>,
+    stepper: Stepper,
+}
+
+impl<R: BufRead, W: Write> Debugger<R, W> {
+    /// Creates a debugger for the given script that pauses before its first statement.
+    pub fn new(source: &str, input: R, output: W) -> Self {
+        Debugger {
+            input,
+            output,
+            lines: source.lines().map(str::to_string).collect(),
+            script: None,
+            modules: HashMap::new(),
+            stepper: Stepper::new(true),
+        }
+    }
+
+    fn print(&mut self, message: impl Display) {
+        writeln!(self.output, "{message}").expect("writer to not fail on write");
+    }
+
+    /// Returns the given line of the given file and how the line is referred to.
+    /// Lines of the script are referred to by their number, lines of modules also by the name of
+    /// their file.
+    fn source(&mut self, file: &Option<PathBuf>, line: usize) -> (String, Option<&String>) {
+        // The first pause is always in the script, modules only run after it is entered.
+        if self.script.get_or_insert_with(|| file.clone()) == file {
+            return (format!("line {line}"), self.lines.get(line - 1));
+        }
+
+        let Some(path) = file else {
+            return (format!("line {line}"), None);
+        };
+
+        let lines = self.modules.entry(path.clone()).or_insert_with(|| {
+            fs::read_to_string(path).unwrap_or_default().lines().map(str::to_string).collect()
+        });
+
+        (format!("line {line} of {}", file_name(path)), lines.get(line - 1))
+    }
+
+    /// Reads and runs commands until one of them resumes or stops the script.
+    fn pause(&mut self, interpreter: &mut Interpreter, line: usize) -> ControlFlow<()> {
+        let file = interpreter.file().map(Path::to_path_buf);
+        let (location, source) = self.source(&file, line);
+        let source = source.map(|source| source.trim().to_string()).unwrap_or_default();
+        self.print(format!("Paused at {location}: {source}"));
+
+        loop {
+            write!(self.output, "(debug) ").expect("writer to not fail on write");
+            self.output.flush().expect("writer to not fail on flush");
+
+            let mut command = String::new();
+            match self.input.read_line(&mut command) {
+                // There will be no more commands, so there is no way to resume the script.
+                Ok(0) | Err(_) => return ControlFlow::Break(()),
+                Ok(_) => (),
+            }
+
+            let command = command.trim();
+            let (name, argument) = command.split_once(' ').unwrap_or((command, ""));
+            let argument = argument.trim();
+
+            match name {
+                "" => (),
+                "b" | "break" => match argument.parse::<usize>() {
+                    Ok(line) if line > 0 => {
+                        self.stepper.breakpoints.insert((file.clone(), line));
+                        self.print(format!("Breakpoint set at line {line}"));
+                    },
+                    _ => self.print("Expected a line number"),
+                },
+                "d" | "delete" => match argument.parse::<usize>() {
+                    Ok(line) if self.stepper.breakpoints.remove(&(file.clone(), line)) => {
+                        self.print(format!("Breakpoint removed from line {line}"));
+                    },
+                    Ok(line) => self.print(format!("No breakpoint at line {line}")),
+                    Err(_) => self.print("Expected a line number"),
+                },
+                "c" | "continue" => {
+                    self.stepper.resume();
+                    return ControlFlow::Continue(());
+                },
+                "s" | "step" => {
+                    self.stepper.step_in();
+                    return ControlFlow::Continue(());
+                },
+                "n" | "next" => {
+                    self.stepper.step_over(interpreter.depth());
+                    return ControlFlow::Continue(());
+                },
+                "o" | "out" => {
+                    self.stepper.step_out(interpreter.depth());
+                    return ControlFlow::Continue(());
+                },
+                "l" | "locals" => {
+                    let variables = locals(interpreter.environment().cloned());
+                    if variables.is_empty() {
+                        self.print("No local variables");
+                    }
+
+                    for (name, value) in variables {
+                        self.print(format!("{name} = {value}"));
+                    }
+                },
+                "p" | "print" => match interpreter.evaluate_source(argument, interpreter.environment().cloned(), Rc::clone(interpreter.globals())) {
+                    Ok(value) => self.print(value),
+                    Err(diagnostics) => diagnostics.iter().for_each(|diagnostic| self.print(diagnostic)),
+                },
+                "h" | "help" => self.print(HELP),
+                "q" | "quit" => return ControlFlow::Break(()),
+                _ => self.print(format!("Unknown command '{name}', type 'help' for a list of commands")),
+            }
+        }
+    }
+}
+
+impl<R: BufRead, W: Write> Hook for Debugger<R, W> {
+    fn statement(&mut self, interpreter: &mut Interpreter, stmt: &Stmt) -> ControlFlow<()> {
+        let Some(token) = stmt.token() else {
+            return ControlFlow::Continue(());
+        };
+
+        let line = token.location.line + 1;
+
+        match self.stepper.check(interpreter.file(), line, interpreter.depth()) {
+            Some(_) => self.pause(interpreter, line),
+            None => ControlFlow::Continue(()),
+        }
+    }
+}
@@ -1,14 +1,16 @@
 use std::fmt::Debug;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::mem;
 use std::rc::Rc;
 
+use crate::gc::{Collect, Trace, Tracer};
 use crate::object::Object;
-use crate::token::Token;
-use crate::error::{RuntimeError, Error};
 
-/// Represents an environment in which variables are stored.
-/// The environment is a hash map of variable names to their values.
+/// Represents a local scope in which variables are stored.
+/// Variables are stored in the order they are declared in and are accessed by their slot, which
+/// is assigned by the [`Resolver`](crate::resolver::Resolver). Their names are only kept for
+/// the [`debugger`](crate::debugger). Global variables are not stored in
+/// environments, the [`Interpreter`](crate::interpreter::Interpreter) looks them up by name.
 /// Each environment has a reference to its enclosing environment.
 /// This is an optional reference to implement lexical scoping and closures.
 #[derive(Clone)]
@@ -16,7 +18,8 @@ pub struct Environment {
     /// Using an Rc and Refcell here allows us to have multiple mutable references
     /// to the same environment.
     pub enclosing: Option<Rc<RefCell<Environment>>>,
-    variables: HashMap<String, Object>,
+    names: Vec<String>,
+    values: Vec<Object>,
 }
 
 impl Environment {
@@ -24,13 +27,22 @@ impl Environment {
     pub fn new(enclosing: Option<Rc<RefCell<Environment>>>) -> Self {
         Environment {
             enclosing,
-            variables: HashMap::new(),
+            names: Vec::new(),
+            values: Vec::new(),
         }
     }
 
-    /// Defines a new variable in the environment with the given name and value.
+    /// Defines a new variable in the environment with the given value.
+    /// The variable is stored in the next free slot.
     pub fn define(&mut self, name: &str, value: Object) {
-        self.variables.insert(name.to_string(), value);
+        self.names.push(name.to_string());
+        self.values.push(value);
+    }
+
+    /// Returns the names and values of the variables in the environment in the order they were
+    /// declared in.
+    pub fn variables(&self) -> impl Iterator<Item = (&str, &Object)> {
+        self.names.iter().map(String::as_str).zip(&self.values)
     }
 
     /// Accesses the ancestor environment at the given distance.
@@ -48,66 +60,25 @@ impl Environment {
         environment
     }
 
-    /// Assigns the given value to the variable with the given name.
-    /// If the variable is not define in this environment but is defined in an enclosing environment,
-    /// it will try to recursively assign the value to the variable in the enclosing environment.
-    /// If the variable is not defined in this environment or any enclosing environment, it will
-    /// throw a runtime error.
-    pub fn assign(&mut self, name: &Token, value: Object) {
-        if self.variables.contains_key(&name.lexeme) {
-            self.variables.insert(name.lexeme.clone(), value);
-            return;
-        }
-
-        if let Some(enclosing) = &mut self.enclosing {
-            enclosing.borrow_mut().assign(name, value);
-            return;
-        }
-
-        RuntimeError {
-            token: name.clone(),
-            message: format!("Undefined variable '{}'", name.lexeme),
-        }.throw();
-    }
-
-    /// Works like [`Environment::assign`] but assigns the value to the variable in the ancestor
-    /// environment at the given distance.
-    pub fn assign_at(&mut self, distance: usize, name: &Token, value: Object) {
+    /// Assigns the given value to the variable in the given slot of the ancestor environment at
+    /// the given distance.
+    pub fn assign_at(&mut self, distance: usize, slot: usize, value: Object) {
         if distance == 0 {
-            return self.assign(name, value);
-        }
-
-        self.ancestor(distance).borrow_mut().assign(name, value);
-    }
-
-    /// Returns the value of the variable with the given name.
-    /// If the variable is not defined in this environment but is defined in an enclosing environment,
-    /// it will try to recursively get the value of the variable in the enclosing environment.
-    /// If the variable is not defined in this environment or any enclosing environment, it will
-    /// throw a runtime error.
-    pub fn get(&self, name: &Token) -> Result<Object, RuntimeError> {
-        if let Some(variable) = self.variables.get(&name.lexeme) {
-            return Ok(variable.clone());
-        }
-
-        if let Some(enclosing) = &self.enclosing {
-            return enclosing.borrow().get(name);
+            self.values[slot] = value;
+            return;
         }
 
-        Err(RuntimeError {
-            token: name.clone(),
-            message: format!("Undefined variable '{}'", name.lexeme)
-        })
+        self.ancestor(distance).borrow_mut().values[slot] = value;
     }
 
-    /// Works like [`Environment::get`] but gets the value of the variable in the ancestor
-    /// environment at the given distance.
-    pub fn get_at(&self, distance: usize, name: &Token) -> Result<Object, RuntimeError> {
+    /// Returns the value of the variable in the given slot of the ancestor environment at the
+    /// given distance.
+    pub fn get_at(&self, distance: usize, slot: usize) -> Object {
         if distance == 0 {
-            return self.get(name);
+            return self.values[slot].clone();
         }
 
-        return self.ancestor(distance).borrow().get(name);
+        return self.ancestor(distance).borrow().values[slot].clone();
     }
 }
 
@@ -121,7 +92,27 @@ impl Debug for Environment {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Environment")
             .field("enclosing", &self.enclosing)
-            .field("variables", &self.variables.keys())
+            .field("values", &self.values.len())
             .finish()
     }
 }
+
+impl Trace for RefCell<Environment> {
+    fn trace(&self, tracer: &mut Tracer) {
+        let Ok(environment) = self.try_borrow() else {
+            return tracer.pin();
+        };
+
+        environment.values.iter().for_each(|value| value.trace(tracer));
+        if let Some(enclosing) = &environment.enclosing {
+            tracer.edge(enclosing);
+        }
+    }
+}
+
+impl Collect for RefCell<Environment> {
+    fn clear(&self) {
+        // The variables are dropped after the borrow ends.
+        let _variables = self.try_borrow_mut().map(|mut environment| mem::take(&mut *environment));
+    }
+}
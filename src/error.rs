@@ -1,134 +1,423 @@
+use std::any::Any;
+use std::fmt::{self, Display};
+use std::io::{self, IsTerminal};
+use std::rc::Rc;
+
+use crate::linter::Rule;
+use crate::literal::Literal;
 use crate::object::Object;
+use crate::render::{self, Format};
+use crate::stdlib::StdValue;
 use crate::token::{Token, Type, Location};
 
-static mut HAD_ERROR: bool = false;
-static mut HAD_RUNTIME_ERROR: bool = false;
+/// Represents the phase of the interpreter that produced a diagnostic.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Phase {
+    /// Reading the source code, before anything is scanned. These diagnostics have no span.
+    Io,
+    Scan,
+    Parse,
+    Resolve,
+    Lint,
+    Runtime,
+}
+
+/// Represents how severe a diagnostic is.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "Error"),
+            Severity::Warning => write!(f, "Warning"),
+        }
+    }
+}
+
+/// Represents a range in the source code.
+/// The start is inclusive and the end is exclusive.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Span {
+    pub start: Location,
+    pub end: Location,
+}
+
+impl Span {
+    /// Creates a new span.
+    pub fn new(start: Location, end: Location) -> Self {
+        Span { start, end }
+    }
+}
+
+impl From<Location> for Span {
+    /// Creates an empty span at the given location.
+    fn from(location: Location) -> Self {
+        Span::new(location, location)
+    }
+}
+
+impl From<&Token> for Span {
+    /// Creates a span covering the source code of the given token.
+    fn from(token: &Token) -> Self {
+        Span::new(token.location, token.end)
+    }
+}
+
+/// Represents a single problem reported by any phase of the interpreter.
+/// Diagnostics are collected and returned to the host instead of being printed directly.
+/// Printing them is the job of a [`Reporter`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    /// The phase that produced the diagnostic.
+    pub phase: Phase,
+    /// The severity of the diagnostic.
+    pub severity: Severity,
+    /// The range of source code that the diagnostic refers to.
+    pub span: Span,
+    /// The human readable message.
+    pub message: String,
+    /// The token that the diagnostic refers to (if any).
+    pub token: Option<Token>,
+    /// A note on how to fix the problem (if any).
+    pub help: Option<String>,
+    /// The calls that were being executed when a runtime error occurred, the innermost one first.
+    /// Empty for every other diagnostic.
+    pub trace: Vec<TraceFrame>,
+}
+
+impl Diagnostic {
+    /// Returns if the diagnostic is an error.
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.phase == Phase::Io {
+            return write!(f, "{}: {}", self.severity, self.message);
+        }
+
+        write!(
+            f,
+            "[line {line}:{column}] {severity}",
+            line = self.span.start.line + 1,
+            column = self.span.start.column + 1,
+            severity = self.severity,
+        )?;
+
+        match &self.token {
+            Some(token) if token.r#type == Type::EOF => write!(f, " at end")?,
+            Some(token) => write!(f, " at '{}'", token.lexeme)?,
+            None => (),
+        };
+
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Represents a call that was being executed when a runtime error occurred.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TraceFrame {
+    /// The name of the function, method or class that was called, or `<script>` for top-level
+    /// code.
+    pub name: String,
+    /// The name of the file that the code is in, if it was run from a file.
+    pub file: Option<String>,
+    /// The location that was being executed in the call, or `None` for native functions.
+    pub location: Option<Location>,
+}
+
+impl Display for TraceFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.file, self.location) {
+            (_, None) => write!(f, "at {} (native)", self.name),
+            (Some(file), Some(location)) => write!(f, "at {} ({file}:{})", self.name, location.line + 1),
+            (None, Some(location)) => write!(f, "at {} (line {})", self.name, location.line + 1),
+        }
+    }
+}
+
+/// Receives diagnostics as they are produced.
+/// This is used to decide how diagnostics are presented to the user.
+pub trait Reporter {
+    /// Reports a single diagnostic.
+    fn report(&mut self, diagnostic: &Diagnostic);
+
+    /// Called with the source code that is about to be run and the name of its file (if any),
+    /// before any of its diagnostics are reported.
+    fn source(&mut self, _file: Option<&str>, _source: &str) {}
+}
+
+/// Reports diagnostics by printing them to the standard error.
+/// By default, diagnostics are [rendered](render::human) with the line of source code that they
+/// refer to, in color if the standard error is a terminal and the `NO_COLOR` environment
+/// variable is not set.
+#[derive(Debug, Clone)]
+pub struct StderrReporter {
+    format: Format,
+    color: bool,
+    file: Option<String>,
+    source: String,
+}
+
+impl StderrReporter {
+    /// Creates a new reporter that prints in the human readable format.
+    pub fn new() -> Self {
+        StderrReporter {
+            format: Format::Human,
+            color: io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+            file: None,
+            source: String::new(),
+        }
+    }
+
+    /// Sets the format that diagnostics are printed in.
+    pub fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Turns colored output on or off.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+}
 
-/// Checks if an error occurred during scanning, parsing, or interpreting.
-pub fn did_error() -> bool {
-    unsafe { HAD_ERROR || HAD_RUNTIME_ERROR }
+impl Default for StderrReporter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-/// Checks if an error occurred during runtime.
-pub fn did_runtime_error() -> bool {
-    unsafe { HAD_RUNTIME_ERROR }
+impl Reporter for StderrReporter {
+    fn report(&mut self, diagnostic: &Diagnostic) {
+        match self.format {
+            Format::Human => eprint!("{}", render::human(diagnostic, self.file.as_deref(), &self.source, self.color)),
+            Format::Json => eprintln!("{}", render::json(diagnostic, self.file.as_deref())),
+        }
+    }
+
+    fn source(&mut self, file: Option<&str>, source: &str) {
+        self.file = file.map(str::to_string);
+        self.source = source.to_string();
+    }
 }
 
-/// Resets the error flag.
-/// This is used to reset the interpreter after an error occurs when running prompts.
-pub fn reset_error() {
-    unsafe {
-        HAD_ERROR = false;
-        HAD_RUNTIME_ERROR = false;
+/// Allows any closure to be used as a reporter.
+impl<F: FnMut(&Diagnostic)> Reporter for F {
+    fn report(&mut self, diagnostic: &Diagnostic) {
+        self(diagnostic)
     }
 }
 
 /// Every error type must implement this trait.
 pub trait Error {
-    /// Prints the error message and sets the error flag.
-    fn throw(&self);
+    /// Converts the error into a diagnostic.
+    fn to_diagnostic(&self) -> Diagnostic;
 }
 
 /// Represents an error that occurs during scanning.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ScanError {
     pub location: Location,
     pub message: String,
 }
 
 impl Error for ScanError {
-    fn throw(&self) {
-        eprintln!(
-            "[line {line}:{column}] Error: {message}",
-            line = self.location.line + 1,
-            column = self.location.column + 1,
-            message = self.message
-        );
-
-        unsafe {
-            HAD_ERROR = true;
+    fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic {
+            phase: Phase::Scan,
+            severity: Severity::Error,
+            span: Span::from(self.location),
+            message: self.message.clone(),
+            token: None,
+            help: None,
+            trace: vec![],
         }
     }
 }
 
 /// Represents an error that occurs during parsing.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ParseError {
     pub token: Token,
     pub message: String,
+    pub help: Option<String>,
 }
 
 impl Error for ParseError {
-    fn throw(&self) {
-        if self.token.r#type == Type::EOF {
-            eprintln!(
-                "[line {line}:{column}] Error at end: {message}",
-                line = self.token.location.line + 1,
-                column = self.token.location.column + 1,
-                message = self.message
-            );
-        } else {
-            eprintln!(
-                "[line {line}:{column}] Error at '{lexeme}': {message}",
-                line = self.token.location.line + 1,
-                column = self.token.location.column + 1,
-                lexeme = self.token.lexeme,
-                message = self.message
-            );
-        }
-
-        unsafe {
-            HAD_ERROR = true;
+    fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic {
+            phase: Phase::Parse,
+            severity: Severity::Error,
+            span: Span::from(&self.token),
+            message: self.message.clone(),
+            token: Some(self.token.clone()),
+            help: self.help.clone(),
+            trace: vec![],
         }
     }
 }
 
 /// Represents an error that occurs during resolution.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ResolveError {
     pub token: Token,
     pub message: String,
 }
 
 impl Error for ResolveError {
-    fn throw(&self) {
-        eprintln!(
-            "[line {line}:{column}] Error at '{lexeme}': {message}",
-            line = self.token.location.line + 1,
-            column = self.token.location.column + 1,
-            lexeme = self.token.lexeme,
-            message = self.message
-        );
-
-        unsafe {
-            HAD_ERROR = true;
+    fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic {
+            phase: Phase::Resolve,
+            severity: Severity::Error,
+            span: Span::from(&self.token),
+            message: self.message.clone(),
+            token: Some(self.token.clone()),
+            help: None,
+            trace: vec![],
+        }
+    }
+}
+
+/// Represents a warning about a likely mistake that the [`Linter`](crate::linter::Linter) found.
+#[derive(Debug, Clone)]
+pub struct LintWarning {
+    pub token: Token,
+    /// The rule that produced the warning.
+    pub rule: Rule,
+    pub message: String,
+}
+
+impl Error for LintWarning {
+    fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic {
+            phase: Phase::Lint,
+            severity: Severity::Warning,
+            span: Span::from(&self.token),
+            message: format!("{} ({})", self.message, self.rule),
+            token: Some(self.token.clone()),
+            help: None,
+            trace: vec![],
         }
     }
 }
 
 /// Represents an error that occurs during runtime.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RuntimeError {
     pub token: Token,
     pub message: String,
+    /// A note on how to fix the error, such as a similarly named variable.
+    pub help: Option<String>,
+    /// The calls that were being executed when the error occurred, the innermost one first.
+    /// The trace is recorded by the backend as the error leaves the call it occurred in.
+    pub trace: Vec<TraceFrame>,
+    /// The value that a `throw` statement raised if it was not an error. It is a value of the
+    /// backend that threw it, the message is its string representation.
+    pub value: Option<Rc<dyn Any>>,
+}
+
+impl RuntimeError {
+    /// Returns the property of the error with the given name.
+    /// Caught errors are exposed to scripts as error objects with a `message`, a `line`, a
+    /// `column` and a `value` property. The location is one-based, just like in the reported
+    /// diagnostics. The value is the thrown value, or `null` if the error was not thrown by a
+    /// script.
+    pub fn get<V: StdValue>(&self, name: &Token) -> Result<V, RuntimeError> {
+        match name.lexeme.as_str() {
+            "message" => Ok(V::from(Literal::String(self.message.clone()))),
+            "line" => Ok(V::from(Literal::Number((self.token.location.line + 1) as f64))),
+            "column" => Ok(V::from(Literal::Number((self.token.location.column + 1) as f64))),
+            "value" => Ok(self.value.as_ref()
+                .and_then(|value| value.downcast_ref::<V>())
+                .cloned()
+                .unwrap_or(V::from(Literal::Null))),
+            _ => Err(RuntimeError {
+                token: name.clone(),
+                message: format!("Undefined property '{}'", name.lexeme),
+                help: None,
+                trace: vec![],
+                value: None,
+            }),
+        }
+    }
 }
 
 impl Error for RuntimeError {
-    fn throw(&self) {
-        eprintln!(
-            "[line {line}:{column}] Error at '{lexeme}': {message}",
-            line = self.token.location.line + 1,
-            column = self.token.location.column + 1,
-            lexeme = self.token.lexeme,
-            message = self.message
-        );
-
-        unsafe {
-            HAD_RUNTIME_ERROR = true;
+    fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic {
+            phase: Phase::Runtime,
+            severity: Severity::Error,
+            span: Span::from(&self.token),
+            message: self.message.clone(),
+            token: Some(self.token.clone()),
+            help: self.help.clone(),
+            trace: self.trace.clone(),
         }
     }
 }
 
+/// Returns the candidate that is most similar to the given name.
+/// Candidates are compared by the number of single character insertions, deletions,
+/// substitutions and transpositions that turn one into the other. Only candidates that are at
+/// most a third of the name length away are considered.
+pub fn most_similar<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let threshold = (name.chars().count() / 3).max(1);
+
+    candidates.into_iter()
+        .filter(|candidate| !candidate.is_empty() && *candidate != name)
+        .map(|candidate| (distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Returns a help note that suggests the candidate that is most similar to the given name.
+/// ```
+/// use rocks_lang::error::did_you_mean;
+///
+/// assert_eq!(did_you_mean("lenght", ["length", "width"]), Some("did you mean 'length'?".to_string()));
+/// assert_eq!(did_you_mean("x", ["length", "width"]), None);
+/// ```
+pub fn did_you_mean<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    most_similar(name, candidates).map(|candidate| format!("did you mean '{candidate}'?"))
+}
+
+/// Returns the optimal string alignment distance between the given strings.
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // Rows of the distances between the prefixes of `a` and every prefix of `b`.
+    let mut previous: Vec<usize> = vec![0; b.len() + 1];
+    let mut current: Vec<usize> = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        let before = std::mem::replace(&mut previous, current.clone());
+        current[0] = i;
+
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+    }
+
+    current[b.len()]
+}
+
 /// This error is thrown when a return statement is executed. Since errors are propagated up the
 /// execution stack, the interpreter can catch it and return the value of the return statement.
 #[derive(Debug)]
@@ -137,9 +426,20 @@ pub struct ReturnError {
 }
 
 /// This error is thrown when a break statement is executed. Since errors are propagated up the
-/// execution stack, the interpreter can catch it and break out of the current loop.
+/// execution stack, the interpreter can catch it and break out of the current loop or the
+/// enclosing loop with the given label.
 #[derive(Debug)]
-pub struct BreakError;
+pub struct BreakError {
+    pub label: Option<Token>,
+}
+
+/// This error is thrown when a continue statement is executed. Since errors are propagated up the
+/// execution stack, the interpreter can catch it and skip to the next iteration of the current loop
+/// or the enclosing loop with the given label.
+#[derive(Debug)]
+pub struct ContinueError {
+    pub label: Option<Token>,
+}
 
 /// Represents a special error that is caught by the interpreter.
 #[derive(Debug)]
@@ -147,4 +447,5 @@ pub enum ReturnType {
     Error(RuntimeError),
     Return(ReturnError),
     Break(BreakError),
+    Continue(ContinueError),
 }
@@ -1,5 +1,9 @@
+use std::cell::Cell;
+
 use crate::token::Token;
 use crate::literal::Literal;
+use crate::stmt::Stmt;
+use crate::resolver::Slot;
 
 /// Represents a [`logical`](Expr::Logical) expression's data in the language.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -33,12 +37,17 @@ pub struct GroupingData {
 /// Represents a [`variable`](Expr::Variable) expression's data in the language.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct VariableData {
+    /// Where the variable lives, set by the [`Resolver`](crate::resolver::Resolver).
+    /// Globals are left as `None`.
+    pub slot: Cell<Option<Slot>>,
     pub name: Token,
 }
 
 /// Represents an [`assign`](Expr::Assign) expression's data in the language.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct AssignData {
+    /// Like [`VariableData::slot`].
+    pub slot: Cell<Option<Slot>>,
     pub name: Token,
     pub value: Box<Expr>,
 }
@@ -69,16 +78,70 @@ pub struct SetData {
 /// Represents a [`this`](Expr::This) expression's data in the language.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct ThisData {
+    /// Like [`VariableData::slot`].
+    pub slot: Cell<Option<Slot>>,
     pub keyword: Token,
 }
 
 /// Represents a [`super`](Expr::Super) expression's data in the language.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct SuperData {
+    /// Like [`VariableData::slot`].
+    pub slot: Cell<Option<Slot>>,
     pub keyword: Token,
     pub method: Token,
 }
 
+/// Represents a [`lambda`](Expr::Lambda) expression's data in the language.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LambdaData {
+    /// The 'fun' keyword.
+    pub keyword: Token,
+    /// The function's parameters.
+    pub params: Vec<Token>,
+    /// The function's body.
+    /// The short form `fun (x) => x` is stored as a body with a single return statement.
+    pub body: Vec<Stmt>,
+}
+
+impl LambdaData {
+    /// Returns the name that the function created by the lambda is displayed with.
+    pub fn name(&self) -> Token {
+        Token { lexeme: "<lambda>".to_string(), ..self.keyword.clone() }
+    }
+}
+
+/// Represents a [`list`](Expr::List) expression's data in the language.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ListData {
+    pub bracket: Token,
+    pub elements: Vec<Expr>,
+}
+
+/// Represents a [`map`](Expr::Map) expression's data in the language.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MapData {
+    pub brace: Token,
+    pub entries: Vec<(Expr, Expr)>,
+}
+
+/// Represents an [`index`](Expr::Index) expression's data in the language.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct IndexData {
+    pub object: Box<Expr>,
+    pub bracket: Token,
+    pub index: Box<Expr>,
+}
+
+/// Represents an [`index set`](Expr::IndexSet) expression's data in the language.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct IndexSetData {
+    pub object: Box<Expr>,
+    pub bracket: Token,
+    pub index: Box<Expr>,
+    pub value: Box<Expr>,
+}
+
 /// Represents an expression in the language.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Expr {
@@ -147,9 +210,60 @@ pub enum Expr {
     /// - `super.method()`
     /// - `super.method(arg1, 23)`
     Super(SuperData),
+
+    /// A lambda (anonymous function) expression.
+    /// - `fun (a, b) { return a + b; }`
+    /// - `fun (x) => x * 2`
+    Lambda(LambdaData),
+
+    /// A list expression.
+    /// - `[]`
+    /// - `[1, "hello", true]`
+    List(ListData),
+
+    /// A map expression.
+    /// - `{}`
+    /// - `{"a": 1, 2: true}`
+    Map(MapData),
+
+    /// An index expression.
+    /// - `list[0]`
+    /// - `list[i + 1]`
+    /// - `map["key"]`
+    Index(IndexData),
+
+    /// An index set expression.
+    /// - `list[0] = 1`
+    /// - `list[i] = "hello"`
+    /// - `map["key"] = true`
+    IndexSet(IndexSetData),
 }
 
 impl Expr {
+    /// Returns a token that locates the expression in the source code.
+    /// Literals have no token, groupings use the token of their inner expression.
+    pub fn token(&self) -> Option<&Token> {
+        match self {
+            Expr::Literal(_) => None,
+            Expr::Logical(data) => Some(&data.operator),
+            Expr::Unary(data) => Some(&data.operator),
+            Expr::Binary(data) => Some(&data.operator),
+            Expr::Grouping(data) => data.expr.token(),
+            Expr::Variable(data) => Some(&data.name),
+            Expr::Assign(data) => Some(&data.name),
+            Expr::Call(data) => Some(&data.paren),
+            Expr::Get(data) => Some(&data.name),
+            Expr::Set(data) => Some(&data.name),
+            Expr::This(data) => Some(&data.keyword),
+            Expr::Super(data) => Some(&data.keyword),
+            Expr::Lambda(data) => Some(&data.keyword),
+            Expr::List(data) => Some(&data.bracket),
+            Expr::Map(data) => Some(&data.brace),
+            Expr::Index(data) => Some(&data.bracket),
+            Expr::IndexSet(data) => Some(&data.bracket),
+        }
+    }
+
     /// Accepts a visitor and returns the result of the visit.
     /// This is used to implement the visitor pattern.
     pub fn accept<T>(&self, visitor: &mut impl ExprVisitor<T>) -> T {
@@ -168,6 +282,11 @@ impl Expr {
             Set(_) => visitor.visit_set_expr(self),
             This(_) => visitor.visit_this_expr(self),
             Super(_) => visitor.visit_super_expr(self),
+            Lambda(_) => visitor.visit_lambda_expr(self),
+            List(_) => visitor.visit_list_expr(self),
+            Map(_) => visitor.visit_map_expr(self),
+            Index(_) => visitor.visit_index_expr(self),
+            IndexSet(_) => visitor.visit_index_set_expr(self),
         }
     }
 }
@@ -186,4 +305,9 @@ pub trait ExprVisitor<T> {
     fn visit_set_expr(&mut self, expr: &Expr) -> T;
     fn visit_this_expr(&mut self, expr: &Expr) -> T;
     fn visit_super_expr(&mut self, expr: &Expr) -> T;
+    fn visit_lambda_expr(&mut self, expr: &Expr) -> T;
+    fn visit_list_expr(&mut self, expr: &Expr) -> T;
+    fn visit_map_expr(&mut self, expr: &Expr) -> T;
+    fn visit_index_expr(&mut self, expr: &Expr) -> T;
+    fn visit_index_set_expr(&mut self, expr: &Expr) -> T;
 }
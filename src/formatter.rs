@@ -0,0 +1,660 @@
+//! A formatter that prints the syntax tree back into canonical source code.
+//!
+//! Unlike the [`ASTPrinter`](crate::ast::ASTPrinter), the output is valid rocks code that parses
+//! into the same syntax tree. Statements are indented with four spaces, operators and commas are
+//! followed by a single space and the argument lists of calls that do not fit into the line width
+//! are wrapped with one argument per line. Single blank lines between statements are kept.
+//!
+//! The syntax tree does not hold comments, so the formatter places the comments that the
+//! [`Scanner`] kept aside by their location. A comment is printed before the first statement or
+//! closing brace that follows it, comments that follow code on the same line stay at the end of
+//! that line. Comments within a multi-line expression are moved after its statement.
+//!
+//! ```
+//! use rocks_lang::formatter::Formatter;
+//!
+//! let source = "fun add(a,b){return a+b;} // sum\nprint add(1,2);";
+//! let formatted = Formatter::new().format(source).unwrap();
+//!
+//! assert_eq!(formatted, "fun add(a, b) {\n    return a + b;\n} // sum\nprint add(1, 2);\n");
+//! ```
+
+use crate::error::{Diagnostic, Error};
+use crate::expr::{CallData, Expr};
+use crate::literal::Literal;
+use crate::parser::Parser;
+use crate::scanner::Scanner;
+use crate::stmt::{BlockData, Stmt, WhileData};
+use crate::token::{Comment, Location, Token, Type};
+
+/// The default maximum length of a line.
+const WIDTH: usize = 100;
+
+const INDENT: &str = "    ";
+
+/// Formats source code into the canonical style.
+#[derive(Debug, Clone, Copy)]
+pub struct Formatter {
+    width: usize,
+}
+
+impl Formatter {
+    /// Creates a formatter that wraps lines longer than 100 characters.
+    pub fn new() -> Self {
+        Formatter { width: WIDTH }
+    }
+
+    /// Sets the maximum length of a line.
+    /// Lines can still be longer if there is no call to wrap.
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Returns the formatted source code, or the scan and parse errors of the source code.
+    pub fn format(&self, source: &str) -> Result<String, Vec<Diagnostic>> {
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan_tokens();
+
+        if !scanner.errors().is_empty() {
+            return Err(scanner.errors().iter().map(Error::to_diagnostic).collect());
+        }
+
+        let braces = braces(&tokens);
+        let mut parser = Parser::new(tokens);
+        let statements = parser.parse();
+
+        if !parser.errors().is_empty() {
+            return Err(parser.errors().iter().map(Error::to_diagnostic).collect());
+        }
+
+        let mut printer = Printer {
+            width: self.width,
+            blank: source.lines().map(|line| line.trim().is_empty()).collect(),
+            comments: scanner.comments().to_vec(),
+            braces,
+            output: String::new(),
+            indent: 0,
+            fresh: true,
+            flat: false,
+            commented: false,
+            comment: 0,
+            brace: 0,
+        };
+
+        printer.statements(&statements);
+        printer.comments(None);
+
+        Ok(printer.output)
+    }
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the locations of the opening and the closing braces of every pair of braces in the
+/// order of the opening braces.
+fn braces(tokens: &[Token]) -> Vec<(Location, Location)> {
+    let mut pairs = vec![];
+    let mut open = vec![];
+
+    for token in tokens {
+        match token.r#type {
+            Type::LeftBrace => {
+                open.push(pairs.len());
+                pairs.push((token.location, token.location));
+            },
+            Type::RightBrace => {
+                if let Some(index) = open.pop() {
+                    pairs[index].1 = token.location;
+                }
+            },
+            _ => (),
+        }
+    }
+
+    pairs
+}
+
+/// Returns the location of the first token of the expression (if it has one).
+fn start(expr: &Expr) -> Option<Location> {
+    match expr {
+        Expr::Literal(_) => None,
+        Expr::Logical(data) => start(&data.left).or(Some(data.operator.location)),
+        Expr::Unary(data) => Some(data.operator.location),
+        Expr::Binary(data) => start(&data.left).or(Some(data.operator.location)),
+        Expr::Grouping(data) => start(&data.expr),
+        Expr::Variable(data) => Some(data.name.location),
+        Expr::Assign(data) => Some(data.name.location),
+        Expr::Call(data) => start(&data.callee).or(Some(data.paren.location)),
+        Expr::Get(data) => start(&data.object).or(Some(data.name.location)),
+        Expr::Set(data) => start(&data.object).or(Some(data.name.location)),
+        Expr::This(data) => Some(data.keyword.location),
+        Expr::Super(data) => Some(data.keyword.location),
+        Expr::Lambda(data) => Some(data.keyword.location),
+        Expr::List(data) => Some(data.bracket.location),
+        Expr::Map(data) => Some(data.brace.location),
+        Expr::Index(data) => start(&data.object).or(Some(data.bracket.location)),
+        Expr::IndexSet(data) => start(&data.object).or(Some(data.bracket.location)),
+    }
+}
+
+/// Returns the initializer and the loop of a `for` statement with an initializer, which the
+/// parser turns into a block that holds both.
+/// A block that was written in the source code starts before the loop.
+fn desugared_for(block: &BlockData) -> Option<(&Stmt, &WhileData)> {
+    let [initializer, Stmt::While(data)] = block.statements.as_slice() else {
+        return None;
+    };
+
+    let initialized = match initializer {
+        Stmt::Var(var) => Some(var.name.location),
+        Stmt::Expression(expression) => start(&expression.expr),
+        _ => return None,
+    };
+
+    let keyword = data.keyword.location;
+    let after = initialized.is_none_or(|location| location > keyword);
+
+    (data.keyword.r#type == Type::For && after).then_some((initializer, data))
+}
+
+/// Holds the state of formatting a single source file.
+struct Printer {
+    width: usize,
+    /// If a line of the source code is blank, by line number.
+    blank: Vec<bool>,
+    comments: Vec<Comment>,
+    braces: Vec<(Location, Location)>,
+    output: String,
+    indent: usize,
+    /// If true, nothing has been printed in the current block yet.
+    fresh: bool,
+    /// If true, calls are not wrapped.
+    flat: bool,
+    /// If true, the last line is a comment on its own.
+    commented: bool,
+    /// The index of the next comment to print.
+    comment: usize,
+    /// The index of the next pair of braces to print.
+    brace: usize,
+}
+
+/// Represents the progress of the printer that it can go back to.
+struct Checkpoint {
+    output: usize,
+    fresh: bool,
+    commented: bool,
+    comment: usize,
+    brace: usize,
+}
+
+impl Printer {
+    fn write(&mut self, text: &str) {
+        self.output += text;
+        self.commented = false;
+    }
+
+    /// Starts a new line at the current indentation.
+    fn newline(&mut self) {
+        self.output.push('\n');
+        self.write_indent();
+    }
+
+    fn write_indent(&mut self) {
+        for _ in 0..self.indent {
+            self.output += INDENT;
+        }
+    }
+
+    /// Prepares a line for a statement or a comment that starts at the given line of the source
+    /// code, keeping a blank line before it if there was one.
+    fn line(&mut self, line: usize) {
+        if !self.fresh && line > 0 && self.blank.get(line - 1).copied().unwrap_or_default() {
+            self.output.push('\n');
+        }
+
+        self.fresh = false;
+        self.write_indent();
+    }
+
+    /// Prints the comments before the given location, or all of the remaining ones.
+    fn comments(&mut self, before: Option<Location>) {
+        while let Some(comment) = self.comments.get(self.comment).cloned() {
+            let location = comment.location;
+            if before.is_some_and(|before| location >= before) {
+                break;
+            }
+
+            self.comment += 1;
+            if comment.trailing && self.output.ends_with('\n') && !self.commented {
+                self.output.pop();
+                self.write(&format!(" {}\n", comment.text));
+            } else {
+                self.line(location.line);
+                self.write(&comment.text);
+                self.output.push('\n');
+                self.commented = true;
+            }
+        }
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            output: self.output.len(),
+            fresh: self.fresh,
+            commented: self.commented,
+            comment: self.comment,
+            brace: self.brace,
+        }
+    }
+
+    fn restore(&mut self, checkpoint: Checkpoint) {
+        self.output.truncate(checkpoint.output);
+        self.fresh = checkpoint.fresh;
+        self.commented = checkpoint.commented;
+        self.comment = checkpoint.comment;
+        self.brace = checkpoint.brace;
+    }
+
+    /// Returns if every line printed since the given position fits into the width.
+    fn fits(&self, position: usize) -> bool {
+        let start = self.output[..position].rfind('\n').map_or(0, |index| index + 1);
+        self.output[start..].lines().all(|line| line.chars().count() <= self.width)
+    }
+
+    /// Returns the location of the first token of the statement (if it has one).
+    fn start(&self, stmt: &Stmt) -> Option<Location> {
+        match stmt {
+            Stmt::Expression(data) => start(&data.expr),
+            Stmt::Function(data) => Some(data.name.location),
+            Stmt::If(data) => start(&data.condition).or_else(|| self.start(&data.then_branch)),
+            Stmt::Print(data) => Some(data.keyword.location),
+            Stmt::Return(data) => Some(data.keyword.location),
+            Stmt::Break(data) => Some(data.keyword.location),
+            Stmt::Continue(data) => Some(data.keyword.location),
+            Stmt::Throw(data) => Some(data.keyword.location),
+            Stmt::Try(data) => Some(data.keyword.location),
+            Stmt::Import(data) => Some(data.keyword.location),
+            Stmt::Var(data) => Some(data.name.location),
+            Stmt::While(data) => Some(data.label.as_ref().unwrap_or(&data.keyword).location),
+            Stmt::Class(data) => Some(data.name.location),
+            Stmt::Block(data) => match desugared_for(data) {
+                Some((_, data)) => Some(data.label.as_ref().unwrap_or(&data.keyword).location),
+                None => self.braces.get(self.brace).map(|(open, _)| *open),
+            },
+        }
+    }
+
+    /// Prints each statement on its own line, with the comments before it.
+    fn statements(&mut self, statements: &[Stmt]) {
+        for statement in statements {
+            let start = self.start(statement);
+            if let Some(start) = start {
+                self.comments(Some(start));
+            }
+
+            self.line(start.map_or(0, |start| start.line));
+            self.statement(statement);
+            self.output.push('\n');
+        }
+    }
+
+    /// Prints the statements between a pair of braces.
+    fn block(&mut self, statements: &[Stmt]) {
+        self.enclose(statements.is_empty(), |printer| printer.statements(statements));
+    }
+
+    /// Prints a pair of braces with the lines that the given closure prints between them.
+    /// Empty braces are printed on a single line unless there are comments between them.
+    fn enclose(&mut self, empty: bool, lines: impl FnOnce(&mut Self)) {
+        let close = self.open();
+
+        let commented = self.comments.get(self.comment).is_some_and(|comment| {
+            comment.location < close
+        });
+        if empty && !commented {
+            self.write("}");
+            return;
+        }
+
+        self.output.push('\n');
+        self.indent += 1;
+        self.fresh = true;
+
+        lines(self);
+        self.comments(Some(close));
+
+        self.indent -= 1;
+        self.fresh = false;
+        self.write_indent();
+        self.write("}");
+    }
+
+    /// Prints an opening brace and returns the location of its closing brace.
+    fn open(&mut self) -> Location {
+        self.write("{");
+
+        let close = self.braces.get(self.brace).map_or(Location::new(usize::MAX, 0), |(_, close)| *close);
+        self.brace += 1;
+        close
+    }
+
+    /// Prints the body of a control flow statement after its header.
+    /// Bodies that are not blocks are printed on the next line with an extra indentation.
+    fn body(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Block(data) if desugared_for(data).is_none() => {
+                self.write(" ");
+                self.block(&data.statements);
+            },
+            stmt => {
+                self.indent += 1;
+                self.newline();
+                self.statement(stmt);
+                self.indent -= 1;
+            },
+        }
+    }
+
+    fn statement(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expression(data) => {
+                self.expr(&data.expr);
+                self.write(";");
+            },
+            Stmt::Function(data) => {
+                self.write("fun ");
+                self.function(&data.name.lexeme, &data.params, &data.body);
+            },
+            Stmt::If(data) => {
+                self.write("if (");
+                self.expr(&data.condition);
+                self.write(")");
+                self.body(&data.then_branch);
+
+                let Some(else_branch) = &data.else_branch else { return };
+
+                // Comments before the else branch stay before the `else`, which starts a new line.
+                let start = self.start(else_branch);
+                let commented = start.is_some_and(|start| {
+                    self.comments.get(self.comment).is_some_and(|comment| comment.location < start)
+                });
+
+                match data.then_branch.as_ref() {
+                    _ if commented => {
+                        self.output.push('\n');
+                        self.comments(start);
+                        self.write_indent();
+                        self.write("else");
+                    },
+                    Stmt::Block(block) if desugared_for(block).is_none() => self.write(" else"),
+                    _ => {
+                        self.newline();
+                        self.write("else");
+                    },
+                }
+
+                match else_branch.as_ref() {
+                    Stmt::If(_) => {
+                        self.write(" ");
+                        self.statement(else_branch);
+                    },
+                    else_branch => self.body(else_branch),
+                }
+            },
+            Stmt::Print(data) => {
+                self.write("print ");
+                self.expr(&data.expr);
+                self.write(";");
+            },
+            Stmt::Return(data) => {
+                self.write("return");
+                if let Some(value) = &data.value {
+                    self.write(" ");
+                    self.expr(value);
+                }
+                self.write(";");
+            },
+            Stmt::Break(data) => self.jump("break", &data.label),
+            Stmt::Continue(data) => self.jump("continue", &data.label),
+            Stmt::Throw(data) => {
+                self.write("throw ");
+                self.expr(&data.value);
+                self.write(";");
+            },
+            Stmt::Try(data) => {
+                self.write("try ");
+                self.block(&data.body);
+
+                if let Some(catch) = &data.catch {
+                    self.write(&format!(" catch ({}) ", catch.name.lexeme));
+                    self.block(&catch.body);
+                }
+
+                if let Some(finally) = &data.finally {
+                    self.write(" finally ");
+                    self.block(finally);
+                }
+            },
+            Stmt::Import(data) => {
+                let names: Vec<&str> = data.names.iter().map(|name| name.lexeme.as_str()).collect();
+
+                match &data.alias {
+                    Some(alias) => self.write(&format!("import \"{}\" as {};", data.path.lexeme, alias.lexeme)),
+                    None => self.write(&format!("from \"{}\" import {};", data.path.lexeme, names.join(", "))),
+                }
+            },
+            Stmt::Var(data) => {
+                self.write(&format!("var {}", data.name.lexeme));
+                if let Some(initializer) = &data.initializer {
+                    self.write(" = ");
+                    self.expr(initializer);
+                }
+                self.write(";");
+            },
+            Stmt::While(data) => self.r#loop(None, data),
+            Stmt::Block(data) => match desugared_for(data) {
+                Some((initializer, data)) => self.r#loop(Some(initializer), data),
+                None => self.block(&data.statements),
+            },
+            Stmt::Class(data) => {
+                self.write(&format!("class {}", data.name.lexeme));
+                if let Some(Expr::Variable(superclass)) = &data.superclass {
+                    self.write(&format!(" < {}", superclass.name.lexeme));
+                }
+                self.write(" ");
+
+                self.enclose(data.methods.is_empty(), |printer| {
+                    for method in &data.methods {
+                        let Stmt::Function(method) = method else { unreachable!() };
+
+                        printer.comments(Some(method.name.location));
+                        printer.line(method.name.location.line);
+                        printer.function(&method.name.lexeme, &method.params, &method.body);
+                        printer.output.push('\n');
+                    }
+                });
+            },
+        }
+    }
+
+    fn jump(&mut self, keyword: &str, label: &Option<Token>) {
+        self.write(keyword);
+        if let Some(label) = label {
+            self.write(&format!(" {}", label.lexeme));
+        }
+        self.write(";");
+    }
+
+    /// Prints a while loop, or a for loop if the loop was written with the 'for' keyword.
+    fn r#loop(&mut self, initializer: Option<&Stmt>, data: &WhileData) {
+        if let Some(label) = &data.label {
+            self.write(&format!("{}: ", label.lexeme));
+        }
+
+        if data.keyword.r#type == Type::While {
+            self.write("while (");
+            self.expr(&data.condition);
+            self.write(")");
+            self.body(&data.body);
+            return;
+        }
+
+        self.write("for (");
+        match initializer {
+            Some(initializer) => self.statement(initializer),
+            None => self.write(";"),
+        }
+
+        // A loop without a condition is stored with a condition that is always true.
+        if data.condition != Expr::Literal(Literal::Bool(true)) {
+            self.write(" ");
+            self.expr(&data.condition);
+        }
+        self.write(";");
+
+        if let Some(increment) = &data.increment {
+            self.write(" ");
+            self.expr(increment);
+        }
+        self.write(")");
+        self.body(&data.body);
+    }
+
+    /// Prints the name, the parameters and the body of a function.
+    fn function(&mut self, name: &str, params: &[Token], body: &[Stmt]) {
+        let params: Vec<&str> = params.iter().map(|param| param.lexeme.as_str()).collect();
+        self.write(&format!("{name}({}) ", params.join(", ")));
+        self.block(body);
+    }
+
+    fn exprs(&mut self, exprs: &[Expr]) {
+        for (i, expr) in exprs.iter().enumerate() {
+            if i > 0 {
+                self.write(", ");
+            }
+            self.expr(expr);
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Literal(Literal::String(string)) => self.write(&format!("\"{string}\"")),
+            Expr::Literal(literal) => self.write(&literal.to_string()),
+            Expr::Logical(data) => {
+                self.expr(&data.left);
+                self.write(&format!(" {} ", data.operator.lexeme));
+                self.expr(&data.right);
+            },
+            Expr::Binary(data) => {
+                self.expr(&data.left);
+                self.write(&format!(" {} ", data.operator.lexeme));
+                self.expr(&data.right);
+            },
+            Expr::Unary(data) => {
+                self.write(&data.operator.lexeme);
+                self.expr(&data.expr);
+            },
+            Expr::Grouping(data) => {
+                self.write("(");
+                self.expr(&data.expr);
+                self.write(")");
+            },
+            Expr::Variable(data) => self.write(&data.name.lexeme),
+            Expr::Assign(data) => {
+                self.write(&format!("{} = ", data.name.lexeme));
+                self.expr(&data.value);
+            },
+            Expr::Call(data) => self.call(data),
+            Expr::Get(data) => {
+                self.expr(&data.object);
+                self.write(&format!(".{}", data.name.lexeme));
+            },
+            Expr::Set(data) => {
+                self.expr(&data.object);
+                self.write(&format!(".{} = ", data.name.lexeme));
+                self.expr(&data.value);
+            },
+            Expr::This(_) => self.write("this"),
+            Expr::Super(data) => self.write(&format!("super.{}", data.method.lexeme)),
+            Expr::Lambda(data) => {
+                let params: Vec<&str> = data.params.iter().map(|param| param.lexeme.as_str()).collect();
+                self.write(&format!("fun ({}) ", params.join(", ")));
+
+                // The short form is stored as a return statement whose keyword is the arrow.
+                match data.body.as_slice() {
+                    [Stmt::Return(body)] if body.keyword.r#type == Type::Arrow => {
+                        self.write("=> ");
+                        self.expr(body.value.as_ref().expect("short lambda to return a value"));
+                    },
+                    body => self.block(body),
+                }
+            },
+            Expr::List(data) => {
+                self.write("[");
+                self.exprs(&data.elements);
+                self.write("]");
+            },
+            Expr::Map(data) => {
+                self.open();
+                for (i, (key, value)) in data.entries.iter().enumerate() {
+                    if i > 0 {
+                        self.write(", ");
+                    }
+                    self.expr(key);
+                    self.write(": ");
+                    self.expr(value);
+                }
+                self.write("}");
+            },
+            Expr::Index(data) => {
+                self.expr(&data.object);
+                self.write("[");
+                self.expr(&data.index);
+                self.write("]");
+            },
+            Expr::IndexSet(data) => {
+                self.expr(&data.object);
+                self.write("[");
+                self.expr(&data.index);
+                self.write("] = ");
+                self.expr(&data.value);
+            },
+        }
+    }
+
+    /// Prints a call on a single line if it fits, otherwise with one argument per line.
+    fn call(&mut self, data: &CallData) {
+        self.expr(&data.callee);
+
+        let checkpoint = self.checkpoint();
+        let flat = self.flat;
+        self.flat = true;
+        self.write("(");
+        self.exprs(&data.arguments);
+        self.write(")");
+        self.flat = flat;
+
+        if flat || data.arguments.is_empty() || self.fits(checkpoint.output) {
+            return;
+        }
+
+        self.restore(checkpoint);
+        self.write("(");
+        self.indent += 1;
+        for (i, argument) in data.arguments.iter().enumerate() {
+            self.newline();
+            self.expr(argument);
+            if i + 1 < data.arguments.len() {
+                self.write(",");
+            }
+        }
+        self.indent -= 1;
+        self.newline();
+        self.write(")");
+    }
+}
@@ -1,36 +1,51 @@
 use std::fmt::{Debug, Display};
 use std::cell::RefCell;
+use std::path::Path;
 use std::rc::Rc;
 
+use crate::config::InterpreterConfig;
 use crate::environment::Environment;
-use crate::interpreter::Interpreter;
-use crate::object::{Object, Callable};
+use crate::gc::{Trace, Tracer};
+use crate::interpreter::{Globals, Interpreter};
+use crate::object::{Arity, Object, Callable};
 use crate::error::{RuntimeError, ReturnType};
 use crate::stmt::Stmt;
 use crate::token::Token;
 use crate::literal::Literal;
+use crate::stdlib::{self, StdError, StdFunction};
 
 /// Represents a function.
 /// This is a struct that wraps the function's name, parameters, and body.
-/// It also contains a reference to the environment in which it was defined.
+/// It also contains a reference to the environment and the globals of the file in which it was
+/// defined, along with the path of that file.
 #[derive(Debug, Clone)]
 pub struct Function {
     pub name: Token,
     params: Vec<Token>,
     body: Vec<Stmt>,
-    closure: Rc<RefCell<Environment>>,
+    closure: Option<Rc<RefCell<Environment>>>,
+    globals: Globals,
+    file: Option<Rc<Path>>,
     is_initializer: bool,
 }
 
 impl Function {
     /// Creates a new function.
-    pub fn new(stmt: Stmt, closure: Rc<RefCell<Environment>>, is_initializer: bool) -> Self {
+    pub fn new(
+        stmt: Stmt,
+        closure: Option<Rc<RefCell<Environment>>>,
+        globals: Globals,
+        file: Option<Rc<Path>>,
+        is_initializer: bool
+    ) -> Self {
         if let Stmt::Function(data) = stmt {
             Function {
                 name: data.name,
                 params: data.params,
                 body: data.body,
                 closure,
+                globals,
+                file,
                 is_initializer,
             }
         } else {
@@ -40,18 +55,26 @@ impl Function {
 
     /// Binds the function to an instance by wrapping its environment.
     /// This is used to allow the function to access the instance's fields.
-    pub fn bind(&mut self, instance: Object) -> Self {
-        let mut environment = Environment::new(Some(Rc::clone(&self.closure)));
+    pub fn bind(&mut self, instance: Object, interpreter: &mut Interpreter) -> Self {
+        let mut environment = Environment::new(self.closure.clone());
         environment.define("this", instance);
 
         Function {
             name: self.name.clone(),
             params: self.params.clone(),
             body: self.body.clone(),
-            closure: Rc::new(RefCell::new(environment)),
+            closure: Some(interpreter.allocate(environment)),
+            globals: Rc::clone(&self.globals),
+            file: self.file.clone(),
             is_initializer: self.is_initializer,
         }
     }
+
+    /// Returns the instance that the method is bound to.
+    /// 'this' is always the only variable in the environment that a method is bound with.
+    fn this(&self) -> Object {
+        self.closure.as_ref().expect("method to be bound").borrow().get_at(0, 0)
+    }
 }
 
 impl Callable for Function {
@@ -61,44 +84,50 @@ impl Callable for Function {
     ///
     /// Note: Initializer methods will return the instance that they were called on.
     fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, RuntimeError> {
-        let environment = Rc::new(RefCell::new(
-            Environment::new(Some(Rc::clone(&self.closure)))
-        ));
+        let environment = interpreter.allocate(Environment::new(self.closure.clone()));
 
-        self.params.iter().zip(arguments.iter()).for_each(|(param, arg)| {
-            environment.borrow_mut().define(&param.lexeme, arg.to_owned());
+        self.params.iter().zip(arguments).for_each(|(param, arg)| {
+            environment.borrow_mut().define(&param.lexeme, arg);
         });
 
-        match interpreter.execute_block(&self.body, environment) {
+        match interpreter.execute_function(&self.body, environment, &self.globals, &self.file) {
             Ok(_) => {
                 if self.is_initializer {
-                    return self.closure.borrow().get_at(0, &Token::from("this"));
+                    return Ok(self.this());
                 }
 
                 Ok(Object::from(Literal::Null))
             },
             Err(err) => {
-                if self.is_initializer {
-                    return self.closure.borrow().get_at(0, &Token::from("this"));
-                }
-
                 match err {
+                    // Returning from an initializer still returns the instance.
+                    ReturnType::Return(_) if self.is_initializer => {
+                        return Ok(self.this());
+                    },
                     ReturnType::Return(err) => {
                         return Ok(err.value);
                     },
                     ReturnType::Error(err) => {
                         return Err(err);
                     },
-                    ReturnType::Break(_) => {
-                        unreachable!("function calls should not return break");
-                    } 
+                    ReturnType::Break(_) | ReturnType::Continue(_) => {
+                        unreachable!("function calls should not return break or continue");
+                    },
                 }
             },
         }
     }
 
-    fn arity(&self) -> usize {
-        self.params.len()
+    fn arity(&self) -> Arity {
+        Arity::Fixed(self.params.len())
+    }
+}
+
+impl Trace for Function {
+    fn trace(&self, tracer: &mut Tracer) {
+        if let Some(closure) = &self.closure {
+            tracer.edge(closure);
+        }
     }
 }
 
@@ -114,15 +143,19 @@ impl PartialEq for Function {
     }
 }
 
+/// The signature of the Rust closure that implements a [`NativeFunction`].
+pub type NativeFn = dyn Fn(&mut Interpreter, Vec<Object>) -> Result<Object, RuntimeError>;
+
 /// Represents a native function.
 /// This is a special type of function that is used to implement built-in functions.
 /// These functions are external to the language and are implemented in Rust.
 /// They are used to provide access to the host environment.
 ///
-/// Native functions are defined in the
+/// Built-in native functions are defined in the
 /// [`NativeFunction::get_globals`](NativeFunction::get_globals) method and used
-/// during the initialization of the [`Interpreter`](crate::interpreter::Interpreter::new).
-/// These functions will be available to the user in the global scope.
+/// during the initialization of the [`Interpreter`](crate::interpreter::Interpreter::with_config).
+/// These functions will be available to the user in the global scope. Hosts can register their
+/// own native functions using [`rocks::register_native`](crate::rocks::register_native).
 ///
 /// Current native functions:
 /// - `clock()` - Returns the current time in milliseconds.
@@ -130,44 +163,81 @@ impl PartialEq for Function {
 #[derive(Clone)]
 pub struct NativeFunction {
     pub name: Token,
-    function: fn(&mut Interpreter, Vec<Object>) -> Result<Object, RuntimeError>,
+    arity: Arity,
+    function: Rc<NativeFn>,
+    /// The object that the function is bound to (if any), it is passed as the first argument.
+    /// Methods of lists and maps hold their receiver here instead of capturing it, so that the
+    /// cycle collector can see the reference.
+    receiver: Option<Box<Object>>,
+}
+
+impl NativeFunction {
+    /// Creates a new native function with the given name, arity and implementation.
+    /// The implementation can be any closure, including ones that capture their environment.
+    pub fn new<F>(name: &str, arity: Arity, function: F) -> Self
+    where
+        F: Fn(&mut Interpreter, Vec<Object>) -> Result<Object, RuntimeError> + 'static
+    {
+        NativeFunction {
+            name: Token::from(name),
+            arity,
+            function: Rc::new(function),
+            receiver: None,
+        }
+    }
+
+    /// Binds the function to the given receiver, which is passed as the first argument of every
+    /// call. The arity does not include the receiver.
+    pub fn bind(mut self, receiver: Object) -> Self {
+        self.receiver = Some(Box::new(receiver));
+        self
+    }
+
+    /// Returns a list of native functions with their implementations.
+    /// Functions that need a capability which the given configuration denies fail when called.
+    pub fn get_globals(config: &InterpreterConfig) -> Vec<NativeFunction> {
+        stdlib::globals()
+            .into_iter()
+            .map(|function| NativeFunction::from(function.gated(config)))
+            .collect()
+    }
+}
+
+impl From<StdFunction<Object>> for NativeFunction {
+    /// Wraps a function of the [`stdlib`](crate::stdlib), its failures are turned into runtime
+    /// errors.
+    fn from(value: StdFunction<Object>) -> Self {
+        let StdFunction { name, arity, function, .. } = value;
+
+        NativeFunction::new(name, arity, move |interpreter, arguments| {
+            function(arguments).map_err(|error| match error {
+                StdError::Message(message) => RuntimeError { token: Token::from(name), message, help: None, trace: vec![], value: None },
+                StdError::Exit(code) => interpreter.exit(code, Token::from(name)),
+            })
+        })
+    }
 }
 
 impl Callable for NativeFunction {
     /// Calls the native function and returns its return value.
-    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, RuntimeError> {
+    fn call(&self, interpreter: &mut Interpreter, mut arguments: Vec<Object>) -> Result<Object, RuntimeError> {
+        if let Some(receiver) = &self.receiver {
+            arguments.insert(0, (**receiver).clone());
+        }
+
         (self.function)(interpreter, arguments)
     }
 
-    fn arity(&self) -> usize {
-        0
+    fn arity(&self) -> Arity {
+        self.arity
     }
 }
 
-impl NativeFunction {
-    /// Returns a list of native functions with their implementations.
-    pub fn get_globals() -> Vec<NativeFunction> {
-        vec![
-            NativeFunction {
-                name: Token::from("clock"),
-                function: |_, _| {
-                    let now = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_millis();
-                    Ok(Object::from(now as f64))
-                },
-            },
-            NativeFunction {
-                name: Token::from("input"),
-                function: |_, _| {
-                    let mut input = String::new();
-                    std::io::stdin().read_line(&mut input).unwrap();
-                    input.pop();  // Remove newline
-                    Ok(Object::from(input))
-                },
-            },
-        ]
+impl Trace for NativeFunction {
+    fn trace(&self, tracer: &mut Tracer) {
+        if let Some(receiver) = &self.receiver {
+            receiver.trace(tracer);
+        }
     }
 }
 
@@ -0,0 +1,238 @@
+//! A cycle collector for the reference counted values of both backends.
+//!
+//! Values are shared with [`Rc`], which frees them as soon as their last reference is dropped.
+//! Reference cycles, such as an instance that stores a method bound to itself or a closure that
+//! is stored in the environment it captured, keep each other alive forever. The [`Heap`] tracks
+//! every object that can take part in a cycle and periodically collects the ones that are only
+//! reachable through other tracked objects.
+//!
+//! The collector works like the one of CPython and does not need to know the roots:
+//! 1. The references between tracked objects are subtracted from their reference counts.
+//! 2. Objects with remaining references are referenced from outside the heap (the stack of the
+//!    backend, its globals or the host), they and everything they reach are alive.
+//! 3. The rest is garbage, they are cleared which drops the references that form the cycles.
+//!
+//! The methods of lists and maps hold the collection they are bound to like bound methods hold
+//! their instance, so the collector sees those references. References that it can not see (like
+//! the ones captured by the closures of native functions) only keep objects alive, they never
+//! cause live objects to be collected.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::mem;
+use std::rc::{Rc, Weak};
+
+use crate::map::Map;
+
+/// Represents a value that can hold references to tracked objects.
+pub trait Trace {
+    /// Reports every reference to a heap object that the value holds to the tracer.
+    fn trace(&self, tracer: &mut Tracer);
+}
+
+/// Represents an object that is tracked by the [`Heap`].
+pub trait Collect: Trace {
+    /// Drops the references to other objects to break the cycles that the object is part of.
+    fn clear(&self);
+}
+
+/// Collects the references that an object holds.
+#[derive(Debug, Default)]
+pub struct Tracer {
+    edges: Vec<usize>,
+    pinned: bool,
+}
+
+impl Tracer {
+    /// Records a reference to the given object.
+    pub fn edge<T: ?Sized>(&mut self, object: &Rc<T>) {
+        self.edges.push(address(object));
+    }
+
+    /// Marks the traced object as alive.
+    /// This is used when the references of an object can not be inspected because it is being
+    /// modified.
+    pub fn pin(&mut self) {
+        self.pinned = true;
+    }
+}
+
+/// The minimum number of allocations between two automatic collections.
+const THRESHOLD: usize = 1024;
+
+/// Tracks the objects of a backend that can form reference cycles and collects the unreachable
+/// ones. An optional cap limits the number of objects that can be alive at the same time.
+pub struct Heap {
+    objects: Vec<Weak<dyn Collect>>,
+    /// The number of objects that survived the last collection.
+    survivors: usize,
+    /// The number of objects that were tracked since the last collection.
+    allocations: usize,
+    cap: Option<usize>,
+    /// The error message of the exceeded cap, it stays set until the heap is restarted.
+    exceeded: Option<String>,
+}
+
+impl Heap {
+    pub fn new(cap: Option<usize>) -> Self {
+        Heap { objects: vec![], survivors: 0, allocations: 0, cap, exceeded: None }
+    }
+
+    /// Starts tracking the given object.
+    /// Tracking an object might trigger a collection, the object itself is alive since the caller
+    /// holds a reference to it.
+    pub fn track<T: Collect + 'static>(&mut self, object: &Rc<T>) {
+        let object: Weak<T> = Rc::downgrade(object);
+        let object: Weak<dyn Collect> = object;
+        self.objects.push(object);
+        self.allocations += 1;
+
+        let alive = self.survivors + self.allocations;
+        if self.allocations >= THRESHOLD.max(self.survivors) || self.cap.is_some_and(|cap| alive > cap) {
+            self.collect();
+        }
+    }
+
+    /// Collects the objects that are only reachable through reference cycles and returns how
+    /// many of them were collected.
+    pub fn collect(&mut self) -> usize {
+        let mut objects: Vec<Rc<dyn Collect>> = vec![];
+        let mut index: HashMap<usize, usize> = HashMap::new();
+
+        for object in self.objects.iter().filter_map(Weak::upgrade) {
+            index.entry(address(&object)).or_insert_with(|| {
+                objects.push(object);
+                objects.len() - 1
+            });
+        }
+
+        // The upgraded reference in `objects` is not counted.
+        let mut references: Vec<usize> = objects.iter().map(|object| Rc::strong_count(object) - 1).collect();
+        let mut children: Vec<Vec<usize>> = vec![vec![]; objects.len()];
+        let mut alive = vec![false; objects.len()];
+
+        for (i, object) in objects.iter().enumerate() {
+            let mut tracer = Tracer::default();
+            object.trace(&mut tracer);
+            alive[i] = tracer.pinned;
+
+            for edge in tracer.edges {
+                if let Some(&child) = index.get(&edge) {
+                    references[child] = references[child].saturating_sub(1);
+                    children[i].push(child);
+                }
+            }
+        }
+
+        let mut pending: Vec<usize> = (0..objects.len())
+            .filter(|&i| alive[i] || references[i] > 0)
+            .collect();
+
+        pending.iter().for_each(|&i| alive[i] = true);
+        while let Some(i) = pending.pop() {
+            for &child in &children[i] {
+                if !alive[child] {
+                    alive[child] = true;
+                    pending.push(child);
+                }
+            }
+        }
+
+        let mut collected = 0;
+        for (object, alive) in objects.iter().zip(&alive) {
+            if !alive {
+                object.clear();
+                collected += 1;
+            }
+        }
+
+        // Objects that were tracked more than once are only kept once.
+        self.objects = objects.iter().map(Rc::downgrade).collect();
+        drop(objects);
+        self.objects.retain(|object| object.strong_count() > 0);
+        self.survivors = self.objects.len();
+        self.allocations = 0;
+
+        if let Some(cap) = self.cap {
+            if self.survivors > cap {
+                self.exceeded = Some(format!("Allocation cap of {cap} objects exceeded"));
+            }
+        }
+
+        collected
+    }
+
+    /// Returns the number of tracked objects, including the ones that have not been collected
+    /// yet.
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// Returns if no objects are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// Returns an error message if the allocation cap was exceeded during the current run.
+    pub fn check(&self) -> Result<(), String> {
+        match &self.exceeded {
+            Some(message) => Err(message.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns if the allocation cap was exceeded during the current run.
+    pub fn is_exceeded(&self) -> bool {
+        self.exceeded.is_some()
+    }
+
+    /// Forgets the exceeded cap at the beginning of a run.
+    pub fn restart(&mut self) {
+        self.exceeded = None;
+    }
+}
+
+impl std::fmt::Debug for Heap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Heap")
+            .field("objects", &self.objects.len())
+            .field("survivors", &self.survivors)
+            .field("cap", &self.cap)
+            .finish()
+    }
+}
+
+impl<V: Trace> Trace for RefCell<Vec<V>> {
+    fn trace(&self, tracer: &mut Tracer) {
+        match self.try_borrow() {
+            Ok(elements) => elements.iter().for_each(|element| element.trace(tracer)),
+            Err(_) => tracer.pin(),
+        }
+    }
+}
+
+impl<V: Trace> Collect for RefCell<Vec<V>> {
+    fn clear(&self) {
+        // The elements are dropped after the borrow ends.
+        let _elements = self.try_borrow_mut().map(|mut elements| mem::take(&mut *elements));
+    }
+}
+
+impl<V: Trace + Clone> Trace for RefCell<Map<V>> {
+    fn trace(&self, tracer: &mut Tracer) {
+        match self.try_borrow() {
+            Ok(map) => map.iter().for_each(|(_, value)| value.trace(tracer)),
+            Err(_) => tracer.pin(),
+        }
+    }
+}
+
+impl<V: Trace + Clone> Collect for RefCell<Map<V>> {
+    fn clear(&self) {
+        let _entries = self.try_borrow_mut().map(|mut map| mem::take(&mut *map));
+    }
+}
+
+fn address<T: ?Sized>(object: &Rc<T>) -> usize {
+    Rc::as_ptr(object) as *const () as usize
+}
@@ -1,70 +1,560 @@
 use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fs;
+use std::mem;
+use std::ops::ControlFlow;
+use std::path::Path;
 use std::rc::Rc;
 use std::cell::RefCell;
 
 use crate::class::Class;
+use crate::config::{Capability, InterpreterConfig, Limits, Meter};
 use crate::environment::Environment;
-use crate::error::{self, Error, ReturnType, RuntimeError, ReturnError, BreakError};
+use crate::error::{self, Diagnostic, Error, ParseError, ReturnType, RuntimeError, ReturnError, BreakError, ContinueError, TraceFrame};
 use crate::expr::{Expr, ExprVisitor};
 use crate::function::{NativeFunction, Function};
+use crate::gc::Heap;
+use crate::module::{file_name, Loader, Module};
 use crate::object::{Object, Callable};
-use crate::stmt::{Stmt, StmtVisitor};
-use crate::token::{Type, Token};
+use crate::parser::Parser;
+use crate::resolver::{Resolver, Slot};
+use crate::scanner::Scanner;
+use crate::stmt::{FunctionData, Stmt, StmtVisitor};
+use crate::token::{Location, Type, Token};
 use crate::literal::Literal;
+use crate::list;
+use crate::map::{self, Map};
+use crate::Termination;
+
+/// The global variables of a file. Every module has its own globals which are shared with the
+/// functions declared in it.
+pub type Globals = Rc<RefCell<HashMap<String, Object>>>;
+
+/// The amount of native stack that has to be left before a call grows the stack.
+const STACK_RED_ZONE: usize = 128 * 1024;
+/// The size of the native stack segments that are allocated when the stack grows.
+const STACK_GROWTH: usize = 4 * 1024 * 1024;
+
+/// Observes the execution of an [`Interpreter`], which is how the
+/// [`Debugger`](crate::debugger::Debugger) pauses scripts.
+pub trait Hook {
+    /// Called before every statement that has a location in the source code is executed.
+    /// Breaking stops the run with an error that can not be caught.
+    fn statement(&mut self, interpreter: &mut Interpreter, stmt: &Stmt) -> ControlFlow<()>;
+}
+
+/// Represents a function call that is being executed.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// The name of the function, method or class that was called.
+    pub name: String,
+    /// The closing parenthesis of the call, or the path of the import statement that executes a
+    /// module.
+    pub call: Token,
+    /// The path of the file that the call is in.
+    pub file: Option<Rc<Path>>,
+    /// The globals of the file that the call is in.
+    pub globals: Globals,
+    /// The innermost local scope of the caller at the time of the call.
+    pub environment: Option<Rc<RefCell<Environment>>>,
+}
 
 pub struct Interpreter<'w> {
     // Interior mutability with multiple owners
-    environment: Rc<RefCell<Environment>>,
-    globals: Rc<RefCell<Environment>>,
-    locals: HashMap<Token, usize>,
+    /// The innermost local scope, or `None` while executing top-level code.
+    environment: Option<Rc<RefCell<Environment>>>,
+    /// The globals of the file that is being executed.
+    globals: Globals,
+    /// The path of the file that is being executed, if it is run from a file.
+    file: Option<Rc<Path>>,
+    /// The native functions that every module starts with.
+    natives: HashMap<String, Object>,
+    modules: Loader,
+    config: InterpreterConfig,
+    meter: Meter,
+    /// The environments and objects that can form reference cycles.
+    heap: Heap,
+    /// The function calls that are being executed, the innermost one is the last.
+    frames: Vec<Frame>,
+    hook: Option<Box<dyn Hook + 'w>>,
+    /// If true, the hook stopped the current run.
+    stopped: bool,
+    /// The code that the script exited the current run with (if it did).
+    exit: Option<i32>,
     writer: Box<dyn std::io::Write + 'w>,
 }
 
 impl<'w> Interpreter<'w> {
+    /// Creates a new interpreter that grants every capability to the scripts.
     pub fn new<W: std::io::Write>(writer: &'w mut W) -> Self {
-        let globals = Rc::new(RefCell::new(Environment::default()));
-
-        NativeFunction::get_globals().iter().for_each(|native| {
-            globals.borrow_mut().define(&native.name.lexeme, Object::from(native.clone()));
-        });
+        Self::with_config(writer, InterpreterConfig::default())
+    }
 
-        Interpreter {
-            environment: Rc::clone(&globals),
-            globals: Rc::clone(&globals),
-            locals: HashMap::new(),
+    /// Creates a new interpreter whose natives are installed according to the given
+    /// configuration.
+    pub fn with_config<W: std::io::Write>(writer: &'w mut W, config: InterpreterConfig) -> Self {
+        let mut interpreter = Interpreter {
+            environment: None,
+            globals: Rc::new(RefCell::new(HashMap::new())),
+            file: None,
+            natives: HashMap::new(),
+            modules: Loader::new(),
+            config: InterpreterConfig::default(),
+            meter: Meter::new(Limits::default()),
+            heap: Heap::new(None),
+            frames: vec![],
+            hook: None,
+            stopped: false,
+            exit: None,
             writer: Box::new(writer),
+        };
+
+        interpreter.set_config(config);
+        interpreter
+    }
+
+    /// Replaces the configuration and reinstalls the natives and the native modules according to
+    /// it.
+    pub fn set_config(&mut self, config: InterpreterConfig) {
+        for native in NativeFunction::get_globals(&config) {
+            self.define_native(native);
         }
+
+        let modules = config.stdlib.modules()
+            .iter()
+            .map(|module| module.module(&config, |function| Object::from(NativeFunction::from(function))))
+            .collect();
+
+        self.modules.set_natives(modules);
+        self.meter = Meter::new(config.limits);
+        self.heap = Heap::new(config.limits.allocations);
+        self.config = config;
+    }
+
+    /// Installs the hook that is called before every statement.
+    pub fn set_hook<H: Hook + 'w>(&mut self, hook: H) {
+        self.hook = Some(Box::new(hook));
+    }
+
+    /// Returns the number of function calls that are being executed.
+    pub fn depth(&self) -> usize {
+        self.frames.len()
     }
 
-    pub fn interpret(&mut self, statements: &Vec<Stmt>) {
+    /// Returns the function calls that are being executed, the innermost one is the last.
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// Returns the path of the file that is being executed, if it is run from a file.
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Returns the globals of the file that is being executed.
+    pub fn globals(&self) -> &Globals {
+        &self.globals
+    }
+
+    /// Returns the innermost local scope, or `None` while executing top-level code.
+    pub fn environment(&self) -> Option<&Rc<RefCell<Environment>>> {
+        self.environment.as_ref()
+    }
+
+    /// Sets the file of the script that is being run, imports are resolved relative to it.
+    pub fn set_script(&mut self, path: &Path) {
+        self.modules.set_script(path);
+        self.file = Some(Rc::from(fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())));
+    }
+
+    /// Forgets the file of the script, for code that is not run from a file.
+    pub fn clear_script(&mut self) {
+        self.modules.clear_script();
+        self.file = None;
+    }
+
+    /// Executes the given statements in order.
+    /// A runtime error aborts the top-level statement it occurred in but the execution continues
+    /// with the next one, unless a limit of the run was exceeded. Returns how the run ended with
+    /// all of its runtime errors, a script can exit after some of its statements failed.
+    pub fn interpret(&mut self, statements: &Vec<Stmt>) -> (Termination, Vec<RuntimeError>) {
+        let mut errors = vec![];
+        self.meter.restart();
+        self.heap.restart();
+        self.stopped = false;
+        self.exit = None;
+
         for statement in statements {
-            self.execute(statement).unwrap_or_else(|error| {
-                if let ReturnType::Error(error) = error {
-                    error.throw();
+            if let Err(ReturnType::Error(mut error)) = self.execute(statement) {
+                // Exiting is not an error, the run just ends.
+                if self.exit.is_some() {
+                    break;
                 }
-            });
+
+                if error.trace.is_empty() {
+                    error.trace = self.trace(Some(error.token.location));
+                }
+
+                errors.push(error);
+
+                if self.is_stopped() {
+                    break;
+                }
+            }
+        }
+
+        match self.exit {
+            Some(code) => (Termination::Exit(code), errors),
+            None => (Termination::Completed, errors),
         }
     }
  
     fn execute(&mut self, stmt: &Stmt) -> Result<(), ReturnType> {
+        self.check_limits(stmt.token())?;
+        self.run_hook(stmt)?;
         stmt.accept(self)
     }
 
-    pub fn resolve(&mut self, name: &Token, depth: usize) {
-        self.locals.insert(name.clone(), depth);
+    /// Calls the hook (if any) before the given statement.
+    /// The hook is taken out while it runs, so the statements it evaluates are not observed.
+    fn run_hook(&mut self, stmt: &Stmt) -> Result<(), ReturnType> {
+        let Some(token) = stmt.token() else {
+            return Ok(());
+        };
+
+        let Some(mut hook) = self.hook.take() else {
+            return Ok(());
+        };
+
+        let flow = hook.statement(self, stmt);
+        self.hook = Some(hook);
+
+        if flow.is_break() {
+            self.stopped = true;
+        }
+
+        match self.stopped {
+            true => Err(ReturnType::Error(RuntimeError {
+                token: token.clone(),
+                message: "Execution was stopped".to_string(),
+                help: None,
+                trace: vec![],
+                value: None,
+            })),
+            false => Ok(()),
+        }
+    }
+
+    /// Counts a statement or expression against the limits of the run.
+    /// An exceeded limit is reported at the next statement or expression that has a token.
+    fn check_limits(&mut self, token: Option<&Token>) -> Result<(), ReturnType> {
+        let result = self.meter.tick().and_then(|_| self.heap.check());
+
+        match (result, token) {
+            (Err(message), Some(token)) => Err(ReturnType::Error(RuntimeError {
+                token: token.clone(),
+                message,
+                help: None,
+                trace: vec![],
+                value: None,
+            })),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns if a limit of the run was exceeded, the hook stopped it or the script exited.
+    fn is_stopped(&self) -> bool {
+        self.meter.is_exceeded() || self.heap.is_exceeded() || self.stopped || self.exit.is_some()
+    }
+
+    /// Ends the current run with the given exit code.
+    /// Returns the error that unwinds the run from the given call site, it can not be caught and
+    /// it is not reported.
+    pub fn exit(&mut self, code: i32, token: Token) -> RuntimeError {
+        self.exit = Some(code);
+
+        RuntimeError {
+            token,
+            message: format!("Exited with code {code}"),
+            help: None,
+            trace: vec![],
+            value: None,
+        }
+    }
+
+    /// Moves the given environment to the heap so that the cycles it is part of can be collected.
+    pub fn allocate(&mut self, environment: Environment) -> Rc<RefCell<Environment>> {
+        let environment = Rc::new(RefCell::new(environment));
+        self.heap.track(&environment);
+        environment
+    }
+
+    /// Tracks the given object if it is stored on the heap and can be part of a cycle.
+    pub fn track(&mut self, object: &Object) {
+        match object {
+            Object::Class(class) => self.heap.track(class),
+            Object::Instance(instance) => self.heap.track(instance),
+            Object::List(list) => self.heap.track(list),
+            Object::Map(map) => self.heap.track(map),
+            _ => (),
+        }
+    }
+
+    /// Collects the objects that are only reachable through reference cycles and returns how
+    /// many of them were collected.
+    pub fn collect_garbage(&mut self) -> usize {
+        self.heap.collect()
+    }
+
+    /// Forgets the global variables that were defined by the scripts, only the natives remain.
+    pub fn reset(&mut self) {
+        self.globals = Rc::new(RefCell::new(self.natives.clone()));
+        self.environment = None;
+    }
+
+    /// Defines a new variable with the given name and value in the global scope.
+    pub fn define_global(&mut self, name: &str, value: Object) {
+        self.globals.borrow_mut().insert(name.to_string(), value);
+    }
+
+    /// Defines a native function in the global scope of the script and of every module it
+    /// imports.
+    pub fn define_native(&mut self, native: NativeFunction) {
+        let name = native.name.lexeme.clone();
+        let native = Object::from(native);
+
+        self.natives.insert(name.clone(), native.clone());
+        self.define_global(&name, native);
+    }
+
+    /// Defines a new variable in the current scope.
+    /// Local variables only need their value since they are accessed by their slot.
+    fn define(&mut self, name: &str, value: Object) {
+        match &self.environment {
+            Some(environment) => environment.borrow_mut().define(name, value),
+            None => self.define_global(name, value),
+        }
+    }
+
+    fn local_environment(&self) -> &Rc<RefCell<Environment>> {
+        // Resolver only resolves variables that are declared in a local scope.
+        self.environment.as_ref().expect("resolved variable to be in a local scope")
     }
 
-    fn lookup_variable(&mut self, name: &Token) -> Result<Object, ReturnType> {
-        let variable = match self.locals.get(name) {
-            Some(distance) => self.environment.borrow().get_at(*distance, name),
-            None => self.globals.borrow().get(name),
+    /// Calls the given object with the given arguments after checking its arity.
+    /// The given token is used to report errors that occur at the call site.
+    pub fn call(&mut self, callee: Object, arguments: Vec<Object>, paren: &Token) -> Result<Object, RuntimeError> {
+        let arity = match &callee {
+            Object::Function(function) => function.arity(),
+            Object::NativeFunction(function) => function.arity(),
+            Object::Class(class) => class.borrow().arity(),
+            _ => {
+                return Err(RuntimeError {
+                    token: paren.clone(),
+                    message: "Can only call functions and classes".to_string(),
+                    help: None,
+                    trace: vec![],
+                    value: None,
+                });
+            }
         };
 
-        return match variable {
-            Ok(value) => Ok(value),
-            Err(error) => Err(ReturnType::Error(error)),
+        if !arity.accepts(arguments.len()) {
+            return Err(RuntimeError {
+                token: paren.clone(),
+                message: format!("Expected {} but got {}", arity, arguments.len()),
+                help: None,
+                trace: vec![],
+                value: None,
+            });
+        }
+
+        if self.frames.len() >= self.config.limits.call_depth {
+            return Err(RuntimeError {
+                token: paren.clone(),
+                message: "Stack overflow".to_string(),
+                help: None,
+                trace: vec![],
+                value: None,
+            });
+        }
+
+        // Calls recurse on the native stack, grow it on demand so that the call depth limit is
+        // reached before the native stack runs out.
+        let name = match &callee {
+            Object::Function(function) => function.name.lexeme.clone(),
+            Object::NativeFunction(function) => function.name.lexeme.clone(),
+            Object::Class(class) => class.borrow().name().to_string(),
+            _ => unreachable!(),
         };
+
+        let native = matches!(callee, Object::NativeFunction(_));
+        self.frames.push(Frame {
+            name,
+            call: paren.clone(),
+            file: self.file.clone(),
+            globals: Rc::clone(&self.globals),
+            environment: self.environment.clone(),
+        });
+        let mut result = stacker::maybe_grow(STACK_RED_ZONE, STACK_GROWTH, || {
+            self.invoke(callee, arguments, paren)
+        });
+
+        if let Err(error) = &mut result {
+            if native && error.trace.is_empty() {
+                error.trace = self.trace(None);
+            }
+        }
+        self.frames.pop();
+
+        result
+    }
+
+    fn invoke(&mut self, callee: Object, arguments: Vec<Object>, paren: &Token) -> Result<Object, RuntimeError> {
+        match callee {
+            Object::Function(function) => function.call(self, arguments),
+            Object::NativeFunction(function) => {
+                let result = function.call(self, arguments).map_err(|mut error| {
+                    // Native functions have no location in the source code,
+                    // report their errors at the call site instead.
+                    error.token = paren.clone();
+                    error
+                })?;
+
+                // Natives create lists and maps without access to the heap.
+                self.track(&result);
+                Ok(result)
+            },
+            Object::Class(class) => class.borrow().call(self, arguments),
+            _ => unreachable!(),
+        }
+    }
+
+    fn lookup_variable(&self, slot: Option<Slot>, name: &Token) -> Result<Object, ReturnType> {
+        if let Some(slot) = slot {
+            return Ok(self.local_environment().borrow().get_at(slot.depth, slot.index));
+        }
+
+        let value = self.globals.borrow().get(&name.lexeme).cloned();
+        match value {
+            Some(value) => Ok(value),
+            None => Err(ReturnType::Error(self.undefined_variable(name))),
+        }
+    }
+
+    /// Returns the error for the given variable that is not defined. The error suggests the
+    /// variable with the most similar name that is in scope.
+    fn undefined_variable(&self, name: &Token) -> RuntimeError {
+        let mut candidates: Vec<String> = self.globals.borrow().keys().cloned().collect();
+
+        let mut environment = self.environment.clone();
+        while let Some(current) = environment {
+            let current = current.borrow();
+            candidates.extend(current.variables().map(|(name, _)| name.to_string()));
+            environment = current.enclosing.clone();
+        }
+
+        RuntimeError {
+            token: name.clone(),
+            message: format!("Undefined variable '{}'", name.lexeme),
+            help: error::did_you_mean(&name.lexeme, candidates.iter().map(String::as_str)),
+            trace: vec![],
+            value: None,
+        }
+    }
+
+    /// Executes the body of a function in the given environment with the globals of the file
+    /// that the function was declared in.
+    /// Runtime errors that leave the function get the trace of the calls that led to them.
+    pub fn execute_function(
+        &mut self,
+        body: &Vec<Stmt>,
+        environment: Rc<RefCell<Environment>>,
+        globals: &Globals,
+        file: &Option<Rc<Path>>,
+    ) -> Result<(), ReturnType> {
+        let previous = mem::replace(&mut self.globals, Rc::clone(globals));
+        let previous_file = mem::replace(&mut self.file, file.clone());
+        let mut result = self.execute_block(body, environment);
+
+        if let Err(ReturnType::Error(error)) = &mut result {
+            if error.trace.is_empty() {
+                error.trace = self.trace(Some(error.token.location));
+            }
+        }
+
+        self.globals = previous;
+        self.file = previous_file;
+
+        result
+    }
+
+    /// Returns the calls that are being executed, the innermost one first. The innermost call is
+    /// at the given location of the current file, or is a native function if there is none.
+    fn trace(&self, location: Option<Location>) -> Vec<TraceFrame> {
+        let mut trace = vec![];
+        let mut file = location.and(self.file.clone());
+        let mut location = location;
+
+        for frame in self.frames.iter().rev() {
+            trace.push(TraceFrame { name: frame.name.clone(), file: file.map(|file| file_name(&file)), location });
+            file = frame.file.clone();
+            location = Some(frame.call.location);
+        }
+
+        trace.push(TraceFrame { name: "<script>".to_string(), file: file.map(|file| file_name(&file)), location });
+        trace
+    }
+
+    /// Returns the namespace object of the module that the given path refers to.
+    /// The module is executed the first time it is imported, in its own global scope.
+    fn import(&mut self, path: &Token) -> Result<Rc<Module>, RuntimeError> {
+        if let Some(module) = self.modules.native(path) {
+            return Ok(module);
+        }
+
+        self.config.check(Capability::Filesystem, &format!("import '{}'", path.lexeme))
+            .map_err(|message| RuntimeError { token: path.clone(), message, help: None, trace: vec![], value: None })?;
+
+        let file = self.modules.resolve(path)?;
+        if let Some(module) = self.modules.get(&file) {
+            return Ok(module);
+        }
+
+        let source = self.modules.load(&file, path)?;
+
+        // The module is executed like a call, so that it shows up in the traces of its errors.
+        self.frames.push(Frame {
+            name: "<script>".to_string(),
+            call: path.clone(),
+            file: self.file.clone(),
+            globals: Rc::clone(&self.globals),
+            environment: self.environment.clone(),
+        });
+        let previous_file = self.file.replace(Rc::from(file.as_path()));
+
+        let globals = mem::replace(&mut self.globals, Rc::new(RefCell::new(self.natives.clone())));
+        let mut result = source.statements.iter().try_for_each(|statement| self.execute(statement));
+        let module_globals = mem::replace(&mut self.globals, globals);
+
+        if let Err(ReturnType::Error(error)) = &mut result {
+            if error.trace.is_empty() {
+                error.trace = self.trace(Some(error.token.location));
+            }
+        }
+        self.file = previous_file;
+        self.frames.pop();
+
+        let module = result.is_ok().then(|| {
+            Rc::new(Module::new(source.name.clone(), module_globals, source.exports()))
+        });
+        self.modules.finish(module.clone());
+
+        match result {
+            Ok(()) => Ok(module.expect("module to be executed")),
+            Err(ReturnType::Error(error)) => Err(error),
+            Err(_) => unreachable!("top-level code can only fail with errors"),
+        }
     }
 
     pub fn execute_block(
@@ -73,7 +563,7 @@ impl<'w> Interpreter<'w> {
         environment: Rc<RefCell<Environment>>
     ) -> Result<(), ReturnType> {
         let previous = self.environment.clone();
-        self.environment = environment;
+        self.environment = Some(environment);
 
         for statement in statements {
             if let Err(return_type) = self.execute(statement) {
@@ -88,8 +578,67 @@ impl<'w> Interpreter<'w> {
     }
 
     fn evaluate(&mut self, expr: &Expr) -> Result<Object, ReturnType> {
+        self.check_limits(expr.token())?;
         expr.accept(self)
     }
+
+    /// Evaluates the given source code as an expression in the given local scope (or the global
+    /// scope if `None`) with the given globals.
+    /// This is used by the [`debugger`](crate::debugger) to inspect a paused script, so local
+    /// variables are resolved by their names instead of ahead of time.
+    pub fn evaluate_source(
+        &mut self,
+        source: &str,
+        environment: Option<Rc<RefCell<Environment>>>,
+        globals: Globals,
+    ) -> Result<Object, Vec<Diagnostic>> {
+        let source = format!("{};\n", source.trim().trim_end_matches(';'));
+
+        let mut scanner = Scanner::new(&source);
+        let tokens = scanner.scan_tokens();
+        if !scanner.errors().is_empty() {
+            return Err(scanner.errors().iter().map(Error::to_diagnostic).collect());
+        }
+
+        let first = tokens[0].clone();
+        let mut parser = Parser::new(tokens);
+        let statements = parser.parse();
+        if !parser.errors().is_empty() {
+            return Err(parser.errors().iter().map(Error::to_diagnostic).collect());
+        }
+
+        let [Stmt::Expression(data)] = statements.as_slice() else {
+            let error = ParseError { token: first, message: "Expected an expression".to_string(), help: None };
+            return Err(vec![error.to_diagnostic()]);
+        };
+
+        let mut scopes = vec![];
+        let mut scope = environment.clone();
+        while let Some(current) = scope {
+            let current = current.borrow();
+            scopes.push(current.variables().map(|(name, _)| name.to_string()).collect());
+            scope = current.enclosing.clone();
+        }
+        scopes.reverse();
+
+        let mut resolver = Resolver::with_scopes(scopes);
+        resolver.resolve(&statements);
+        if !resolver.errors().is_empty() {
+            return Err(resolver.errors().iter().map(Error::to_diagnostic).collect());
+        }
+
+        let previous = mem::replace(&mut self.environment, environment);
+        let previous_globals = mem::replace(&mut self.globals, globals);
+        let result = self.evaluate(&data.expr);
+        self.environment = previous;
+        self.globals = previous_globals;
+
+        match result {
+            Ok(value) => Ok(value),
+            Err(ReturnType::Error(error)) => Err(vec![error.to_diagnostic()]),
+            Err(_) => unreachable!("expressions can only fail with errors"),
+        }
+    }
 }
 
 impl<'w> Default for Interpreter<'w> {
@@ -139,6 +688,9 @@ impl<'w> ExprVisitor<Result<Object, ReturnType>> for Interpreter<'w> {
             return Err(ReturnType::Error(RuntimeError {
                 token: unary.operator.clone(),
                 message: error_message,
+                help: None,
+                trace: vec![],
+                value: None,
             }));
         }
     }
@@ -182,6 +734,9 @@ impl<'w> ExprVisitor<Result<Object, ReturnType>> for Interpreter<'w> {
             return Err(ReturnType::Error(RuntimeError {
                 token: binary.operator.clone(),
                 message: error_message,
+                help: None,
+                trace: vec![],
+                value: None,
             }));
         }
     }
@@ -202,63 +757,7 @@ impl<'w> ExprVisitor<Result<Object, ReturnType>> for Interpreter<'w> {
             .map(|expr| self.evaluate(expr))
             .collect::<Result<Vec<Object>, ReturnType>>()?;
 
-        match callee {
-            Object::Function(function) => {
-                if arguments.len() != function.arity() {
-                    return Err(ReturnType::Error(RuntimeError {
-                        token: call.paren.clone(),
-                        message: format!("Expected {} arguments but got {}", function.arity(), arguments.len()),
-                    }));
-                }
-
-                return match function.call(self, arguments) {
-                    Ok(value) => Ok(value),
-                    Err(error) => {
-                        // TODO: look into implementing call stack on error
-                        // error.token = call.paren.clone();
-                        return Err(ReturnType::Error(error));
-                    }
-                };
-            },
-            Object::NativeFunction(function) => {
-                if arguments.len() != function.arity() {
-                    return Err(ReturnType::Error(RuntimeError {
-                        token: call.paren.clone(),
-                        message: format!("Expected {} arguments but got {}", function.arity(), arguments.len()),
-                    }));
-                }
-
-                return match function.call(self, arguments) {
-                    Ok(result) => Ok(result),
-                    Err(error) => {
-                        // error.token = call.paren.clone();
-                        return Err(ReturnType::Error(error));
-                    }
-                };
-            },
-            Object::Class(class) => {
-                if arguments.len() != class.borrow().arity() {
-                    return Err(ReturnType::Error(RuntimeError {
-                        token: call.paren.clone(),
-                        message: format!("Expected {} arguments but got {}", class.borrow().arity(), arguments.len()),
-                    }));
-                }
-
-                return match class.borrow().call(self, arguments) {
-                    Ok(result) => Ok(result),
-                    Err(error) => {
-                        // error.token = call.paren.clone();
-                        return Err(ReturnType::Error(error));
-                    }
-                };
-            },
-            _ => {
-                return Err(ReturnType::Error(RuntimeError {
-                    token: call.paren.clone(),
-                    message: "Can only call functions and classes".to_string(),
-                }));
-            }
-        }
+        self.call(callee, arguments, &call.paren).map_err(ReturnType::Error)
     }
 
     fn visit_grouping_expr(&mut self, expr: &Expr) -> Result<Object, ReturnType> {
@@ -268,20 +767,24 @@ impl<'w> ExprVisitor<Result<Object, ReturnType>> for Interpreter<'w> {
 
     fn visit_variable_expr(&mut self, expr: &Expr) -> Result<Object, ReturnType> {
         let Expr::Variable(variable) = expr else { unreachable!() };
-        self.lookup_variable(&variable.name)
+        self.lookup_variable(variable.slot.get(), &variable.name)
     }
 
     fn visit_assign_expr(&mut self, expr: &Expr) -> Result<Object, ReturnType> {
         let Expr::Assign(assign) = expr else { unreachable!() };
         let value = self.evaluate(&assign.value)?;
 
-        if let Some(distance) = self.locals.get(&assign.name) {
-            self.environment.borrow_mut().assign_at(*distance, &assign.name, value.clone());
-        } else {
-            self.globals.borrow_mut().assign(&assign.name, value.clone());
+        if let Some(slot) = assign.slot.get() {
+            self.local_environment().borrow_mut().assign_at(slot.depth, slot.index, value.clone());
+            return Ok(value);
         }
 
-        Ok(value)
+        if let Some(variable) = self.globals.borrow_mut().get_mut(&assign.name.lexeme) {
+            *variable = value.clone();
+            return Ok(value);
+        }
+
+        Err(ReturnType::Error(self.undefined_variable(&assign.name)))
     }
 
     fn visit_get_expr(&mut self, expr: &Expr) -> Result<Object, ReturnType> {
@@ -289,15 +792,34 @@ impl<'w> ExprVisitor<Result<Object, ReturnType>> for Interpreter<'w> {
         let object = self.evaluate(&get.object)?;
 
         if let Object::Instance(ref instance) = object {
-            return match instance.borrow().get(&get.name, &object) {
+            return match instance.borrow().get(&get.name, &object, self) {
                 Ok(value) => Ok(value),
                 Err(error) => Err(ReturnType::Error(error)),
             }
         }
 
+        if let Object::List(_) = object {
+            return list::get_method(&object, &get.name).map_err(ReturnType::Error);
+        }
+
+        if let Object::Map(_) = object {
+            return map::get_method(&object, &get.name).map_err(ReturnType::Error);
+        }
+
+        if let Object::Module(ref module) = object {
+            return module.get(&get.name).map_err(ReturnType::Error);
+        }
+
+        if let Object::Error(ref error) = object {
+            return error.get(&get.name).map_err(ReturnType::Error);
+        }
+
         return Err(ReturnType::Error(RuntimeError {
             token: get.name.clone(),
             message: "Only instances have properties".to_owned(),
+            help: None,
+            trace: vec![],
+            value: None,
         }));
     }
 
@@ -314,6 +836,9 @@ impl<'w> ExprVisitor<Result<Object, ReturnType>> for Interpreter<'w> {
             return Err(ReturnType::Error(RuntimeError {
                 token: set.name.clone(),
                 message: "Only instances can have fields".to_string(),
+                help: None,
+                trace: vec![],
+                value: None,
             }));
         }
     }
@@ -321,7 +846,7 @@ impl<'w> ExprVisitor<Result<Object, ReturnType>> for Interpreter<'w> {
     fn visit_this_expr(&mut self, expr: &Expr) -> Result<Object, ReturnType> {
         let Expr::This(this) = expr else { unreachable!() };
 
-        self.lookup_variable(&this.keyword)
+        self.lookup_variable(this.slot.get(), &this.keyword)
     }
 
     fn visit_super_expr(&mut self, expr: &Expr) -> Result<Object, ReturnType> {
@@ -329,32 +854,138 @@ impl<'w> ExprVisitor<Result<Object, ReturnType>> for Interpreter<'w> {
 
         // Resolver would have catched if super was used incorrectly.
         // It is okay to unwrap here.
-        let distance = self.locals.get(&super_expr.keyword).unwrap();
-        let superclass = match self.environment.borrow().get_at(*distance, &super_expr.keyword) {
-            Ok(value) => Ok(value),
-            Err(error) => Err(ReturnType::Error(error)),
-        }?;
+        let slot = super_expr.slot.get().unwrap();
+        let environment = self.local_environment().borrow();
+        let superclass = environment.get_at(slot.depth, slot.index);
 
-        let object = match self.environment.borrow().get_at(distance - 1, &Token::from("this")) {
-            Ok(value) => Ok(value),
-            Err(error) => Err(ReturnType::Error(error)),
-        }?;
+        // 'this' is always the only variable in the scope right inside 'super'.
+        let object = environment.get_at(slot.depth - 1, 0);
+        drop(environment);
 
         if let Object::Class(superclass) = superclass {
             let method = superclass.borrow().get_method(&super_expr.method.lexeme);
 
             if let Some(mut method) = method {
-                return Ok(Object::from(method.bind(object)));
+                return Ok(Object::from(method.bind(object, self)));
             } else {
+                let methods = superclass.borrow().method_names();
                 return Err(ReturnType::Error(RuntimeError {
                     token: super_expr.method.clone(),
-                    message: format!("Undefined property '{}'", super_expr.method.lexeme)
+                    message: format!("Undefined property '{}'", super_expr.method.lexeme),
+                    help: error::did_you_mean(&super_expr.method.lexeme, methods.iter().map(String::as_str)),
+                    trace: vec![],
+                    value: None,
                 }));
             }
         } else {
             unreachable!();
         }
     }
+
+    fn visit_lambda_expr(&mut self, expr: &Expr) -> Result<Object, ReturnType> {
+        let Expr::Lambda(lambda) = expr else { unreachable!() };
+
+        let stmt = Stmt::Function(FunctionData {
+            name: lambda.name(),
+            params: lambda.params.clone(),
+            body: lambda.body.clone(),
+        });
+
+        Ok(Object::from(Function::new(stmt, self.environment.clone(), Rc::clone(&self.globals), self.file.clone(), false)))
+    }
+
+    fn visit_list_expr(&mut self, expr: &Expr) -> Result<Object, ReturnType> {
+        let Expr::List(list) = expr else { unreachable!() };
+
+        let elements = list.elements
+            .iter()
+            .map(|expr| self.evaluate(expr))
+            .collect::<Result<Vec<Object>, ReturnType>>()?;
+
+        let list = Object::from(elements);
+        self.track(&list);
+        Ok(list)
+    }
+
+    fn visit_map_expr(&mut self, expr: &Expr) -> Result<Object, ReturnType> {
+        let Expr::Map(map_expr) = expr else { unreachable!() };
+
+        let mut map = Map::default();
+        for (key, value) in &map_expr.entries {
+            let key = self.evaluate(key)?;
+            let key = map::key(&key, &map_expr.brace).map_err(ReturnType::Error)?;
+            let value = self.evaluate(value)?;
+            map.insert(key, value);
+        }
+
+        let map = Object::from(map);
+        self.track(&map);
+        Ok(map)
+    }
+
+    fn visit_index_expr(&mut self, expr: &Expr) -> Result<Object, ReturnType> {
+        let Expr::Index(index) = expr else { unreachable!() };
+
+        let object = self.evaluate(&index.object)?;
+        let key = self.evaluate(&index.index)?;
+
+        if let Object::List(list) = object {
+            let list = list.borrow();
+            let position = list::index(&list, &key, &index.bracket).map_err(ReturnType::Error)?;
+            return Ok(list[position].clone());
+        }
+
+        if let Object::Map(map) = object {
+            let key = map::key(&key, &index.bracket).map_err(ReturnType::Error)?;
+            return match map.borrow().get(&key) {
+                Some(value) => Ok(value.clone()),
+                None => Err(ReturnType::Error(RuntimeError {
+                    token: index.bracket.clone(),
+                    message: format!("Undefined key '{key}'"),
+                    help: None,
+                    trace: vec![],
+                    value: None,
+                })),
+            };
+        }
+
+        Err(ReturnType::Error(RuntimeError {
+            token: index.bracket.clone(),
+            message: format!("Cannot index into {} type", object.type_str()),
+            help: None,
+            trace: vec![],
+            value: None,
+        }))
+    }
+
+    fn visit_index_set_expr(&mut self, expr: &Expr) -> Result<Object, ReturnType> {
+        let Expr::IndexSet(index_set) = expr else { unreachable!() };
+
+        let object = self.evaluate(&index_set.object)?;
+        let key = self.evaluate(&index_set.index)?;
+        let value = self.evaluate(&index_set.value)?;
+
+        if let Object::List(list) = object {
+            let mut list = list.borrow_mut();
+            let position = list::index(&list, &key, &index_set.bracket).map_err(ReturnType::Error)?;
+            list[position] = value.clone();
+            return Ok(value);
+        }
+
+        if let Object::Map(map) = object {
+            let key = map::key(&key, &index_set.bracket).map_err(ReturnType::Error)?;
+            map.borrow_mut().insert(key, value.clone());
+            return Ok(value);
+        }
+
+        Err(ReturnType::Error(RuntimeError {
+            token: index_set.bracket.clone(),
+            message: format!("Cannot index into {} type", object.type_str()),
+            help: None,
+            trace: vec![],
+            value: None,
+        }))
+    }
 }
 
 impl<'w> StmtVisitor<Result<(), ReturnType>> for Interpreter<'w> {
@@ -368,9 +999,15 @@ impl<'w> StmtVisitor<Result<(), ReturnType>> for Interpreter<'w> {
     fn visit_function_stmt(&mut self, stmt: &Stmt) -> Result<(), ReturnType> {
         let Stmt::Function(_) = stmt else { unreachable!() };
 
-        let function = Function::new(stmt.to_owned(), Rc::clone(&self.environment), false);
+        let function = Function::new(
+            stmt.to_owned(),
+            self.environment.clone(),
+            Rc::clone(&self.globals),
+            self.file.clone(),
+            false
+        );
 
-        self.environment.borrow_mut().define(&function.name.lexeme.clone(), Object::from(function));
+        self.define(&function.name.lexeme.clone(), Object::from(function));
 
         Ok(())
     }
@@ -390,11 +1027,6 @@ impl<'w> StmtVisitor<Result<(), ReturnType>> for Interpreter<'w> {
         let Stmt::Print(data) = stmt else { unreachable!() };
         let value = self.evaluate(&data.expr)?;
 
-        // Make sure evaluate didn't throw an error
-        if error::did_error() {
-            return Ok(());
-        }
-
         writeln!(self.writer, "{value}").expect("writer to not fail on write");
 
         Ok(())
@@ -413,9 +1045,79 @@ impl<'w> StmtVisitor<Result<(), ReturnType>> for Interpreter<'w> {
     }
 
     fn visit_break_stmt(&mut self, stmt: &Stmt) -> Result<(), ReturnType> {
-        let Stmt::Break(_) = stmt else { unreachable!() };
+        let Stmt::Break(data) = stmt else { unreachable!() };
+
+        Err(ReturnType::Break(BreakError { label: data.label.clone() }))
+    }
+
+    fn visit_throw_stmt(&mut self, stmt: &Stmt) -> Result<(), ReturnType> {
+        let Stmt::Throw(data) = stmt else { unreachable!() };
+
+        // Rethrowing a caught error keeps its original message and location.
+        let error = match self.evaluate(&data.value)? {
+            Object::Error(error) => (*error).clone(),
+            value => RuntimeError {
+                token: data.keyword.clone(),
+                message: value.to_string(),
+                help: None,
+                trace: vec![],
+                value: Some(Rc::new(value)),
+            },
+        };
+
+        Err(ReturnType::Error(error))
+    }
+
+    fn visit_try_stmt(&mut self, stmt: &Stmt) -> Result<(), ReturnType> {
+        let Stmt::Try(data) = stmt else { unreachable!() };
+
+        let environment = self.allocate(Environment::new(self.environment.clone()));
+        let mut result = self.execute_block(&data.body, environment);
+
+        // Exceeding the instruction budget, the timeout or the allocation cap stops the run, it
+        // can not be caught. The same goes for runs that were stopped by the hook.
+        if self.is_stopped() {
+            return result;
+        }
+
+        if let (Err(ReturnType::Error(error)), Some(catch)) = (&result, &data.catch) {
+            let mut environment = Environment::new(self.environment.clone());
+            environment.define(&catch.name.lexeme, Object::Error(Rc::new(error.clone())));
+            let environment = self.allocate(environment);
+            result = self.execute_block(&catch.body, environment);
+        }
+
+        // The finally clause runs no matter how the body exited but its own exit takes precedence.
+        if let Some(finally) = &data.finally {
+            let environment = self.allocate(Environment::new(self.environment.clone()));
+            self.execute_block(finally, environment)?;
+        }
 
-        Err(ReturnType::Break(BreakError {}))
+        result
+    }
+
+    fn visit_import_stmt(&mut self, stmt: &Stmt) -> Result<(), ReturnType> {
+        let Stmt::Import(data) = stmt else { unreachable!() };
+
+        let module = self.import(&data.path).map_err(ReturnType::Error)?;
+
+        match &data.alias {
+            Some(alias) => self.define(&alias.lexeme, Object::Module(module)),
+            None => {
+                for name in &data.names {
+                    let value = module.get(name).map_err(ReturnType::Error)?;
+                    self.define(&name.lexeme, value);
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    fn visit_continue_stmt(&mut self, stmt: &Stmt) -> Result<(), ReturnType> {
+        let Stmt::Continue(data) = stmt else { unreachable!() };
+
+        Err(ReturnType::Continue(ContinueError { label: data.label.clone() }))
     }
 
     fn visit_var_stmt(&mut self, stmt: &Stmt) -> Result<(), ReturnType> {
@@ -425,7 +1127,7 @@ impl<'w> StmtVisitor<Result<(), ReturnType>> for Interpreter<'w> {
             None => Object::from(Literal::Null),
         };
 
-        self.environment.borrow_mut().define(&data.name.lexeme, value);
+        self.define(&data.name.lexeme, value);
 
         Ok(())
     }
@@ -433,11 +1135,20 @@ impl<'w> StmtVisitor<Result<(), ReturnType>> for Interpreter<'w> {
     fn visit_while_stmt(&mut self, stmt: &Stmt) -> Result<(), ReturnType> {
         let Stmt::While(data) = stmt else { unreachable!() };
         while self.evaluate(&data.condition)?.as_bool().is_some_and(|x| x) {
+            // Count every iteration, the condition and the body might have no token to report an
+            // exceeded limit at.
+            self.check_limits(Some(&data.keyword))?;
+
             match self.execute(&data.body) {
-                Err(ReturnType::Break(_)) => break,
+                Err(ReturnType::Break(err)) if data.is_target(&err.label) => break,
+                Err(ReturnType::Continue(err)) if data.is_target(&err.label) => {},
                 Err(err)=> return Err(err),
                 _ => {},
             }
+
+            if let Some(increment) = &data.increment {
+                self.evaluate(increment)?;
+            }
         }
 
         Ok(())
@@ -445,10 +1156,8 @@ impl<'w> StmtVisitor<Result<(), ReturnType>> for Interpreter<'w> {
 
     fn visit_block_stmt(&mut self, stmt: &Stmt) -> Result<(), ReturnType> {
         let Stmt::Block(data) = stmt else { unreachable!() };
-        self.execute_block(
-            &data.statements,
-            Rc::new(RefCell::new(Environment::new(Some(Rc::clone(&self.environment)))))
-        )
+        let environment = self.allocate(Environment::new(self.environment.clone()));
+        self.execute_block(&data.statements, environment)
     }
 
     fn visit_class_stmt(&mut self, stmt: &Stmt) -> Result<(), ReturnType> {
@@ -467,18 +1176,19 @@ impl<'w> StmtVisitor<Result<(), ReturnType>> for Interpreter<'w> {
                         // This is reporting the lexeme of the class name,
                         // it is non-trivial to get the superclass name.
                         token: data.name.clone(),
-                        message: "Superclass must be a class".to_string()
+                        message: "Superclass must be a class".to_string(),
+                        help: None,
+                        trace: vec![],
+                        value: None,
                     }));
                 },
             }
         }
 
-        self.environment.borrow_mut().define(&data.name.lexeme, Object::Literal(Literal::Null));
-
         if let Some(ref superclass) = superclass {
-            let mut environment = Environment::new(Some(Rc::clone(&self.environment)));
+            let mut environment = Environment::new(self.environment.clone());
             environment.define("super", superclass.clone());
-            self.environment = Rc::new(RefCell::new(environment));
+            self.environment = Some(self.allocate(environment));
         }
 
         let mut methods: HashMap<String, Function> = HashMap::new();
@@ -486,7 +1196,9 @@ impl<'w> StmtVisitor<Result<(), ReturnType>> for Interpreter<'w> {
             if let Stmt::Function(function) = method {
                 let function = Function::new(
                     method.clone(),
-                    Rc::clone(&self.environment),
+                    self.environment.clone(),
+                    Rc::clone(&self.globals),
+                    self.file.clone(),
                     function.name.lexeme.eq("init")
                 );
                 methods.insert(function.name.lexeme.clone(), function);
@@ -498,11 +1210,15 @@ impl<'w> StmtVisitor<Result<(), ReturnType>> for Interpreter<'w> {
         let class = Class::new(data.name.lexeme.clone(), superclass.clone(), methods);
 
         if superclass.is_some() {
-            let enclosing = self.environment.borrow().enclosing.clone().expect("enclosing to exist");
+            let enclosing = self.local_environment().borrow().enclosing.clone();
             self.environment = enclosing;
         }
 
-        self.environment.borrow_mut().assign(&data.name, Object::from(Rc::new(RefCell::new(class))));
+        // Methods only look the class up when they are called,
+        // so it is safe to define it after they are created.
+        let class = Object::from(Rc::new(RefCell::new(class)));
+        self.track(&class);
+        self.define(&data.name.lexeme, class);
 
         Ok(())
     }
@@ -1,4 +1,6 @@
 #![allow(clippy::needless_return)]
+#![allow(clippy::result_large_err)]
+#![allow(clippy::large_enum_variant)]
 
 //! Rocks is a programming language written in Rust. It is a dynamically typed language with
 //! lexical scoping and first-class functions. Rocks is a tree-walk interpreter with a hand-written
@@ -64,7 +66,9 @@
 //! ```
 //! However, much like the scanner, the parser will continue parsing the source code even if it
 //! has already encountered a syntax error using a technique called synchronization. This is useful
-//! because it allows the user to fix multiple syntax errors at once.
+//! because it allows the user to fix multiple syntax errors at once. The parser synchronizes at
+//! the end of statements, blocks, class members and arguments, so an error inside a block or a
+//! class body does not hide the errors that follow it.
 //!
 //! ## Resolving
 //! The third step in the interpreter is resolving. Resolving is the process of statically analyzing
@@ -95,14 +99,140 @@
 //! var a = "123";
 //! var b = a + 123;
 //! ```
-//! The interpreter is also responsible for managing the environment. The environment is a mapping of
-//! variable names to their values. The environment is implemented in the [`environment`](environment)
-//! module as a stack of hash maps. Each hash map represents a scope in the program. This allows the
-//! interpreter to implement lexical scoping. The interpreter also manages the call stack.
+//! Runtime errors can be caught with a `try` statement, the caught error is an object that holds
+//! the message and the location of the error. Scripts can also raise their own errors with `throw`:
+//! ```text
+//! try {
+//!     var b = a + 123;
+//! } catch (e) {
+//!     print e.message;
+//!     throw e;
+//! } finally {
+//!     print "done";
+//! }
+//! ```
+//! The interpreter is also responsible for managing the environment. The environment holds the
+//! values of the variables. The environment is implemented in the [`environment`](environment)
+//! module as a chain of scopes. Each scope stores its variables in the slots that the resolver
+//! assigned to them, while global variables are looked up by name. This allows the interpreter to
+//! implement lexical scoping. The interpreter also manages the call stack.
+//!
+//! ## Modules
+//! Scripts can import other files with the [`module`](module) system. Every module runs once, in
+//! its own global scope, and its top-level bindings are exposed as a namespace object. Paths are
+//! relative to the importing file:
+//! ```text
+//! import "lib/math.rocks" as math;
+//! from "lib/math.rocks" import square, cube;
+//!
+//! print math.square(2) + cube(3);
+//! ```
+//!
+//! The [`stdlib`](stdlib) provides native modules that are imported by name instead of path:
+//! `math`, `str`, `io`, `os` and `time`.
+//!
+//! ## Sandboxing
+//! Scripts that are not trusted can be denied access to the host. The
+//! [`InterpreterConfig`](config::InterpreterConfig) grants capabilities (filesystem, environment,
+//! stdin and clock) and chooses the enabled native modules; it is passed to
+//! [`rocks::with_config`]. Calling a native function that needs a denied capability is a runtime
+//! error that names the capability. The [`Limits`](config::Limits) of the configuration bound the
+//! call depth, the number of evaluated instructions, the time that a run can take and the number
+//! of objects that can be alive at the same time.
+//!
+//! ## Memory
+//! Values are reference counted, which frees them as soon as they are no longer used. Objects
+//! that refer to each other, like an instance that stores one of its bound methods, are freed by
+//! the cycle collector of the [`gc`](gc) module instead.
+//!
+//! ## Debugging
+//! Running `rocks debug script.rocks` pauses the script under the [`debugger`](debugger), which
+//! supports breakpoints, stepping through calls and inspecting variables. It is built on the
+//! [`Hook`](interpreter::Hook) of the interpreter which hosts can install with
+//! [`rocks::with_hook`].
+//!
+//! Editors can debug scripts through `rocks dap`, which serves the
+//! [Debug Adapter Protocol](debugger::dap) over the standard input and output. The `rocks-lsp`
+//! binary serves the [Language Server Protocol](lsp) in the same way to report diagnostics and to
+//! navigate scripts.
+//!
+//! ## Formatting
+//! Running `rocks fmt` rewrites scripts in the canonical style of the
+//! [`Formatter`](formatter::Formatter), which prints the syntax tree back into source code while
+//! keeping the comments. With `--check`, the scripts are only checked and the command fails if any
+//! of them is not formatted, which is useful in continuous integration.
+//!
+//! Running `rocks lint` checks scripts with the [`Linter`](linter::Linter) for likely mistakes,
+//! like unused variables or code after a `return`. Rules are turned off with `--allow rule`.
+//!
+//! ## REPL
+//! Running `rocks` without a script starts the interactive [`Repl`](repl::Repl). Inputs that are
+//! not complete yet, like a function with an open brace, continue on the next line and the values
+//! of expressions are printed without a `print` statement. Meta-commands like `:env`, `:ast` and
+//! `:load` inspect the session, `:help` lists all of them. In a terminal, the input is
+//! highlighted as it is typed and the tab key completes keywords, variables and the fields and
+//! methods of instances.
+//!
+//! ## Bytecode
+//! Alternatively, the resolved AST can be run by the [`vm`](vm) module. Instead of walking the AST,
+//! the [`Compiler`](vm::compiler::Compiler) converts it into bytecode which is then executed by a
+//! stack based virtual machine. Local variables are stored on the stack and accessed by their slot
+//! instead of being looked up by name. Both backends produce the same output and the same errors,
+//! the backend is chosen with [`rocks::with_backend`]:
+//! ```
+//! use rocks_lang::{rocks, Backend};
+//!
+//! let mut output = Vec::new();
+//! let mut rocks = rocks::with_backend(&mut output, Backend::Vm);
+//!
+//! rocks.run("var a = 1; print a + 2;".to_string()).unwrap();
+//! drop(rocks);
+//! assert_eq!(String::from_utf8(output).unwrap(), "3\n");
+//! ```
+//!
+//! ## Diagnostics
+//! None of the steps above print their errors directly. Instead, every error is converted into a
+//! [`Diagnostic`](error::Diagnostic) that carries the phase it was produced in, its severity, the
+//! span of source code it refers to and a message. [`rocks::run`] returns these diagnostics to the
+//! caller and also passes each of them to a [`Reporter`](error::Reporter). By default, diagnostics
+//! are printed to the standard error but any closure taking a diagnostic can be used instead.
+//! Runtime errors also carry the [`trace`](error::Diagnostic::trace) of the calls that led to
+//! them, which the default reporter prints below the error:
+//! ```
+//! use rocks_lang::rocks;
+//!
+//! let mut output = Vec::new();
+//! let mut rocks = rocks::new(&mut output).with_reporter(|_: &_| {});
+//!
+//! let diagnostics = rocks.run("print 1 +;".to_string()).unwrap_err();
+//! assert_eq!(diagnostics[0].to_string(), "[line 1:10] Error at ';': Expected expression");
+//!
+//! let diagnostics = rocks.run("fun negate(a) { return -a; }\nnegate(\"a\");".to_string()).unwrap_err();
+//! let trace: Vec<String> = diagnostics[0].trace.iter().map(|frame| frame.to_string()).collect();
+//! assert_eq!(trace, ["at negate (line 1)", "at <script> (line 2)"]);
+//! ```
+//!
+//! Some diagnostics also carry a [`help`](error::Diagnostic::help) note. Misspelled keywords,
+//! variables, properties and methods are answered with the most similar name that is in scope:
+//! ```
+//! use rocks_lang::rocks;
+//!
+//! let mut output = Vec::new();
+//! let mut rocks = rocks::new(&mut output).with_reporter(|_: &_| {});
+//!
+//! let diagnostics = rocks.run("var counter = 1;\nprint conter;".to_string()).unwrap_err();
+//! assert_eq!(diagnostics[0].help.as_deref(), Some("did you mean 'counter'?"));
+//! ```
+//!
+//! The default reporter [renders](render) diagnostics with the line of source code they refer to
+//! and the offending span underlined. With `--error-format=json`, the command line prints them as
+//! JSON objects instead, one per line, for tools to consume.
 
-use std::{fs, process};
+use std::fs;
+use std::path::Path;
 
 pub mod error;
+pub mod render;
 pub mod token;
 pub mod scanner;
 pub mod expr;
@@ -116,85 +246,360 @@ pub mod object;
 pub mod function;
 pub mod resolver;
 pub mod class;
+pub mod list;
+pub mod map;
+pub mod module;
+pub mod config;
+pub mod debugger;
+pub mod formatter;
+pub mod linter;
+pub mod repl;
+pub mod lsp;
+pub mod gc;
+pub mod stdlib;
+pub mod vm;
 
+use error::{Diagnostic, Error, Phase, Reporter, Severity, Span, StderrReporter};
+use function::NativeFunction;
+use object::Arity;
 use parser::Parser;
 use scanner::Scanner;
 use resolver::Resolver;
+use config::InterpreterConfig;
+use expr::Expr;
+use stmt::{ExpressionData, PrintData, Stmt};
+use token::{Location, Token};
+use vm::compiler::Compiler;
+
+/// The result of running a piece of source code.
+/// All diagnostics produced during the run are returned on failure.
+pub type RunResult = Result<Termination, Vec<Diagnostic>>;
+
+/// Represents how a run that did not fail ended.
+/// ```
+/// use rocks_lang::{rocks, Termination};
+///
+/// let mut output = Vec::new();
+/// let mut rocks = rocks::new(&mut output);
+///
+/// assert_eq!(rocks.run("print 1;".to_string()).unwrap(), Termination::Completed);
+/// assert_eq!(rocks.run("import \"os\" as os; os.exit(3); print 2;".to_string()).unwrap(), Termination::Exit(3));
+/// drop(rocks);
+/// assert_eq!(String::from_utf8(output).unwrap(), "1\n");
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Termination {
+    /// Every statement ran.
+    Completed,
+    /// The script called `os.exit` with the given code, the statements after the call did not
+    /// run. The host decides what to do with the code, such as exiting the process with it.
+    /// Runtime errors of the earlier statements are still sent to the reporter.
+    Exit(i32),
+}
+
+impl Termination {
+    /// Returns the code that the host process should exit with.
+    pub fn code(&self) -> i32 {
+        match self {
+            Termination::Completed => 0,
+            Termination::Exit(code) => *code,
+        }
+    }
+}
+
+/// Represents the backend that runs the programs.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Backend {
+    /// The tree-walk [`Interpreter`](interpreter::Interpreter).
+    #[default]
+    Interpreter,
+    /// The bytecode [`Vm`](vm::Vm).
+    Vm,
+}
+
+enum Engine<'w> {
+    Interpreter(interpreter::Interpreter<'w>),
+    Vm(vm::Vm<'w>),
+}
 
 #[allow(non_camel_case_types)]
 pub struct rocks<'w> {
-    interpreter: interpreter::Interpreter<'w>,
+    engine: Engine<'w>,
+    reporter: Box<dyn Reporter + 'w>,
+    /// The name of the file that is being run, if it was run with [`rocks::run_file`].
+    file: Option<String>,
 }
 
 impl<'w> rocks<'w> {
+    /// Creates a new instance that writes the output of the program into the given writer and
+    /// reports diagnostics to the standard error.
     pub fn new<W: std::io::Write>(writer: &'w mut W) -> Self {
-        rocks {
-            interpreter: interpreter::Interpreter::new(writer),
+        Self::with_backend(writer, Backend::Interpreter)
+    }
+
+    /// Creates a new instance like [`rocks::new`] that runs the programs on the given backend.
+    pub fn with_backend<W: std::io::Write>(writer: &'w mut W, backend: Backend) -> Self {
+        let engine = match backend {
+            Backend::Interpreter => Engine::Interpreter(interpreter::Interpreter::new(writer)),
+            Backend::Vm => Engine::Vm(vm::Vm::new(writer)),
+        };
+
+        rocks { engine, reporter: Box::new(StderrReporter::new()), file: None }
+    }
+
+    /// Replaces the reporter that diagnostics are sent to.
+    pub fn with_reporter<R: Reporter + 'w>(mut self, reporter: R) -> Self {
+        self.reporter = Box::new(reporter);
+        self
+    }
+
+    /// Replaces the configuration of the backend, which decides the capabilities of the scripts
+    /// and the native modules that they can import. Every capability and module is enabled by
+    /// default.
+    /// ```
+    /// use rocks_lang::rocks;
+    /// use rocks_lang::config::InterpreterConfig;
+    /// use rocks_lang::stdlib::{StdModule, Stdlib};
+    ///
+    /// let mut output = Vec::new();
+    /// let config = InterpreterConfig {
+    ///     stdlib: Stdlib::new(&[StdModule::Math]),
+    ///     ..InterpreterConfig::default()
+    /// };
+    /// let mut rocks = rocks::new(&mut output).with_config(config).with_reporter(|_: &_| {});
+    ///
+    /// rocks.run("import \"math\" as math; print math.sqrt(16);".to_string()).unwrap();
+    /// assert!(rocks.run("import \"io\" as io;".to_string()).is_err());
+    /// drop(rocks);
+    /// assert_eq!(String::from_utf8(output).unwrap(), "4\n");
+    /// ```
+    pub fn with_config(mut self, config: InterpreterConfig) -> Self {
+        match &mut self.engine {
+            Engine::Interpreter(interpreter) => interpreter.set_config(config),
+            Engine::Vm(vm) => vm.set_config(config),
         }
+
+        self
     }
 
-    pub fn run_file(&mut self, path: String) {
-        let contents = fs::read_to_string(path)
-            .expect("Should have been able to read the file");
+    /// Installs a hook that is called before every statement, like the
+    /// [`Debugger`](debugger::Debugger).
+    ///
+    /// Hooks observe the interpreter, so this fails if the instance runs on the [`Backend::Vm`]
+    /// backend.
+    /// ```
+    /// use rocks_lang::{rocks, Backend};
+    /// use rocks_lang::debugger::Debugger;
+    ///
+    /// let mut output = Vec::new();
+    /// let debugger = Debugger::new("print 1;", "continue\n".as_bytes(), std::io::sink());
+    /// assert!(rocks::with_backend(&mut output, Backend::Vm).with_hook(debugger).is_err());
+    /// ```
+    pub fn with_hook<H: interpreter::Hook + 'w>(mut self, hook: H) -> Result<Self, String> {
+        let Engine::Interpreter(interpreter) = &mut self.engine else {
+            return Err("Hooks can only be installed on the interpreter backend".to_string());
+        };
 
-        self.run(contents);
+        interpreter.set_hook(hook);
+        Ok(self)
+    }
 
-        if error::did_error() {
-            process::exit(65);
+    /// Registers a [`Native`](stdlib::Native) function with the given name and arity.
+    /// The function will be available to the scripts in the global scope. Arguments are checked
+    /// against the arity before the function is called.
+    ///
+    /// Any closure that takes and returns literals is a native function, including ones that
+    /// capture their environment:
+    /// ```
+    /// use rocks_lang::rocks;
+    /// use rocks_lang::literal::Literal;
+    /// use rocks_lang::object::Arity;
+    ///
+    /// let mut output = Vec::new();
+    /// let mut rocks = rocks::new(&mut output);
+    ///
+    /// let greeting = String::from("Hello");
+    /// rocks.register_native("greet", Arity::Fixed(1), move |arguments: Vec<Literal>| {
+    ///     Ok(Literal::String(format!("{greeting}, {}!", arguments[0])))
+    /// });
+    ///
+    /// rocks.run("print greet(\"rocks\");".to_string()).unwrap();
+    /// drop(rocks);
+    /// assert_eq!(String::from_utf8(output).unwrap(), "Hello, rocks!\n");
+    /// ```
+    /// Functions that need the other values of the scripts or call back into them implement the
+    /// trait themselves:
+    /// ```
+    /// use rocks_lang::{rocks, Backend};
+    /// use rocks_lang::error::RuntimeError;
+    /// use rocks_lang::object::Arity;
+    /// use rocks_lang::stdlib::{Caller, Native, StdValue};
+    /// use rocks_lang::token::Token;
+    ///
+    /// struct Apply;
+    ///
+    /// impl Native for Apply {
+    ///     fn call<V: StdValue>(&self, caller: &mut dyn Caller<V>, mut arguments: Vec<V>) -> Result<V, RuntimeError> {
+    ///         let argument = arguments.pop().unwrap();
+    ///         let callee = arguments.pop().unwrap();
+    ///         caller.call(callee, vec![argument], &Token::from("apply"))
+    ///     }
+    /// }
+    ///
+    /// let mut output = Vec::new();
+    /// let mut rocks = rocks::with_backend(&mut output, Backend::Vm);
+    ///
+    /// rocks.register_native("apply", Arity::Fixed(2), Apply);
+    /// rocks.run("print apply(fun (x) => x * 2, 21);".to_string()).unwrap();
+    /// drop(rocks);
+    /// assert_eq!(String::from_utf8(output).unwrap(), "42\n");
+    /// ```
+    pub fn register_native<N: stdlib::Native>(&mut self, name: &str, arity: Arity, native: N) {
+        match &mut self.engine {
+            Engine::Interpreter(interpreter) => interpreter.define_native(NativeFunction::new(
+                name,
+                arity,
+                move |interpreter, arguments| native.call(interpreter, arguments),
+            )),
+            Engine::Vm(vm) => vm.define_native(vm::value::NativeFunction::new(
+                name,
+                arity,
+                move |vm, arguments| native.call(vm, arguments),
+            )),
         }
     }
 
-    pub fn run_prompt(&mut self) {
-        let mut rl = rustyline::DefaultEditor::new().unwrap();
+    /// Collects the objects that are only reachable through reference cycles and returns how
+    /// many of them were collected. Collections also happen automatically while scripts run.
+    /// ```
+    /// use rocks_lang::rocks;
+    ///
+    /// let mut output = Vec::new();
+    /// let mut rocks = rocks::new(&mut output);
+    ///
+    /// rocks.run("class Node {} { var node = Node(); node.next = node; }".to_string()).unwrap();
+    /// assert!(rocks.collect_garbage() > 0);
+    /// assert_eq!(rocks.collect_garbage(), 0);
+    /// ```
+    pub fn collect_garbage(&mut self) -> usize {
+        match &mut self.engine {
+            Engine::Interpreter(interpreter) => interpreter.collect_garbage(),
+            Engine::Vm(vm) => vm.collect_garbage(),
+        }
+    }
 
-        let history_path = home::home_dir().unwrap().join(".rocks.history");
-        rl.load_history(&history_path).ok();
+    /// Runs the file at the given path.
+    /// Modules imported by the file are resolved relative to its directory.
+    /// If the file can not be read, the error is reported as a diagnostic.
+    pub fn run_file(&mut self, path: String) -> RunResult {
+        self.file = Path::new(&path).file_name().map(|name| name.to_string_lossy().to_string());
 
-        loop {
-            let readline = rl.readline("> ");
-            match readline {
-                Ok(mut line) => {
-                    rl.add_history_entry(line.as_str()).unwrap();
-                    line.push('\n');
-                    self.run(line);
-                    error::reset_error();
-                },
-                Err(_) => {
-                    break;
-                }
-            }
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                let diagnostic = Diagnostic {
+                    phase: Phase::Io,
+                    severity: Severity::Error,
+                    span: Span::from(Location::new(0, 0)),
+                    message: format!("Could not read '{path}': {err}"),
+                    token: None,
+                    help: None,
+                    trace: vec![],
+                };
+
+                self.reporter.source(self.file.as_deref(), "");
+                self.reporter.report(&diagnostic);
+                return Err(vec![diagnostic]);
+            },
+        };
+
+        match &mut self.engine {
+            Engine::Interpreter(interpreter) => interpreter.set_script(Path::new(&path)),
+            Engine::Vm(vm) => vm.set_script(Path::new(&path)),
         }
 
-        rl.save_history(&history_path).ok();
+        self.run(contents)
+    }
+
+    /// Starts the interactive [`Repl`](repl::Repl) on the standard input.
+    /// Returns how the session ended, which is only an exit if a script exited.
+    pub fn run_prompt(&mut self) -> Termination {
+        repl::Repl::new(self).run()
+    }
+
+    /// Runs the given source code.
+    /// Every diagnostic is sent to the reporter as well as returned to the caller.
+    pub fn run(&mut self, source: String) -> RunResult {
+        self.execute(source, false)
     }
 
-    fn run(&mut self, mut source: String) {
+    /// Runs the given source code. If `echo` is set, the values of the expression statements are
+    /// printed like in the REPL, unless they only assign a value.
+    fn execute(&mut self, mut source: String, echo: bool) -> RunResult {
         if !source.ends_with('\n') {
             source.push('\n');
         }
 
+        self.reporter.source(self.file.as_deref(), &source);
+
         let mut scanner = Scanner::new(&source);
         let tokens = scanner.scan_tokens();
 
-        if error::did_error() {
-            return;
+        if !scanner.errors().is_empty() {
+            return self.report(scanner.errors());
         }
 
         let mut parser = Parser::new(tokens);
-        let statements = parser.parse();
+        let mut statements = parser.parse();
+
+        if !parser.errors().is_empty() {
+            return self.report(parser.errors());
+        }
 
-        if error::did_error() {
-            return;
+        if echo {
+            statements = statements.into_iter().map(|statement| match statement {
+                Stmt::Expression(ExpressionData { expr })
+                    if !matches!(expr, Expr::Assign(_) | Expr::Set(_) | Expr::IndexSet(_)) =>
+                {
+                    Stmt::Print(PrintData { keyword: Token::from("print"), expr })
+                },
+                statement => statement,
+            }).collect();
         }
 
-        let mut resolver = Resolver::new(&mut self.interpreter);
+        let mut resolver = Resolver::new();
         resolver.resolve(&statements);
 
-        if error::did_error() {
-            return;
+        if !resolver.errors().is_empty() {
+            return self.report(resolver.errors());
+        }
+
+        let (termination, errors) = match &mut self.engine {
+            Engine::Interpreter(interpreter) => interpreter.interpret(&statements),
+            Engine::Vm(vm) => vm.interpret(Compiler::new().compile(&statements)),
+        };
+
+        if errors.is_empty() {
+            return Ok(termination);
+        }
+
+        // An exit takes priority over the errors before it, which are still reported.
+        let diagnostics = self.report(&errors);
+        match termination {
+            Termination::Exit(_) => Ok(termination),
+            Termination::Completed => diagnostics,
+        }
+    }
+
+    /// Sends the given errors to the reporter and returns them as diagnostics.
+    fn report<E: Error>(&mut self, errors: &[E]) -> RunResult {
+        let diagnostics: Vec<Diagnostic> = errors.iter().map(Error::to_diagnostic).collect();
+
+        for diagnostic in &diagnostics {
+            self.reporter.report(diagnostic);
         }
 
-        self.interpreter.interpret(&statements);
+        Err(diagnostics)
     }
 }
 
@@ -0,0 +1,524 @@
+//! A linter that warns about likely mistakes in valid scripts.
+//!
+//! The linter runs the [`Scanner`], the [`Parser`] and the [`Resolver`] without executing the
+//! script. The declarations and the references that the resolver found are used to report unused
+//! and shadowed names, the rest of the rules are checked by walking the syntax tree. Every rule can
+//! be turned off on its own:
+//! ```
+//! use rocks_lang::linter::{Linter, Rule};
+//!
+//! let source = "fun greet(name) { return \"hello\"; }\nprint greet(1);";
+//!
+//! let warnings = Linter::new().lint(source);
+//! assert_eq!(warnings[0].to_string(), "[line 1:11] Warning at 'name': Parameter 'name' is never used (unused-parameter)");
+//!
+//! let warnings = Linter::new().with_rule(Rule::UnusedParameter, false).lint(source);
+//! assert!(warnings.is_empty());
+//! ```
+//!
+//! Names that start with an underscore are never reported as unused. Functions declared at the
+//! top level of a module are used by the scripts that import it, the `unused-function` rule can
+//! be turned off for modules.
+
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use crate::error::{Diagnostic, Error, LintWarning};
+use crate::expr::{Expr, ExprVisitor};
+use crate::literal::Literal;
+use crate::object::Object;
+use crate::parser::Parser;
+use crate::resolver::{Kind, Resolver, Symbol};
+use crate::scanner::Scanner;
+use crate::stdlib;
+use crate::stmt::{Stmt, StmtVisitor};
+use crate::token::{Token, Type};
+
+/// Represents a check of the linter.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Rule {
+    /// A local variable that is never used.
+    UnusedVariable,
+    /// A parameter that is never used.
+    UnusedParameter,
+    /// A function that is never used.
+    UnusedFunction,
+    /// A local variable that hides a variable of an enclosing scope.
+    ShadowedName,
+    /// A statement after a `return`, `break`, `continue` or `throw` that can never run.
+    UnreachableCode,
+    /// An equality check that always has the same result, like one between a number and a string.
+    ConstantComparison,
+    /// An assignment to a global variable that is never declared.
+    UndeclaredAssignment,
+    /// A field that is assigned in a method and hides a method of the class with the same name.
+    MethodFieldCollision,
+}
+
+impl Rule {
+    /// Every rule of the linter.
+    pub const ALL: [Rule; 8] = [
+        Rule::UnusedVariable,
+        Rule::UnusedParameter,
+        Rule::UnusedFunction,
+        Rule::ShadowedName,
+        Rule::UnreachableCode,
+        Rule::ConstantComparison,
+        Rule::UndeclaredAssignment,
+        Rule::MethodFieldCollision,
+    ];
+
+    /// Returns the name of the rule that is shown in the warnings.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::UnusedVariable => "unused-variable",
+            Rule::UnusedParameter => "unused-parameter",
+            Rule::UnusedFunction => "unused-function",
+            Rule::ShadowedName => "shadowed-name",
+            Rule::UnreachableCode => "unreachable-code",
+            Rule::ConstantComparison => "constant-comparison",
+            Rule::UndeclaredAssignment => "undeclared-assignment",
+            Rule::MethodFieldCollision => "method-field-collision",
+        }
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    /// Returns the rule with the given name.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Rule::ALL.into_iter()
+            .find(|rule| rule.name() == name)
+            .ok_or_else(|| format!("Unknown lint rule '{name}'"))
+    }
+}
+
+/// Checks scripts against a set of rules.
+#[derive(Debug, Default, Clone)]
+pub struct Linter {
+    disabled: HashSet<Rule>,
+}
+
+impl Linter {
+    /// Creates a linter with every rule turned on.
+    pub fn new() -> Self {
+        Linter { disabled: HashSet::new() }
+    }
+
+    /// Turns the given rule on or off.
+    pub fn with_rule(mut self, rule: Rule, enabled: bool) -> Self {
+        match enabled {
+            true => self.disabled.remove(&rule),
+            false => self.disabled.insert(rule),
+        };
+
+        self
+    }
+
+    /// Returns if the given rule is turned on.
+    pub fn is_enabled(&self, rule: Rule) -> bool {
+        !self.disabled.contains(&rule)
+    }
+
+    /// Returns the warnings about the given source code, ordered by their location.
+    /// If the source code has scan or parse errors, only they are returned. Resolve errors are
+    /// returned before the warnings.
+    pub fn lint(&self, source: &str) -> Vec<Diagnostic> {
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan_tokens();
+
+        if !scanner.errors().is_empty() {
+            return scanner.errors().iter().map(Error::to_diagnostic).collect();
+        }
+
+        let mut parser = Parser::new(tokens);
+        let statements = parser.parse();
+
+        if !parser.errors().is_empty() {
+            return parser.errors().iter().map(Error::to_diagnostic).collect();
+        }
+
+        let mut resolver = Resolver::new();
+        resolver.resolve(&statements);
+        let symbols = resolver.symbols();
+
+        let mut globals: HashSet<String> = symbols.iter()
+            .filter(|symbol| symbol.global)
+            .map(|symbol| symbol.name.lexeme.clone())
+            .collect();
+        globals.extend(stdlib::globals::<Object>().iter().map(|native| native.name.to_string()));
+
+        let mut pass = Pass {
+            linter: self,
+            globals,
+            methods: vec![],
+            warnings: vec![],
+        };
+        pass.statements(&statements);
+
+        let mut warnings = pass.warnings;
+        for symbol in &symbols {
+            self.symbol(symbol, &mut warnings);
+        }
+        warnings.retain(|warning| self.is_enabled(warning.rule));
+        warnings.sort_by_key(|warning| warning.token.location);
+
+        let mut diagnostics: Vec<Diagnostic> = resolver.errors().iter().map(Error::to_diagnostic).collect();
+        diagnostics.extend(warnings.iter().map(Error::to_diagnostic));
+        diagnostics
+    }
+
+    /// Checks if the declared name is used and if it shadows another one.
+    fn symbol(&self, symbol: &Symbol, warnings: &mut Vec<LintWarning>) {
+        let name = &symbol.name.lexeme;
+
+        if let Some(shadowed) = &symbol.shadows {
+            warnings.push(LintWarning {
+                token: symbol.name.clone(),
+                rule: Rule::ShadowedName,
+                message: format!("'{name}' shadows the variable declared on line {}", shadowed.location.line + 1),
+            });
+        }
+
+        if !symbol.references.is_empty() || name.starts_with('_') {
+            return;
+        }
+
+        let (rule, message) = match symbol.kind {
+            Kind::Variable if !symbol.global => (Rule::UnusedVariable, format!("Variable '{name}' is never used")),
+            Kind::Parameter => (Rule::UnusedParameter, format!("Parameter '{name}' is never used")),
+            Kind::Function => (Rule::UnusedFunction, format!("Function '{name}' is never used")),
+            _ => return,
+        };
+
+        warnings.push(LintWarning { token: symbol.name.clone(), rule, message });
+    }
+}
+
+/// Returns if the statement always leaves the block it is in.
+fn terminates(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Return(_) | Stmt::Break(_) | Stmt::Continue(_) | Stmt::Throw(_) => true,
+        Stmt::Block(block) => block.statements.iter().any(terminates),
+        Stmt::If(data) => match &data.else_branch {
+            Some(else_branch) => terminates(&data.then_branch) && terminates(else_branch),
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// Returns the first token of the statement that can locate a warning.
+fn token(stmt: &Stmt) -> Option<&Token> {
+    match stmt {
+        Stmt::Block(block) => block.statements.iter().find_map(token),
+        stmt => stmt.token(),
+    }
+}
+
+/// Returns the type of the values that the expression can evaluate to, if there is only one.
+fn type_of(expr: &Expr) -> Option<&'static str> {
+    match expr {
+        Expr::Literal(Literal::Number(_)) => Some("number"),
+        Expr::Literal(Literal::String(_)) => Some("string"),
+        Expr::Literal(Literal::Bool(_)) => Some("bool"),
+        Expr::Literal(Literal::Null) => Some("null"),
+        Expr::Grouping(data) => type_of(&data.expr),
+        Expr::Unary(data) => match data.operator.r#type {
+            Type::Bang => Some("bool"),
+            _ => Some("number"),
+        },
+        Expr::Binary(data) => match data.operator.r#type {
+            // Strings can be added together.
+            Type::Plus => None,
+            Type::Minus | Type::Star | Type::Slash => Some("number"),
+            _ => Some("bool"),
+        },
+        Expr::List(_) => Some("list"),
+        Expr::Map(_) => Some("map"),
+        Expr::Lambda(_) => Some("function"),
+        _ => None,
+    }
+}
+
+/// Walks the syntax tree for the rules that do not depend on the declarations.
+struct Pass<'a> {
+    linter: &'a Linter,
+    /// The names of the global variables and the natives.
+    globals: HashSet<String>,
+    /// The names of the methods of the enclosing classes.
+    methods: Vec<HashSet<String>>,
+    warnings: Vec<LintWarning>,
+}
+
+impl Pass<'_> {
+    fn statements(&mut self, statements: &[Stmt]) {
+        let mut terminated = false;
+
+        for statement in statements {
+            if terminated && self.linter.is_enabled(Rule::UnreachableCode) {
+                if let Some(token) = token(statement) {
+                    self.warnings.push(LintWarning {
+                        token: token.clone(),
+                        rule: Rule::UnreachableCode,
+                        message: "Unreachable code".to_string(),
+                    });
+                }
+
+                // Only the first unreachable statement is reported.
+                terminated = false;
+            } else if terminates(statement) {
+                terminated = true;
+            }
+
+            statement.accept(self);
+        }
+    }
+}
+
+impl ExprVisitor<()> for Pass<'_> {
+    fn visit_literal_expr(&mut self, _expr: &Expr) {}
+
+    fn visit_logical_expr(&mut self, expr: &Expr) {
+        let Expr::Logical(data) = expr else { unreachable!() };
+
+        data.left.accept(self);
+        data.right.accept(self);
+    }
+
+    fn visit_unary_expr(&mut self, expr: &Expr) {
+        let Expr::Unary(data) = expr else { unreachable!() };
+
+        data.expr.accept(self);
+    }
+
+    fn visit_binary_expr(&mut self, expr: &Expr) {
+        let Expr::Binary(data) = expr else { unreachable!() };
+
+        data.left.accept(self);
+        data.right.accept(self);
+
+        let result = match data.operator.r#type {
+            Type::EqualEqual => "false",
+            Type::BangEqual => "true",
+            _ => return,
+        };
+
+        let (Some(left), Some(right)) = (type_of(&data.left), type_of(&data.right)) else {
+            return;
+        };
+
+        // Lists, maps and functions are compared by reference, a new one is not equal to anything.
+        let message = match ["list", "map", "function"].iter().find(|new| left == **new || right == **new) {
+            Some(new) => format!("Comparison with a new {new} is always {result}"),
+            None if left != right => format!("Comparison between a {left} and a {right} is always {result}"),
+            None => return,
+        };
+
+        self.warnings.push(LintWarning { token: data.operator.clone(), rule: Rule::ConstantComparison, message });
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &Expr) {
+        let Expr::Grouping(data) = expr else { unreachable!() };
+
+        data.expr.accept(self);
+    }
+
+    fn visit_variable_expr(&mut self, _expr: &Expr) {}
+
+    fn visit_assign_expr(&mut self, expr: &Expr) {
+        let Expr::Assign(data) = expr else { unreachable!() };
+
+        data.value.accept(self);
+
+        if data.slot.get().is_none() && !self.globals.contains(&data.name.lexeme) {
+            self.warnings.push(LintWarning {
+                token: data.name.clone(),
+                rule: Rule::UndeclaredAssignment,
+                message: format!("Assignment to undeclared global '{}'", data.name.lexeme),
+            });
+        }
+    }
+
+    fn visit_call_expr(&mut self, expr: &Expr) {
+        let Expr::Call(data) = expr else { unreachable!() };
+
+        data.callee.accept(self);
+        for argument in &data.arguments {
+            argument.accept(self);
+        }
+    }
+
+    fn visit_get_expr(&mut self, expr: &Expr) {
+        let Expr::Get(data) = expr else { unreachable!() };
+
+        data.object.accept(self);
+    }
+
+    fn visit_set_expr(&mut self, expr: &Expr) {
+        let Expr::Set(data) = expr else { unreachable!() };
+
+        data.object.accept(self);
+        data.value.accept(self);
+
+        let method = self.methods.last().is_some_and(|methods| methods.contains(&data.name.lexeme));
+        if matches!(data.object.as_ref(), Expr::This(_)) && method {
+            self.warnings.push(LintWarning {
+                token: data.name.clone(),
+                rule: Rule::MethodFieldCollision,
+                message: format!("Field '{}' hides the method with the same name", data.name.lexeme),
+            });
+        }
+    }
+
+    fn visit_this_expr(&mut self, _expr: &Expr) {}
+
+    fn visit_super_expr(&mut self, _expr: &Expr) {}
+
+    fn visit_lambda_expr(&mut self, expr: &Expr) {
+        let Expr::Lambda(data) = expr else { unreachable!() };
+
+        self.statements(&data.body);
+    }
+
+    fn visit_list_expr(&mut self, expr: &Expr) {
+        let Expr::List(data) = expr else { unreachable!() };
+
+        for element in &data.elements {
+            element.accept(self);
+        }
+    }
+
+    fn visit_map_expr(&mut self, expr: &Expr) {
+        let Expr::Map(data) = expr else { unreachable!() };
+
+        for (key, value) in &data.entries {
+            key.accept(self);
+            value.accept(self);
+        }
+    }
+
+    fn visit_index_expr(&mut self, expr: &Expr) {
+        let Expr::Index(data) = expr else { unreachable!() };
+
+        data.object.accept(self);
+        data.index.accept(self);
+    }
+
+    fn visit_index_set_expr(&mut self, expr: &Expr) {
+        let Expr::IndexSet(data) = expr else { unreachable!() };
+
+        data.object.accept(self);
+        data.index.accept(self);
+        data.value.accept(self);
+    }
+}
+
+impl StmtVisitor<()> for Pass<'_> {
+    fn visit_expression_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Expression(data) = stmt else { unreachable!() };
+
+        data.expr.accept(self);
+    }
+
+    fn visit_function_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Function(data) = stmt else { unreachable!() };
+
+        self.statements(&data.body);
+    }
+
+    fn visit_if_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::If(data) = stmt else { unreachable!() };
+
+        data.condition.accept(self);
+        data.then_branch.accept(self);
+        if let Some(else_branch) = &data.else_branch {
+            else_branch.accept(self);
+        }
+    }
+
+    fn visit_print_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Print(data) = stmt else { unreachable!() };
+
+        data.expr.accept(self);
+    }
+
+    fn visit_return_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Return(data) = stmt else { unreachable!() };
+
+        if let Some(value) = &data.value {
+            value.accept(self);
+        }
+    }
+
+    fn visit_break_stmt(&mut self, _stmt: &Stmt) {}
+
+    fn visit_continue_stmt(&mut self, _stmt: &Stmt) {}
+
+    fn visit_throw_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Throw(data) = stmt else { unreachable!() };
+
+        data.value.accept(self);
+    }
+
+    fn visit_try_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Try(data) = stmt else { unreachable!() };
+
+        self.statements(&data.body);
+        if let Some(catch) = &data.catch {
+            self.statements(&catch.body);
+        }
+        if let Some(finally) = &data.finally {
+            self.statements(finally);
+        }
+    }
+
+    fn visit_var_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Var(data) = stmt else { unreachable!() };
+
+        if let Some(initializer) = &data.initializer {
+            initializer.accept(self);
+        }
+    }
+
+    fn visit_while_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::While(data) = stmt else { unreachable!() };
+
+        data.condition.accept(self);
+        data.body.accept(self);
+        if let Some(increment) = &data.increment {
+            increment.accept(self);
+        }
+    }
+
+    fn visit_block_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Block(data) = stmt else { unreachable!() };
+
+        self.statements(&data.statements);
+    }
+
+    fn visit_class_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Class(data) = stmt else { unreachable!() };
+
+        let methods = data.methods.iter().filter_map(|method| match method {
+            Stmt::Function(method) => Some(method.name.lexeme.clone()),
+            _ => None,
+        });
+
+        self.methods.push(methods.collect());
+        for method in &data.methods {
+            method.accept(self);
+        }
+        self.methods.pop();
+    }
+
+    fn visit_import_stmt(&mut self, _stmt: &Stmt) {}
+}
@@ -0,0 +1,119 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::error::RuntimeError;
+use crate::literal::Literal;
+use crate::object::Arity;
+use crate::stdlib::StdValue;
+use crate::token::Token;
+
+/// Returns the built-in method with the given name bound to the given list.
+/// Methods are implemented as native functions that are bound to the list they were accessed on,
+/// which lets the cycle collector see the reference. If there is no method with the given name,
+/// it will return an error.
+///
+/// The methods are written against [`StdValue`] so that both backends share them.
+///
+/// Current list methods:
+/// - `push(value)` - Appends the value to the end of the list.
+/// - `pop()` - Removes the last element of the list and returns it.
+/// - `len()` - Returns the number of elements in the list.
+/// - `map(function)` - Returns a new list with the results of calling the function on every element.
+/// - `filter(function)` - Returns a new list with the elements that the function returns true for.
+pub fn get_method<V: StdValue>(list: &V, name: &Token) -> Result<V, RuntimeError> {
+    let token = name.clone();
+
+    let method = match name.lexeme.as_str() {
+        "push" => V::method(list.clone(), "push", Arity::Fixed(1), move |_, list, mut arguments| {
+            bound_list(list).borrow_mut().push(arguments.remove(0));
+            Ok(V::from(Literal::Null))
+        }),
+        "pop" => V::method(list.clone(), "pop", Arity::Fixed(0), move |_, list, _| {
+            bound_list(list).borrow_mut().pop().ok_or_else(|| RuntimeError {
+                token: token.clone(),
+                message: "Cannot pop from an empty list".to_string(),
+                help: None,
+                trace: vec![],
+                value: None,
+            })
+        }),
+        "len" => V::method(list.clone(), "len", Arity::Fixed(0), move |_, list, _| {
+            Ok(V::from(Literal::Number(bound_list(list).borrow().len() as f64)))
+        }),
+        "map" => V::method(list.clone(), "map", Arity::Fixed(1), move |backend, list, arguments| {
+            // Clone the elements so that the function is free to modify the list.
+            let elements = bound_list(list).borrow().clone();
+
+            let mapped = elements
+                .into_iter()
+                .map(|element| backend.call(arguments[0].clone(), vec![element], &token))
+                .collect::<Result<Vec<V>, RuntimeError>>()?;
+
+            Ok(V::from(mapped))
+        }),
+        "filter" => V::method(list.clone(), "filter", Arity::Fixed(1), move |backend, list, arguments| {
+            let elements = bound_list(list).borrow().clone();
+
+            let mut filtered = vec![];
+            for element in elements {
+                if backend.call(arguments[0].clone(), vec![element.clone()], &token)?.is_truthy() {
+                    filtered.push(element);
+                }
+            }
+
+            Ok(V::from(filtered))
+        }),
+        _ => {
+            return Err(RuntimeError {
+                token: name.clone(),
+                message: format!("Undefined property '{}'", name.lexeme),
+                help: None,
+                trace: vec![],
+                value: None,
+            });
+        }
+    };
+
+    Ok(method)
+}
+
+/// Returns the shared elements of the list that a method is bound to.
+fn bound_list<V: StdValue>(list: &V) -> &Rc<RefCell<Vec<V>>> {
+    list.list().expect("list methods to be bound to lists")
+}
+
+/// Converts the given value into a valid index of the given list.
+/// The index must be a whole number that is within the bounds of the list.
+pub fn index<V: StdValue>(list: &[V], index: &V, bracket: &Token) -> Result<usize, RuntimeError> {
+    let Some(Literal::Number(number)) = index.literal() else {
+        return Err(RuntimeError {
+            token: bracket.clone(),
+            message: format!("List index must be a number, not {}", index.type_name()),
+            help: None,
+            trace: vec![],
+            value: None,
+        });
+    };
+
+    if number.fract() != 0.0 {
+        return Err(RuntimeError {
+            token: bracket.clone(),
+            message: "List index must be a whole number".to_string(),
+            help: None,
+            trace: vec![],
+            value: None,
+        });
+    }
+
+    if *number < 0.0 || *number as usize >= list.len() {
+        return Err(RuntimeError {
+            token: bracket.clone(),
+            message: format!("List index {number} is out of bounds for length {}", list.len()),
+            help: None,
+            trace: vec![],
+            value: None,
+        });
+    }
+
+    Ok(*number as usize)
+}
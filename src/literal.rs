@@ -1,4 +1,5 @@
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::ops::{Add, Sub, Mul, Div, Not, Neg};
 
 /// Represents a literal value in the language.
@@ -57,9 +58,26 @@ impl PartialOrd for Literal {
     }
 }
 
-// This should not be used, but is required for Hashing
+// Numbers are compared by value which makes `NaN` the only literal that is not equal to itself.
+// Maps do not accept `NaN` as a key, so it is safe to treat literals as `Eq` everywhere else.
 impl Eq for Literal {}
 
+/// Literals are hashed by value so that they can be used as [`Map`](crate::map::Map) keys.
+/// Zero is hashed the same regardless of its sign since `0 == -0`.
+impl Hash for Literal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+
+        match self {
+            Literal::String(string) => string.hash(state),
+            Literal::Number(number) if *number == 0.0 => 0.0f64.to_bits().hash(state),
+            Literal::Number(number) => number.to_bits().hash(state),
+            Literal::Bool(bool) => bool.hash(state),
+            Literal::Null => (),
+        }
+    }
+}
+
 impl Add for Literal {
     type Output = Option<Self>;
 
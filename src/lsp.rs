@@ -0,0 +1,298 @@
+//! A [Language Server Protocol](https://microsoft.github.io/language-server-protocol/) server that
+//! brings diagnostics and navigation to editors.
+//!
+//! The server is started with the `rocks-lsp` binary and talks to the editor over the standard
+//! input and output. Every time a document is opened or changed, it is run through the
+//! [`Scanner`], the [`Parser`] and the [`Resolver`] without being executed. The server supports:
+//! - Publishing the scan, parse and resolve errors of the document as diagnostics.
+//! - Going to the definition of a variable and finding its references, using the scopes of the
+//!   resolver. Properties and methods are looked up at runtime, so they can not be followed.
+//! - Hovering over a name to see whether it is a function, a class, a variable, a parameter or an
+//!   import.
+//! - Listing the classes with their methods and the functions of the document as symbols.
+//!
+//! For example, with [nvim-lspconfig](https://github.com/neovim/nvim-lspconfig):
+//! ```text
+//! vim.lsp.start({ name = 'rocks', cmd = { 'rocks-lsp' }, root_dir = vim.fn.getcwd() })
+//! ```
+//!
+//! Positions are counted in characters, which matches the UTF-16 positions of the protocol as
+//! long as the document has no characters outside of the basic multilingual plane.
+
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+use serde_json::{json, Value};
+
+use crate::error::{Diagnostic, Error, Severity, Span};
+use crate::parser::Parser;
+use crate::resolver::{Resolver, Symbol};
+use crate::scanner::Scanner;
+use crate::stmt::Stmt;
+use crate::token::{Location, Token};
+
+/// The error code of the protocol for requests that the server does not implement.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Represents an analyzed document.
+struct Document {
+    statements: Vec<Stmt>,
+    symbols: Vec<Symbol>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Document {
+    /// Scans, parses and resolves the given source code.
+    /// The document is only resolved if it parsed without errors, since the resolver expects a
+    /// complete syntax tree.
+    fn analyze(source: &str) -> Self {
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan_tokens();
+        let mut diagnostics: Vec<Diagnostic> = scanner.errors().iter().map(Error::to_diagnostic).collect();
+
+        let mut parser = Parser::new(tokens);
+        let statements = parser.parse();
+        diagnostics.extend(parser.errors().iter().map(Error::to_diagnostic));
+
+        let mut symbols = vec![];
+        if parser.errors().is_empty() {
+            let mut resolver = Resolver::new();
+            resolver.resolve(&statements);
+            diagnostics.extend(resolver.errors().iter().map(Error::to_diagnostic));
+            symbols = resolver.symbols();
+        }
+
+        Document { statements, symbols, diagnostics }
+    }
+
+    /// Returns the symbol that is declared or used at the given location.
+    fn symbol(&self, location: Location) -> Option<&Symbol> {
+        self.symbols.iter().find(|symbol| {
+            contains(&symbol.name, location) || symbol.references.iter().any(|name| contains(name, location))
+        })
+    }
+}
+
+/// Returns if the given location is on the name, including the position right after it.
+fn contains(name: &Token, location: Location) -> bool {
+    name.location.line == location.line && (name.location.column..=name.end.column).contains(&location.column)
+}
+
+fn range(span: Span) -> Value {
+    json!({
+        "start": { "line": span.start.line, "character": span.start.column },
+        "end": { "line": span.end.line, "character": span.end.column },
+    })
+}
+
+/// Serves the documents of an editor over the given input and output until the client exits.
+/// ```
+/// use rocks_lang::lsp;
+///
+/// let request = r#"{"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}"#;
+/// let input = format!("Content-Length: {}\r\n\r\n{request}", request.len());
+///
+/// let mut output = Vec::new();
+/// lsp::serve(input.as_bytes(), &mut output);
+///
+/// let output = String::from_utf8(output).unwrap();
+/// assert!(output.contains(r#""definitionProvider":true"#));
+/// ```
+pub fn serve<R: BufRead, W: Write>(input: R, output: W) {
+    let mut server = Server { input, output, documents: HashMap::new() };
+
+    while let Some(message) = server.read() {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+
+        match (method, message.get("id")) {
+            ("exit", _) => return,
+            (_, Some(id)) => {
+                let result = server.request(method, params);
+                server.send(match result {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err((code, message)) => {
+                        json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+                    },
+                });
+            },
+            (_, None) => server.notification(method, params),
+        }
+    }
+}
+
+struct Server<R: BufRead, W: Write> {
+    input: R,
+    output: W,
+    /// The open documents by their URI.
+    documents: HashMap<String, Document>,
+}
+
+impl<R: BufRead, W: Write> Server<R, W> {
+    /// Returns the next message, or `None` if the client closed the connection.
+    fn read(&mut self) -> Option<Value> {
+        let mut length = None;
+        loop {
+            let mut header = String::new();
+            if self.input.read_line(&mut header).ok()? == 0 {
+                return None;
+            }
+
+            let header = header.trim();
+            if header.is_empty() {
+                break;
+            }
+
+            if let Some(value) = header.strip_prefix("Content-Length:") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+
+        let mut body = vec![0; length?];
+        self.input.read_exact(&mut body).ok()?;
+        serde_json::from_slice(&body).ok()
+    }
+
+    fn send(&mut self, message: Value) {
+        let body = message.to_string();
+        write!(self.output, "Content-Length: {}\r\n\r\n{body}", body.len()).expect("writer to not fail on write");
+        self.output.flush().expect("writer to not fail on flush");
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default().to_string();
+
+        match method {
+            "textDocument/didOpen" => {
+                let source = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.open(uri, source);
+            },
+            // The whole document is synchronized on every change.
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array();
+                if let Some(source) = changes.and_then(|changes| changes.last()).and_then(|change| change["text"].as_str()) {
+                    self.open(uri, source);
+                }
+            },
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                self.publish(&uri, vec![]);
+            },
+            _ => (),
+        }
+    }
+
+    fn open(&mut self, uri: String, source: &str) {
+        let document = Document::analyze(source);
+        let diagnostics = document.diagnostics.iter().map(|diagnostic| {
+            json!({
+                "range": range(diagnostic.span),
+                "severity": match diagnostic.severity {
+                    Severity::Error => 1,
+                    Severity::Warning => 2,
+                },
+                "source": "rocks",
+                "message": diagnostic.message,
+            })
+        }).collect();
+
+        self.publish(&uri, diagnostics);
+        self.documents.insert(uri, document);
+    }
+
+    fn publish(&mut self, uri: &str, diagnostics: Vec<Value>) {
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        }));
+    }
+
+    /// Answers a request with its result or with an error code and message.
+    fn request(&mut self, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+        if method == "initialize" {
+            return Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "definitionProvider": true,
+                    "referencesProvider": true,
+                    "hoverProvider": true,
+                    "documentSymbolProvider": true,
+                },
+                "serverInfo": { "name": "rocks-lsp", "version": env!("CARGO_PKG_VERSION") },
+            }));
+        }
+
+        if method == "shutdown" {
+            return Ok(Value::Null);
+        }
+
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let Some(document) = self.documents.get(uri) else {
+            return match method {
+                "textDocument/definition" | "textDocument/hover" => Ok(Value::Null),
+                "textDocument/references" | "textDocument/documentSymbol" => Ok(json!([])),
+                method => Err((METHOD_NOT_FOUND, format!("Unsupported request '{method}'"))),
+            };
+        };
+
+        let position = &params["position"];
+        let location = Location::new(
+            position["line"].as_u64().unwrap_or_default() as usize,
+            position["character"].as_u64().unwrap_or_default() as usize,
+        );
+        let location_of = |name: &Token| json!({ "uri": uri, "range": range(Span::from(name)) });
+
+        match method {
+            "textDocument/definition" => Ok(match document.symbol(location) {
+                Some(symbol) => location_of(&symbol.name),
+                None => Value::Null,
+            }),
+            "textDocument/references" => {
+                let Some(symbol) = document.symbol(location) else {
+                    return Ok(json!([]));
+                };
+
+                let declaration = params["context"]["includeDeclaration"].as_bool().unwrap_or_default();
+                let names = declaration.then_some(&symbol.name).into_iter().chain(&symbol.references);
+                Ok(names.map(location_of).collect())
+            },
+            "textDocument/hover" => Ok(match document.symbol(location) {
+                Some(symbol) => json!({
+                    "contents": { "kind": "markdown", "value": format!("({}) `{}`", symbol.kind, symbol.name.lexeme) },
+                    "range": range(Span::from(&symbol.name)),
+                }),
+                None => Value::Null,
+            }),
+            "textDocument/documentSymbol" => Ok(document_symbols(&document.statements).into()),
+            method => Err((METHOD_NOT_FOUND, format!("Unsupported request '{method}'"))),
+        }
+    }
+}
+
+/// Returns the classes with their methods and the functions declared at the top level.
+fn document_symbols(statements: &[Stmt]) -> Vec<Value> {
+    // The symbol kinds of the protocol.
+    const CLASS: u8 = 5;
+    const METHOD: u8 = 6;
+    const FUNCTION: u8 = 12;
+
+    let symbol = |name: &Token, kind: u8, children: Vec<Value>| {
+        let range = range(Span::from(name));
+        json!({ "name": name.lexeme, "kind": kind, "range": range, "selectionRange": range, "children": children })
+    };
+
+    statements.iter().filter_map(|statement| match statement {
+        Stmt::Class(class) => {
+            let methods = class.methods.iter().filter_map(|method| match method {
+                Stmt::Function(method) => Some(symbol(&method.name, METHOD, vec![])),
+                _ => None,
+            }).collect();
+
+            Some(symbol(&class.name, CLASS, methods))
+        },
+        Stmt::Function(function) => Some(symbol(&function.name, FUNCTION, vec![])),
+        _ => None,
+    }).collect()
+}
+
@@ -1,20 +1,216 @@
-use std::{env, process};
+use std::io::{Read, Write};
+use std::{env, fs, io, process};
 
-use rocks_lang::rocks;
+use rocks_lang::{rocks, Backend, Termination};
+use rocks_lang::config::InterpreterConfig;
+use rocks_lang::debugger::{dap, Debugger};
+use rocks_lang::error::{Reporter, Severity, StderrReporter};
+use rocks_lang::formatter::Formatter;
+use rocks_lang::linter::{Linter, Rule};
+use rocks_lang::render::Format;
+use rocks_lang::stdlib::Stdlib;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
 
-    let mut rocks = rocks::default();
+    let mut backend = Backend::Interpreter;
+    let mut format = Format::Human;
+
+    // Only the flags before the script belong to the interpreter, the rest are passed to the script.
+    while let Some(flag) = args.get(1).filter(|arg| arg.starts_with("--")) {
+        match flag.as_str() {
+            "--vm" => backend = Backend::Vm,
+            "--error-format=human" => format = Format::Human,
+            "--error-format=json" => format = Format::Json,
+            _ => usage(),
+        }
+        args.remove(1);
+    }
+
+    let reporter = StderrReporter::new().with_format(format);
+
+    // The standard output carries the protocol, nothing else can be printed to it.
+    if args.get(1).is_some_and(|command| command == "dap") {
+        dap::serve(io::BufReader::new(io::stdin()), io::stdout());
+        return;
+    }
+
+    if args.get(1).is_some_and(|command| command == "fmt") {
+        fmt(&args[2..], reporter);
+    }
+
+    if args.get(1).is_some_and(|command| command == "lint") {
+        lint(&args[2..], reporter);
+    }
+
+    let debug = args.get(1).is_some_and(|command| command == "debug");
+    if debug {
+        args.remove(1);
+    }
+
+    // Everything after the script is passed to it through `os.args()`.
+    let script_args = args.get(2..).unwrap_or_default().to_vec();
+    let mut rocks = rocks::with_backend(Box::leak(Box::new(std::io::stdout())), backend)
+        .with_config(InterpreterConfig {
+            stdlib: Stdlib::default().with_args(script_args),
+            ..InterpreterConfig::default()
+        })
+        .with_reporter(reporter);
 
     println!("rocks v{}", env!("CARGO_PKG_VERSION"));
 
-    match args.len() {
-        n if n > 2 => {
-            println!("Usage: rocks [script]");
+    let termination = match args.get(1) {
+        Some(script) if script.starts_with("--") => usage(),
+        Some(_) if debug && backend == Backend::Vm => {
+            eprintln!("The debugger only supports the interpreter backend");
             process::exit(64);
         },
-        2 => rocks.run_file(args[1].clone()),
-        _ => rocks.run_prompt(),
+        Some(script) if debug => {
+            let source = fs::read_to_string(script).unwrap_or_else(|err| {
+                eprintln!("Could not read '{script}': {err}");
+                process::exit(66);
+            });
+
+            println!("Debugging {script}, type 'help' for a list of commands");
+            let mut rocks = rocks
+                .with_hook(Debugger::new(&source, io::stdin().lock(), io::stdout()))
+                .expect("interpreter to accept hooks");
+            rocks.run_file(script.clone()).unwrap_or_else(|_| process::exit(65))
+        },
+        None if debug => usage(),
+        Some(script) => rocks.run_file(script.clone()).unwrap_or_else(|_| process::exit(65)),
+        None => rocks.run_prompt(),
     };
+
+    // Exiting the process skips the destructors, so the output of the script is flushed first.
+    if let Termination::Exit(code) = termination {
+        io::stdout().flush().expect("writer to not fail on flush");
+        process::exit(code);
+    }
+}
+
+fn usage() -> ! {
+    println!("Usage: rocks [--vm] [--error-format=human|json] [script [args...]]");
+    println!("       rocks debug script [args...]");
+    println!("       rocks dap");
+    println!("       rocks fmt [--check] [--width N] [files...]");
+    println!("       rocks lint [--allow rule]... files...");
+    process::exit(64);
+}
+
+/// Formats the given files in place, or the standard input into the standard output if there are
+/// no files. With `--check`, the files are not changed and the ones that are not formatted are
+/// listed instead.
+fn fmt(args: &[String], mut reporter: StderrReporter) -> ! {
+    let mut formatter = Formatter::new();
+    let mut check = false;
+    let mut files = vec![];
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--check" => check = true,
+            "--width" => match args.next().and_then(|width| width.parse().ok()) {
+                Some(width) => formatter = formatter.with_width(width),
+                None => usage(),
+            },
+            arg if arg.starts_with("--") => usage(),
+            file => files.push(file),
+        }
+    }
+
+    let mut format = |name: &str, source: &str| {
+        formatter.format(source).unwrap_or_else(|diagnostics| {
+            reporter.source(Some(name), source);
+            for diagnostic in diagnostics {
+                reporter.report(&diagnostic);
+            }
+            process::exit(65);
+        })
+    };
+
+    if files.is_empty() {
+        let mut source = String::new();
+        if let Err(err) = io::stdin().read_to_string(&mut source) {
+            eprintln!("Could not read the standard input: {err}");
+            process::exit(66);
+        }
+
+        let formatted = format("<stdin>", &source);
+        if check {
+            process::exit(if formatted == source { 0 } else { 1 });
+        }
+
+        print!("{formatted}");
+        process::exit(0);
+    }
+
+    let mut unformatted = false;
+    for file in files {
+        let source = fs::read_to_string(file).unwrap_or_else(|err| {
+            eprintln!("Could not read '{file}': {err}");
+            process::exit(66);
+        });
+
+        let formatted = format(file, &source);
+        if formatted == source {
+            continue;
+        }
+
+        if check {
+            println!("{file}");
+            unformatted = true;
+        } else if let Err(err) = fs::write(file, formatted) {
+            eprintln!("Could not write '{file}': {err}");
+            process::exit(74);
+        }
+    }
+
+    process::exit(if unformatted { 1 } else { 0 });
+}
+
+/// Lints the given files and reports the warnings. Exits with 1 if there are any warnings and
+/// with 65 if any of the files has errors.
+fn lint(args: &[String], mut reporter: StderrReporter) -> ! {
+    let mut linter = Linter::new();
+    let mut files = vec![];
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--allow" => match args.next().map(|rule| rule.parse::<Rule>()) {
+                Some(Ok(rule)) => linter = linter.with_rule(rule, false),
+                Some(Err(err)) => {
+                    eprintln!("{err}");
+                    process::exit(64);
+                },
+                None => usage(),
+            },
+            arg if arg.starts_with("--") => usage(),
+            file => files.push(file),
+        }
+    }
+
+    if files.is_empty() {
+        usage();
+    }
+
+    let mut code = 0;
+    for file in files {
+        let source = fs::read_to_string(file).unwrap_or_else(|err| {
+            eprintln!("Could not read '{file}': {err}");
+            process::exit(66);
+        });
+
+        reporter.source(Some(file), &source);
+        for diagnostic in linter.lint(&source) {
+            reporter.report(&diagnostic);
+            code = match diagnostic.severity {
+                Severity::Error => 65,
+                Severity::Warning => code.max(1),
+            };
+        }
+    }
+
+    process::exit(code);
 }
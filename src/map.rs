@@ -0,0 +1,175 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use crate::error::RuntimeError;
+use crate::literal::Literal;
+use crate::object::{fmt_once, Arity, Object};
+use crate::stdlib::StdValue;
+use crate::token::Token;
+
+/// Represents a map (also known as a dictionary) in the language.
+/// ##### Keys
+/// Only literals can be used as keys, meaning strings, numbers, booleans and null. Keys are
+/// compared by value, so `1` and `1.0` refer to the same entry.
+/// ##### Ordering
+/// Entries are kept in the order that their keys were first inserted in. This order is used
+/// when the map is printed and when its keys or values are listed.
+///
+/// The type of the values is generic so that the map can be shared between the interpreter and
+/// the [`vm`](crate::vm).
+#[derive(Debug, Clone)]
+pub struct Map<V = Object> {
+    entries: HashMap<Literal, V>,
+    keys: Vec<Literal>,
+}
+
+impl<V: Clone> Map<V> {
+    /// Returns the value associated with the given key (if any).
+    pub fn get(&self, key: &Literal) -> Option<&V> {
+        self.entries.get(key)
+    }
+
+    /// Associates the given value with the given key, replacing the previous value (if any).
+    pub fn insert(&mut self, key: Literal, value: V) {
+        if !self.entries.contains_key(&key) {
+            self.keys.push(key.clone());
+        }
+
+        self.entries.insert(key, value);
+    }
+
+    /// Removes the entry with the given key and returns its value (if any).
+    pub fn remove(&mut self, key: &Literal) -> Option<V> {
+        let value = self.entries.remove(key)?;
+        self.keys.retain(|k| k != key);
+        Some(value)
+    }
+
+    /// Returns if there is an entry with the given key.
+    pub fn contains_key(&self, key: &Literal) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns if the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Returns the keys of the map in insertion order.
+    pub fn keys(&self) -> &[Literal] {
+        &self.keys
+    }
+
+    /// Returns the values of the map in insertion order.
+    pub fn values(&self) -> Vec<V> {
+        self.keys.iter().map(|key| self.entries[key].clone()).collect()
+    }
+
+    /// Returns an iterator over the entries of the map in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Literal, &V)> {
+        self.keys.iter().map(|key| (key, &self.entries[key]))
+    }
+}
+
+impl<V> Default for Map<V> {
+    fn default() -> Self {
+        Map { entries: HashMap::new(), keys: Vec::new() }
+    }
+}
+
+impl<V: Display> Display for Map<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_once(self, f, "{...}", |f| {
+            let entries = self.keys
+                .iter()
+                .map(|key| format!("{key}: {}", self.entries[key]))
+                .collect::<Vec<String>>()
+                .join(", ");
+
+            write!(f, "{{{entries}}}")
+        })
+    }
+}
+
+/// Converts the given value into a map key.
+/// Only literals except `NaN` can be used as keys, any other value will return an error.
+pub fn key<V: StdValue>(value: &V, token: &Token) -> Result<Literal, RuntimeError> {
+    match value.literal() {
+        Some(Literal::Number(number)) if number.is_nan() => Err(RuntimeError {
+            token: token.clone(),
+            message: "Map key cannot be NaN".to_string(),
+            help: None,
+            trace: vec![],
+            value: None,
+        }),
+        Some(literal) => Ok(literal.clone()),
+        None => Err(RuntimeError {
+            token: token.clone(),
+            message: format!("Map key must be a literal, not {}", value.type_name()),
+            help: None,
+            trace: vec![],
+            value: None,
+        }),
+    }
+}
+
+/// Returns the built-in method with the given name bound to the given map.
+/// Methods are implemented as native functions that are bound to the map they were accessed on,
+/// which lets the cycle collector see the reference. If there is no method with the given name,
+/// it will return an error.
+///
+/// The methods are written against [`StdValue`] so that both backends share them.
+///
+/// Current map methods:
+/// - `has(key)` - Returns if the map has an entry with the given key.
+/// - `remove(key)` - Removes the entry with the given key and returns its value (or null).
+/// - `keys()` - Returns a list of the keys in the map.
+/// - `values()` - Returns a list of the values in the map.
+/// - `len()` - Returns the number of entries in the map.
+pub fn get_method<V: StdValue>(map: &V, name: &Token) -> Result<V, RuntimeError> {
+    let token = name.clone();
+
+    let method = match name.lexeme.as_str() {
+        "has" => V::method(map.clone(), "has", Arity::Fixed(1), move |_, map, arguments| {
+            let key = key(&arguments[0], &token)?;
+            Ok(V::from(Literal::Bool(bound_map(map).borrow().contains_key(&key))))
+        }),
+        "remove" => V::method(map.clone(), "remove", Arity::Fixed(1), move |_, map, arguments| {
+            let key = key(&arguments[0], &token)?;
+            Ok(bound_map(map).borrow_mut().remove(&key).unwrap_or(V::from(Literal::Null)))
+        }),
+        "keys" => V::method(map.clone(), "keys", Arity::Fixed(0), move |_, map, _| {
+            let keys = bound_map(map).borrow().keys().iter().cloned().map(V::from).collect::<Vec<V>>();
+            Ok(V::from(keys))
+        }),
+        "values" => V::method(map.clone(), "values", Arity::Fixed(0), move |_, map, _| {
+            Ok(V::from(bound_map(map).borrow().values()))
+        }),
+        "len" => V::method(map.clone(), "len", Arity::Fixed(0), move |_, map, _| {
+            Ok(V::from(Literal::Number(bound_map(map).borrow().len() as f64)))
+        }),
+        _ => {
+            return Err(RuntimeError {
+                token: name.clone(),
+                message: format!("Undefined property '{}'", name.lexeme),
+                help: None,
+                trace: vec![],
+                value: None,
+            });
+        }
+    };
+
+    Ok(method)
+}
+
+/// Returns the shared entries of the map that a method is bound to.
+fn bound_map<V: StdValue>(map: &V) -> &Rc<RefCell<Map<V>>> {
+    map.map().expect("map methods to be bound to maps")
+}
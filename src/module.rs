@@ -0,0 +1,239 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::error::{Error, RuntimeError};
+use crate::literal::Literal;
+use crate::object::Object;
+use crate::parser::Parser;
+use crate::resolver::Resolver;
+use crate::scanner::Scanner;
+use crate::stmt::Stmt;
+use crate::token::Token;
+
+/// Represents the namespace object of an imported module.
+/// It exposes the top-level bindings (variables, functions, classes and imports) that the module
+/// declared. Bindings are read like properties, e.g. `lib.helper`, and always return their current
+/// value in the module.
+///
+/// The type of the values is generic so that modules can be shared between the interpreter and
+/// the [`vm`](crate::vm).
+#[derive(Debug)]
+pub struct Module<V = Object> {
+    pub name: String,
+    /// The globals of the module, they also hold the native functions that are not exported.
+    globals: Rc<RefCell<HashMap<String, V>>>,
+    exports: Vec<String>,
+}
+
+impl<V: Clone> Module<V> {
+    /// Creates a new module with the given name that exports the given globals.
+    pub fn new(name: String, globals: Rc<RefCell<HashMap<String, V>>>, exports: Vec<String>) -> Self {
+        Module { name, globals, exports }
+    }
+
+    /// Returns the value of the binding with the given name.
+    pub fn get(&self, name: &Token) -> Result<V, RuntimeError> {
+        let value = match self.exports.contains(&name.lexeme) {
+            true => self.globals.borrow().get(&name.lexeme).cloned(),
+            false => None,
+        };
+
+        value.ok_or_else(|| RuntimeError {
+            token: name.clone(),
+            message: format!("Module '{}' has no binding '{}'", self.name, name.lexeme),
+            help: None,
+            trace: vec![],
+            value: None,
+        })
+    }
+
+    /// Returns the names of the bindings that the module exports.
+    pub fn exports(&self) -> &[String] {
+        &self.exports
+    }
+}
+
+impl<V> Display for Module<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<module {}>", self.name)
+    }
+}
+
+/// Represents a module that has been read and checked but not executed yet.
+pub struct Source {
+    /// The name of the module, which is the name of its file without the extension.
+    pub name: String,
+    pub statements: Vec<Stmt>,
+    /// The resolver that checked the statements, it holds the slots of their local variables.
+    pub resolver: Resolver,
+}
+
+impl Source {
+    /// Returns the names of the top-level bindings that the module declares.
+    pub fn exports(&self) -> Vec<String> {
+        self.statements.iter().flat_map(|statement| match statement {
+            Stmt::Var(data) => vec![data.name.lexeme.clone()],
+            Stmt::Function(data) => vec![data.name.lexeme.clone()],
+            Stmt::Class(data) => vec![data.name.lexeme.clone()],
+            Stmt::Import(data) => match &data.alias {
+                Some(alias) => vec![alias.lexeme.clone()],
+                None => data.names.iter().map(|name| name.lexeme.clone()).collect(),
+            },
+            _ => vec![],
+        }).collect()
+    }
+}
+
+/// Finds, reads and caches the modules imported by a script.
+///
+/// Import paths are relative to the file that contains the import statement. Every module is
+/// executed only once, importing it again returns the cached namespace object. A module that
+/// (directly or indirectly) imports itself while it is being loaded is reported as an import
+/// cycle.
+///
+/// The native modules of the [`stdlib`](crate::stdlib) are registered by name and take precedence
+/// over files.
+#[derive(Debug)]
+pub struct Loader<V = Object> {
+    natives: HashMap<String, Rc<Module<V>>>,
+    cache: HashMap<PathBuf, Rc<Module<V>>>,
+    /// The files that are being executed, the innermost one is the last.
+    loading: Vec<PathBuf>,
+}
+
+impl<V> Loader<V> {
+    pub fn new() -> Self {
+        Loader { natives: HashMap::new(), cache: HashMap::new(), loading: vec![] }
+    }
+
+    /// Replaces the native modules that can be imported by name.
+    pub fn set_natives(&mut self, modules: Vec<Module<V>>) {
+        self.natives = modules
+            .into_iter()
+            .map(|module| (module.name.clone(), Rc::new(module)))
+            .collect();
+    }
+
+    /// Returns the native module that the path of an import statement names (if there is one).
+    pub fn native(&self, path: &Token) -> Option<Rc<Module<V>>> {
+        self.natives.get(&path.lexeme).cloned()
+    }
+
+    /// Sets the file of the script that is being run.
+    /// Imports of scripts that are not run from a file are relative to the working directory.
+    pub fn set_script(&mut self, path: &Path) {
+        self.loading = fs::canonicalize(path).into_iter().collect();
+    }
+
+    /// Forgets the file of the script, imports are relative to the working directory again.
+    pub fn clear_script(&mut self) {
+        self.loading.clear();
+    }
+
+    /// Resolves the path of an import statement to the file of the module.
+    pub fn resolve(&self, path: &Token) -> Result<PathBuf, RuntimeError> {
+        let Some(Literal::String(relative)) = &path.literal else {
+            unreachable!("import paths are string literals");
+        };
+
+        let base = match self.loading.last().and_then(|file| file.parent()) {
+            Some(directory) => directory.join(relative),
+            None => PathBuf::from(relative),
+        };
+
+        fs::canonicalize(base).map_err(|_| RuntimeError {
+            token: path.clone(),
+            message: format!("Could not find module '{relative}'"),
+            help: None,
+            trace: vec![],
+            value: None,
+        })
+    }
+
+    /// Returns the cached module of the given file (if it was loaded before).
+    pub fn get(&self, file: &Path) -> Option<Rc<Module<V>>> {
+        self.cache.get(file).cloned()
+    }
+
+    /// Reads and checks the module in the given file and marks it as being loaded.
+    /// Every successful call has to be followed by a call to [`Loader::finish`].
+    pub fn load(&mut self, file: &Path, path: &Token) -> Result<Source, RuntimeError> {
+        if let Some(start) = self.loading.iter().position(|loading| loading == file) {
+            let cycle = self.loading[start..]
+                .iter()
+                .chain([&file.to_path_buf()])
+                .map(|file| file_name(file))
+                .collect::<Vec<String>>()
+                .join(" -> ");
+
+            return Err(RuntimeError {
+                token: path.clone(),
+                message: format!("Import cycle detected: {cycle}"),
+                help: None,
+                trace: vec![],
+                value: None,
+            });
+        }
+
+        let error = |message: String| RuntimeError {
+            token: path.clone(),
+            message: format!("Could not load module '{}': {message}", file_name(file)),
+            help: None,
+            trace: vec![],
+            value: None,
+        };
+
+        let mut source = fs::read_to_string(file).map_err(|err| error(err.to_string()))?;
+        if !source.ends_with('\n') {
+            source.push('\n');
+        }
+
+        let mut scanner = Scanner::new(&source);
+        let tokens = scanner.scan_tokens();
+        if let Some(err) = scanner.errors().first() {
+            return Err(error(err.to_diagnostic().to_string()));
+        }
+
+        let mut parser = Parser::new(tokens);
+        let statements = parser.parse();
+        if let Some(err) = parser.errors().first() {
+            return Err(error(err.to_diagnostic().to_string()));
+        }
+
+        let mut resolver = Resolver::new();
+        resolver.resolve(&statements);
+        if let Some(err) = resolver.errors().first() {
+            return Err(error(err.to_diagnostic().to_string()));
+        }
+
+        self.loading.push(file.to_path_buf());
+
+        let name = file.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        Ok(Source { name, statements, resolver })
+    }
+
+    /// Marks the innermost module as loaded and caches its namespace object if it was executed
+    /// successfully.
+    pub fn finish(&mut self, module: Option<Rc<Module<V>>>) {
+        let file = self.loading.pop().expect("a module to be loading");
+
+        if let Some(module) = module {
+            self.cache.insert(file, module);
+        }
+    }
+}
+
+impl<V> Default for Loader<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the name of the given file, which is how files are shown in diagnostics.
+pub fn file_name(file: &Path) -> String {
+    file.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default()
+}
@@ -6,7 +6,10 @@ use std::rc::Rc;
 use crate::class::{Class, Instance};
 use crate::error::RuntimeError;
 use crate::function::{Function, NativeFunction};
+use crate::gc::{Trace, Tracer};
 use crate::literal::Literal;
+use crate::map::Map;
+use crate::module::Module;
 use crate::interpreter::Interpreter;
 
 /// Represents an object that can be stored in a variable or returned from a function.
@@ -18,6 +21,12 @@ pub enum Object {
     NativeFunction(NativeFunction),
     Class(Rc<RefCell<Class>>),
     Instance(Rc<RefCell<Instance>>),
+    List(Rc<RefCell<Vec<Object>>>),
+    Map(Rc<RefCell<Map>>),
+    /// A caught runtime error.
+    Error(Rc<RuntimeError>),
+    /// The namespace object of an imported module.
+    Module(Rc<Module>),
 }
 
 impl Object {
@@ -36,6 +45,10 @@ impl Object {
             Object::NativeFunction(_) => "native function",
             Object::Class(_) => "class",
             Object::Instance(_) => "instance",
+            Object::List(_) => "list",
+            Object::Map(_) => "map",
+            Object::Error(_) => "error",
+            Object::Module(_) => "module",
         }
     }
 }
@@ -48,6 +61,10 @@ impl PartialEq for Object {
             (Object::NativeFunction(left), Object::NativeFunction(right)) => left == right,
             (Object::Class(left), Object::Class(right)) => left == right,
             (Object::Instance(left), Object::Instance(right)) => left == right,
+            (Object::List(left), Object::List(right)) => Rc::ptr_eq(left, right),
+            (Object::Map(left), Object::Map(right)) => Rc::ptr_eq(left, right),
+            (Object::Error(left), Object::Error(right)) => Rc::ptr_eq(left, right),
+            (Object::Module(left), Object::Module(right)) => Rc::ptr_eq(left, right),
             _ => false,
         }
     }
@@ -188,6 +205,18 @@ impl From<Rc<RefCell<Instance>>> for Object {
     }
 }
 
+impl From<Vec<Object>> for Object {
+    fn from(value: Vec<Object>) -> Self {
+        Object::List(Rc::new(RefCell::new(value)))
+    }
+}
+
+impl From<Map> for Object {
+    fn from(value: Map) -> Self {
+        Object::Map(Rc::new(RefCell::new(value)))
+    }
+}
+
 impl Display for Object {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -196,10 +225,82 @@ impl Display for Object {
             Object::NativeFunction(function) => write!(f, "{function}"),
             Object::Class(class) => write!(f, "{}", class.borrow()),
             Object::Instance(instance) => write!(f, "{}", instance.borrow()),
+            Object::List(list) => fmt_once(Rc::as_ptr(list), f, "[...]", |f| {
+                let elements = list.borrow()
+                    .iter()
+                    .map(|element| element.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "[{elements}]")
+            }),
+            Object::Map(map) => write!(f, "{}", map.borrow()),
+            Object::Error(error) => write!(f, "<error {}>", error.message),
+            Object::Module(module) => write!(f, "{module}"),
         }
     }
 }
 
+thread_local! {
+    /// The addresses of the lists and maps that are currently being formatted.
+    static FORMATTING: RefCell<Vec<*const ()>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Formats a list or map at the given address with the given function.
+/// If the same value is already being formatted further up, it contains itself and the
+/// placeholder is written instead to avoid recursing forever.
+pub fn fmt_once<T>(
+    address: *const T,
+    f: &mut std::fmt::Formatter<'_>,
+    placeholder: &str,
+    fmt: impl FnOnce(&mut std::fmt::Formatter<'_>) -> std::fmt::Result,
+) -> std::fmt::Result {
+    let address = address as *const ();
+
+    if FORMATTING.with(|formatting| formatting.borrow().contains(&address)) {
+        return write!(f, "{placeholder}");
+    }
+
+    FORMATTING.with(|formatting| formatting.borrow_mut().push(address));
+    let result = fmt(f);
+    FORMATTING.with(|formatting| formatting.borrow_mut().pop());
+
+    result
+}
+
+/// Represents the number of arguments that a callable object accepts.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Arity {
+    /// Accepts exactly the given number of arguments.
+    Fixed(usize),
+    /// Accepts the given number of arguments or more.
+    Variadic(usize),
+}
+
+impl Arity {
+    /// Returns if the given number of arguments is accepted.
+    pub fn accepts(&self, count: usize) -> bool {
+        match self {
+            Arity::Fixed(arity) => count == *arity,
+            Arity::Variadic(minimum) => count >= *minimum,
+        }
+    }
+}
+
+/// Formats the number of arguments with the noun, like `1 argument` or `at least 2 arguments`.
+impl Display for Arity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let count = match self {
+            Arity::Fixed(arity) => *arity,
+            Arity::Variadic(minimum) => {
+                write!(f, "at least ")?;
+                *minimum
+            },
+        };
+
+        write!(f, "{count} argument{}", if count == 1 { "" } else { "s" })
+    }
+}
+
 /// Represents a callable object in the language.
 pub trait Callable: Debug {
     /// Calls the object with the given arguments and the current state of the interpreter.
@@ -209,5 +310,20 @@ pub trait Callable: Debug {
     fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, RuntimeError>;
 
     /// Returns the arity of the object.
-    fn arity(&self) -> usize;
+    fn arity(&self) -> Arity;
+}
+
+impl Trace for Object {
+    fn trace(&self, tracer: &mut Tracer) {
+        match self {
+            Object::Function(function) => function.trace(tracer),
+            Object::NativeFunction(function) => function.trace(tracer),
+            Object::Class(class) => tracer.edge(class),
+            Object::Instance(instance) => tracer.edge(instance),
+            Object::List(list) => tracer.edge(list),
+            Object::Map(map) => tracer.edge(map),
+            // Modules are kept alive by the loader, errors can not form cycles.
+            Object::Literal(_) | Object::Error(_) | Object::Module(_) => (),
+        }
+    }
 }
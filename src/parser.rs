@@ -1,4 +1,6 @@
-use crate::error::{Error, ParseError};
+use std::cell::Cell;
+
+use crate::error::{self, ParseError};
 use crate::token::{Token, Type};
 use crate::literal::Literal;
 use crate::expr::*;
@@ -33,29 +35,35 @@ macro_rules! matches {
 ///
 /// ### Declarations
 /// ```text
-/// Declaration -> ClassDecl | FunDecl | VarDecl | Statement ;
+/// Declaration -> ClassDecl | FunDecl | VarDecl | ImportDecl | Statement ;
 /// ClassDecl   -> "class" IDENTIFIER ( "<" IDENTIFIER )? "{" Function* "}" ;
 /// FunDecl     -> "fun" Function ;
 /// VarDecl     -> "var" IDENTIFIER ( "=" Expression )? ";" ;
+/// ImportDecl  -> "import" STRING "as" IDENTIFIER ";"
+///              | "from" STRING "import" IDENTIFIER ( "," IDENTIFIER )* ";" ;
 /// ```
 ///
 /// ### Statements
 /// ```text
-/// Statement   -> ExprStmt | ForStmt | IfStmt | PrintStmt | ReturnStmt | BreakStmt | WhileStmt | Block ;
+/// Statement   -> ExprStmt | ForStmt | IfStmt | PrintStmt | ReturnStmt | BreakStmt | ContinueStmt | ThrowStmt | TryStmt | WhileStmt | LabeledStmt | Block ;
 /// ExprStmt    -> Expression ";" ;
 /// ForStmt     -> "for" "(" ( VarDecl | ExprStmt | ";" ) Expression? ";" Expression? ")" Statement ;
 /// IfStmt      -> "if" "(" Expression ")" Statement ( "else" Statement )? ;
 /// PrintStmt   -> "print" Expression ";" ;
 /// ReturnStmt  -> "return" Expression? ";" ;
-/// BreakStmt   -> "break" ";" ;
+/// BreakStmt   -> "break" IDENTIFIER? ";" ;
+/// ContinueStmt -> "continue" IDENTIFIER? ";" ;
+/// ThrowStmt   -> "throw" Expression ";" ;
+/// TryStmt     -> "try" Block ( "catch" "(" IDENTIFIER ")" Block )? ( "finally" Block )? ;
 /// WhileStmt   -> "while" "(" Expression ")" Statement ;
+/// LabeledStmt -> IDENTIFIER ":" ( ForStmt | WhileStmt ) ;
 /// Block       -> "{" Decleration* "}" ;
 /// ```
 ///
 /// ### Expressions
 /// ```text
 /// Expression  -> Assignment ;
-/// Assignment  -> ( Call "." )? IDENTIFIER "=" Assignment | LogicOr ;
+/// Assignment  -> ( Call "." )? IDENTIFIER "=" Assignment | Call "[" Expression "]" "=" Assignment | LogicOr ;
 /// LogicOr     -> LogicAnd ( "or" LogicAnd )* ;
 /// LogicAnd    -> Equality ( "and" Equality )* ;
 /// Equality    -> Comparison ( ( "!=" | "==" ) Comparison )* ;
@@ -63,27 +71,35 @@ macro_rules! matches {
 /// Term        -> Factor ( ( "+" | "-" ) Factor )* ;
 /// Factor      -> Unary ( ( "*" | "/" ) Unary )* ;
 /// Unary       -> ( "!" | "-" ) Unary | Call ;
-/// Call        -> Primary ( "(" Arguments? ")" | "." IDENTIFIER )* ;
-/// Primary     -> NUMBER | STRING | "false" | "true" | "null" | "this" | "(" Expression ")" | IDENTIFIER | "super" "." IDENTIFIER ;
+/// Call        -> Primary ( "(" Arguments? ")" | "." IDENTIFIER | "[" Expression "]" )* ;
+/// Primary     -> NUMBER | STRING | "false" | "true" | "null" | "this" | "(" Expression ")" | IDENTIFIER | "super" "." IDENTIFIER | List | Map | Lambda ;
 /// ```
 ///
 /// ### Misc
 /// ```text
 /// Function    -> IDENTIFIER "(" Parameters? ")" Block ;
+/// Lambda      -> "fun" "(" Parameters? ")" ( Block | "=>" Expression ) ;
 /// Parameters  -> IDENTIFIER ( "," IDENTIFIER )* ;
 /// Arguments   -> Expression ( "," Expression )* ;
+/// List        -> "[" Arguments? "]" ;
+/// Map         -> "{" ( Entry ( "," Entry )* )? "}" ;
+/// Entry       -> Expression ":" Expression ;
 /// ```
 pub struct Parser {
     /// The tokens to parse.
     tokens: Vec<Token>,
     /// The current token index.
     current: u32,
+    /// The errors encountered while parsing.
+    errors: Vec<ParseError>,
+    /// The number of blocks that enclose the current token.
+    depth: u32,
 }
 
 impl Parser {
     /// Creates a new parser with the given tokens.
     pub fn new(tokens: Vec<Token>) -> Self {
-        Parser { tokens, current: 0 }
+        Parser { tokens, current: 0, errors: Vec::new(), depth: 0 }
     }
 
     /// Parses the tokens and returns the resulting expression.
@@ -99,6 +115,31 @@ impl Parser {
         statements
     }
 
+    /// Parses the tokens as a single expression without a trailing semicolon.
+    pub fn parse_expression(&mut self) -> Option<Expr> {
+        let expr = self.expression().and_then(|expr| match self.is_at_end() {
+            true => Ok(expr),
+            false => Err(ParseError {
+                token: self.peek().clone(),
+                message: "Expected end of expression".to_string(),
+                help: None,
+            }),
+        });
+
+        match expr {
+            Ok(expr) => Some(expr),
+            Err(error) => {
+                self.errors.push(error);
+                None
+            }
+        }
+    }
+
+    /// Returns the errors encountered while parsing.
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
     /// Returns the next token without consuming it.
     fn peek(&mut self) -> &Token {
         &self.tokens[self.current as usize]
@@ -123,6 +164,19 @@ impl Parser {
         self.peek().r#type == r#type
     }
 
+    /// Returns if the token after the next one is of the given type.
+    fn check_next(&mut self, r#type: Type) -> bool {
+        match self.tokens.get(self.current as usize + 1) {
+            Some(token) => token.r#type == r#type,
+            None => false,
+        }
+    }
+
+    /// Returns if the next token is an identifier that is used as the given contextual keyword.
+    fn check_contextual(&mut self, keyword: &str) -> bool {
+        self.check(Type::Identifier) && self.peek().lexeme == keyword
+    }
+
     /// Consumes the next token and returns it.
     fn advance(&mut self) -> &Token {
         if !self.is_at_end() {
@@ -142,6 +196,7 @@ impl Parser {
         Err(ParseError {
             token: self.peek().clone(),
             message: message.to_string(),
+            help: None,
         }) 
     }
 
@@ -149,10 +204,18 @@ impl Parser {
     fn decleration(&mut self) -> Option<Stmt> {
         let statement = if matches!(self, Type::Class) {
            self.class_decleration()
-        } else if matches!(self, Type::Fun) {
+        } else if self.check(Type::Fun) && self.check_next(Type::Identifier) {
+            // A 'fun' without a name starts a lambda expression instead.
+            self.advance();
             self.function("function")
         } else if matches!(self, Type::Var) {
             self.var_decleration()
+        } else if matches!(self, Type::Import) {
+            self.import_decleration()
+        } else if self.check_contextual("from") && self.check_next(Type::String) {
+            // 'from' is only a keyword when it starts an import, it can still name variables.
+            self.advance();
+            self.selective_import_decleration()
         } else {
             self.statement()
         };
@@ -160,7 +223,7 @@ impl Parser {
         match statement {
             Ok(stmt) => Some(stmt),
             Err(error) => {
-                error.throw();
+                self.errors.push(error);
                 self.synchronize();
                 None
             }
@@ -173,7 +236,7 @@ impl Parser {
 
         let superclass = if matches!(self, Type::Less) {
             self.consume(Type::Identifier, "Expected superclass name")?;
-            Some(Expr::Variable(VariableData { name: self.previous().clone() }))
+            Some(Expr::Variable(VariableData { slot: Cell::new(None), name: self.previous().clone() }))
         } else {
             None
         };
@@ -182,7 +245,14 @@ impl Parser {
 
         let mut methods: Vec<Stmt> = vec![];
         while !self.check(Type::RightBrace) && !self.is_at_end() {
-            methods.push(self.function("method")?);
+            // A broken method does not hide the errors in the rest of the class body.
+            match self.function("method") {
+                Ok(method) => methods.push(method),
+                Err(error) => {
+                    self.errors.push(error);
+                    self.synchronize_method();
+                }
+            }
         }
 
         self.consume(Type::RightBrace, "Expected '}' after class body")?;
@@ -206,22 +276,56 @@ impl Parser {
         Ok(Stmt::Var(VarData { name, initializer }))
     }
 
+    /// Parses an import decleration that binds the whole module.
+    fn import_decleration(&mut self) -> ParseResult<Stmt> {
+        let keyword = self.previous().clone();
+        let path = self.consume(Type::String, "Expected module path after 'import'")?.clone();
+
+        if !self.check_contextual("as") {
+            return Err(ParseError {
+                token: self.peek().clone(),
+                message: "Expected 'as' after module path".to_string(),
+                help: None,
+            });
+        }
+        self.advance();
+
+        let alias = self.consume(Type::Identifier, "Expected module name after 'as'")?.clone();
+        self.consume(Type::Semicolon, "Expected ';' after import")?;
+
+        Ok(Stmt::Import(ImportData { keyword, path, alias: Some(alias), names: vec![] }))
+    }
+
+    /// Parses an import decleration that binds the given names of the module.
+    fn selective_import_decleration(&mut self) -> ParseResult<Stmt> {
+        let path = self.consume(Type::String, "Expected module path after 'from'")?.clone();
+        let keyword = self.consume(Type::Import, "Expected 'import' after module path")?.clone();
+
+        let mut names = vec![self.consume(Type::Identifier, "Expected name to import")?.clone()];
+        while matches!(self, Type::Comma) {
+            names.push(self.consume(Type::Identifier, "Expected name to import")?.clone());
+        }
+
+        self.consume(Type::Semicolon, "Expected ';' after import")?;
+
+        Ok(Stmt::Import(ImportData { keyword, path, alias: None, names }))
+    }
+
     /// Parses a while statement.
-    fn while_statement(&mut self) -> ParseResult<Stmt> {
+    fn while_statement(&mut self, label: Option<Token>) -> ParseResult<Stmt> {
+        let keyword = self.previous().clone();
         self.consume(Type::LeftParen, "Expected '(' after while.")?;
         let condition = self.expression()?;
         self.consume(Type::RightParen, "Expected ')' after condition.")?;
 
-        let body = match self.statement() {
-            Ok(stmt) => stmt,
-            Err(error) => {
-                return Err(error);
-            }
-        };
+        let body = self.statement()?;
 
         Ok(Stmt::While(WhileData {
+            keyword,
             condition,
             body: Box::new(body),
+            increment: None,
+            label,
         }))
     }
 
@@ -232,8 +336,12 @@ impl Parser {
 
     /// Parses a statement.
     fn statement(&mut self) -> ParseResult<Stmt> {
+        if self.check(Type::Identifier) && self.check_next(Type::Colon) {
+            return self.labeled_statement();
+        }
+
         if matches!(self, Type::For) {
-            return self.for_statement();
+            return self.for_statement(None);
         }
 
         if matches!(self, Type::If) {
@@ -252,8 +360,20 @@ impl Parser {
             return self.break_statement();
         }
 
+        if matches!(self, Type::Continue) {
+            return self.continue_statement();
+        }
+
+        if matches!(self, Type::Throw) {
+            return self.throw_statement();
+        }
+
+        if matches!(self, Type::Try) {
+            return self.try_statement();
+        }
+
         if matches!(self, Type::While) {
-            return self.while_statement();
+            return self.while_statement(None);
         }
 
         if matches!(self, Type::LeftBrace) {
@@ -263,8 +383,29 @@ impl Parser {
         self.expression_statement()
     }
 
+    /// Parses a labeled loop statement.
+    fn labeled_statement(&mut self) -> ParseResult<Stmt> {
+        let label = self.advance().clone();
+        self.advance();
+
+        if matches!(self, Type::For) {
+            return self.for_statement(Some(label));
+        }
+
+        if matches!(self, Type::While) {
+            return self.while_statement(Some(label));
+        }
+
+        Err(ParseError {
+            token: self.peek().clone(),
+            message: "Expected loop after label".to_string(),
+            help: None,
+        })
+    }
+
     /// Parses a for statement.
-    fn for_statement(&mut self) -> ParseResult<Stmt> {
+    fn for_statement(&mut self, label: Option<Token>) -> ParseResult<Stmt> {
+        let keyword = self.previous().clone();
         self.consume(Type::LeftParen, "Expected '(' after 'for'")?;
 
         let initializer: Option<Stmt>;
@@ -288,30 +429,16 @@ impl Parser {
         };
         self.consume(Type::RightParen, "Expected ')' after loop clauses")?;
 
-        let mut body = match self.statement() {
-            Ok(stmt) => stmt,
-            Err(error) => {
-                return Err(error);
-            }
-        };
-
-        // Execute the increment after the body.
-        if let Some(increment) = increment {
-            body = Stmt::Block(BlockData {
-                statements: vec![
-                    body,
-                    Stmt::Expression(ExpressionData {
-                        expr: increment
-                    }),
-                ],
-            });
-        }
+        let mut body = self.statement()?;
 
-        // Wrap the body into a while loop.
+        // Wrap the body into a while loop that executes the increment after the body.
         // If there is no condition, use true.
         body = Stmt::While(WhileData {
+            keyword,
             condition: condition.unwrap_or(Expr::Literal(Literal::Bool(true))),
             body: Box::new(body),
+            increment,
+            label,
         });
 
         // Add the initializer before the loop if there is one.
@@ -344,14 +471,12 @@ impl Parser {
 
     /// Parses a print statement.
     fn print_statement(&mut self) -> ParseResult<Stmt> {
-        let expr = match self.expression() {
-            Ok(expr) => expr,
-            Err(error) => return Err(error),
-        };
+        let keyword = self.previous().to_owned();
+        let expr = self.expression()?;
 
         self.consume(Type::Semicolon, "Expected ';' after value")?;
 
-        Ok(Stmt::Print(PrintData { expr }))
+        Ok(Stmt::Print(PrintData { keyword, expr }))
     }
 
     /// Parses a return statement.
@@ -371,19 +496,86 @@ impl Parser {
     fn break_statement(&mut self) -> ParseResult<Stmt> {
         let keyword = self.previous().clone();
 
+        let label = match matches!(self, Type::Identifier) {
+            true => Some(self.previous().clone()),
+            false => None,
+        };
+
         self.consume(Type::Semicolon, "Expected ';' after break")?;
 
-        Ok(Stmt::Break(BreakData { keyword }))
+        Ok(Stmt::Break(BreakData { keyword, label }))
+    }
+
+    /// Parses a throw statement.
+    fn throw_statement(&mut self) -> ParseResult<Stmt> {
+        let keyword = self.previous().clone();
+        let value = self.expression()?;
+
+        self.consume(Type::Semicolon, "Expected ';' after thrown value")?;
+
+        Ok(Stmt::Throw(ThrowData { keyword, value }))
+    }
+
+    /// Parses a try statement.
+    fn try_statement(&mut self) -> ParseResult<Stmt> {
+        let keyword = self.previous().clone();
+
+        self.consume(Type::LeftBrace, "Expected '{' after 'try'")?;
+        let body = self.block()?;
+
+        let mut catch = None;
+        if matches!(self, Type::Catch) {
+            self.consume(Type::LeftParen, "Expected '(' after 'catch'")?;
+            let name = self.consume(Type::Identifier, "Expected error variable name")?.clone();
+            self.consume(Type::RightParen, "Expected ')' after error variable name")?;
+
+            self.consume(Type::LeftBrace, "Expected '{' before catch body")?;
+            catch = Some(CatchData { name, body: self.block()? });
+        }
+
+        let mut finally = None;
+        if matches!(self, Type::Finally) {
+            self.consume(Type::LeftBrace, "Expected '{' after 'finally'")?;
+            finally = Some(self.block()?);
+        }
+
+        if catch.is_none() && finally.is_none() {
+            return Err(ParseError {
+                token: self.peek().clone(),
+                message: "Expected 'catch' or 'finally' after try block".to_string(),
+                help: None,
+            });
+        }
+
+        Ok(Stmt::Try(TryData { keyword, body, catch, finally }))
+    }
+
+    /// Parses a continue statement.
+    fn continue_statement(&mut self) -> ParseResult<Stmt> {
+        let keyword = self.previous().clone();
+
+        let label = match matches!(self, Type::Identifier) {
+            true => Some(self.previous().clone()),
+            false => None,
+        };
+
+        self.consume(Type::Semicolon, "Expected ';' after continue")?;
+
+        Ok(Stmt::Continue(ContinueData { keyword, label }))
     }
 
     /// Parses an expression statement.
     fn expression_statement(&mut self) -> ParseResult<Stmt> {
-        let expr = match self.expression() {
-            Ok(expr) => expr,
-            Err(error) => return Err(error),
-        };
+        let expr = self.expression()?;
 
-        self.consume(Type::Semicolon, "Expected ';' after expression")?;
+        if let Err(mut error) = self.consume(Type::Semicolon, "Expected ';' after expression") {
+            // Declarations in other languages parse as a variable followed by the declared name.
+            if let Expr::Variable(data) = &expr {
+                error.help = keyword_help(&data.name.lexeme);
+            }
+
+            return Err(error);
+        }
 
         Ok(Stmt::Expression(ExpressionData { expr }))
     }
@@ -393,7 +585,36 @@ impl Parser {
         let name = self.consume(Type::Identifier, &format!("Expected {kind} name"))?.to_owned();
 
         self.consume(Type::LeftParen, &format!("Expected '(' after {kind} name"))?;
+        let params = self.parameters()?;
+
+        self.consume(Type::LeftBrace, &format!("Expected '{{' before {kind} body"))?;
+
+        let body = self.block()?;
+
+        Ok(Stmt::Function(FunctionData { name, params, body }))
+    }
+
+    /// Parses a lambda expression after the 'fun' keyword.
+    fn lambda(&mut self) -> ParseResult<Expr> {
+        let keyword = self.previous().to_owned();
+
+        self.consume(Type::LeftParen, "Expected '(' after 'fun'")?;
+        let params = self.parameters()?;
 
+        let body = if matches!(self, Type::Arrow) {
+            let arrow = self.previous().to_owned();
+            let value = self.expression()?;
+            vec![Stmt::Return(ReturnData { keyword: arrow, value: Some(value) })]
+        } else {
+            self.consume(Type::LeftBrace, "Expected '{' or '=>' before lambda body")?;
+            self.block()?
+        };
+
+        Ok(Expr::Lambda(LambdaData { keyword, params, body }))
+    }
+
+    /// Parses the parameters of a function after the opening parenthesis.
+    fn parameters(&mut self) -> ParseResult<Vec<Token>> {
         let mut params = vec![];
 
         if !self.check(Type::RightParen) {
@@ -402,6 +623,7 @@ impl Parser {
                     return Err(ParseError {
                         token: self.peek().to_owned(),
                         message: "Cannot have more than 255 parameters".to_string(),
+                        help: None,
                     });
                 }
 
@@ -415,22 +637,20 @@ impl Parser {
 
         self.consume(Type::RightParen, "Expected ')' after parameters")?;
 
-        self.consume(Type::LeftBrace, &format!("Expected '{{' before {kind} body"))?;
-
-        let body = self.block()?;
-
-        Ok(Stmt::Function(FunctionData { name, params, body }))
+        Ok(params)
     }
 
     /// Parses a block statement.
     fn block(&mut self) -> ParseResult<Vec<Stmt>> {
         let mut statements = Vec::new();
 
+        self.depth += 1;
         while !self.check(Type::RightBrace) && !self.is_at_end() {
             if let Some(stmt) = self.decleration() {
                 statements.push(stmt);
             }
         }
+        self.depth -= 1;
 
         self.consume(Type::RightBrace, "Expected '}' after block")?;
 
@@ -446,10 +666,9 @@ impl Parser {
             let value = self.assignment()?;
 
             if let Expr::Variable(data) = expr {
-                let name = data.name;
-
                 return Ok(Expr::Assign(AssignData {
-                    name,
+                    slot: data.slot,
+                    name: data.name,
                     value: Box::new(value)
                 }));
             } else if let Expr::Get(data) = expr {
@@ -458,12 +677,20 @@ impl Parser {
                     name: data.name,
                     value: Box::new(value),
                 }));
+            } else if let Expr::Index(data) = expr {
+                return Ok(Expr::IndexSet(IndexSetData {
+                    object: data.object,
+                    bracket: data.bracket,
+                    index: data.index,
+                    value: Box::new(value),
+                }));
             }
 
-            ParseError {
+            self.errors.push(ParseError {
                 token: equals,
-                message: "Invalid assignment target".to_string()
-            }.throw();
+                message: "Invalid assignment target".to_string(),
+                help: None,
+            });
         }
 
         Ok(expr)
@@ -505,17 +732,11 @@ impl Parser {
 
     /// Parses an equality expression.
     fn equality(&mut self) -> ParseResult<Expr> {
-        let mut expr = match self.comparison() {
-            Ok(expr) => expr,
-            Err(error) => return Err(error),
-        };
+        let mut expr = self.comparison()?;
 
         while matches!(self, Type::BangEqual, Type::EqualEqual) {
             let operator = self.previous().clone();
-            let right = match self.comparison() {
-                Ok(expr) => expr,
-                Err(error) => return Err(error),
-            };
+            let right = self.comparison()?;
 
             expr = Expr::Binary(BinaryData {
                 left: Box::new(expr),
@@ -529,17 +750,11 @@ impl Parser {
 
     /// Parses a comparison expression.
     fn comparison(&mut self) -> ParseResult<Expr> {
-        let mut expr = match self.term() {
-            Ok(expr) => expr,
-            Err(error) => return Err(error),
-        };
+        let mut expr = self.term()?;
 
         while matches!(self, Type::Greater, Type::GreaterEqual, Type::Less, Type::LessEqual) {
             let operator = self.previous().clone();
-            let right = match self.term() {
-                Ok(expr) => expr,
-                Err(error) => return Err(error),
-            };
+            let right = self.term()?;
 
             expr = Expr::Binary(BinaryData {
                 left: Box::new(expr),
@@ -553,17 +768,11 @@ impl Parser {
 
     /// Parses a term expression.
     fn term(&mut self) -> ParseResult<Expr> {
-        let mut expr = match self.factor() {
-            Ok(expr) => expr,
-            Err(error) => return Err(error),
-        };
+        let mut expr = self.factor()?;
 
         while matches!(self, Type::Minus, Type::Plus) {
             let operator = self.previous().clone();
-            let right = match self.factor() {
-                Ok(expr) => expr,
-                Err(error) => return Err(error),
-            };
+            let right = self.factor()?;
 
             expr = Expr::Binary(BinaryData {
                 left: Box::new(expr),
@@ -577,17 +786,11 @@ impl Parser {
 
     /// Parses a factor expression.
     fn factor(&mut self) -> ParseResult<Expr> {
-        let mut expr = match self.unary() {
-            Ok(expr) => expr,
-            Err(error) => return Err(error),
-        };
+        let mut expr = self.unary()?;
 
         while matches!(self, Type::Slash, Type::Star) {
             let operator = self.previous().clone();
-            let right = match self.unary() {
-                Ok(expr) => expr,
-                Err(error) => return Err(error),
-            };
+            let right = self.unary()?;
 
             expr = Expr::Binary(BinaryData {
                 left: Box::new(expr),
@@ -603,10 +806,7 @@ impl Parser {
     fn unary(&mut self) -> ParseResult<Expr> {
         if matches!(self, Type::Bang, Type::Minus) {
             let operator = self.previous().clone();
-            let right = match self.unary() {
-                Ok(expr) => expr,
-                Err(error) => return Err(error),
-            };
+            let right = self.unary()?;
 
             return Ok(Expr::Unary(UnaryData {
                 operator,
@@ -624,13 +824,34 @@ impl Parser {
         if !self.check(Type::RightParen) {
             while { 
                 if arguments.len() >= 255 {
-                    ParseError {
-                        token: self.peek().to_owned(),
+                    let token = self.peek().to_owned();
+                    self.errors.push(ParseError {
+                        token,
                         message: "Cannot have more than 255 arguments".to_string(),
-                    }.throw();
+                        help: None,
+                    });
+                }
+
+                let argument = self.expression().and_then(|argument| {
+                    match self.check(Type::Comma) || self.check(Type::RightParen) {
+                        true => Ok(argument),
+                        false => Err(ParseError {
+                            token: self.peek().clone(),
+                            message: "Expected ')' after arguments".to_string(),
+                            help: None,
+                        }),
+                    }
+                });
+
+                match argument {
+                    Ok(argument) => arguments.push(argument),
+                    // Skip the broken argument so that the rest of them are still parsed.
+                    Err(error) => match self.skip_argument() {
+                        true => self.errors.push(error),
+                        false => return Err(error),
+                    },
                 }
 
-                arguments.push(self.expression()?);
                 matches!(self, Type::Comma)
             } {}
         }
@@ -654,6 +875,15 @@ impl Parser {
             } else if matches!(self, Type::Dot) {
                 let name = self.consume(Type::Identifier, "Expected property name after '.'")?;
                 expr = Expr::Get(GetData { object: Box::new(expr), name: name.clone() });
+            } else if matches!(self, Type::LeftBracket) {
+                let bracket = self.previous().clone();
+                let index = self.expression()?;
+                self.consume(Type::RightBracket, "Expected ']' after index")?;
+                expr = Expr::Index(IndexData {
+                    object: Box::new(expr),
+                    bracket,
+                    index: Box::new(index),
+                });
             } else {
                 break;
             }
@@ -686,24 +916,69 @@ impl Parser {
             self.consume(Type::Dot, "Expected '.' after 'super'")?;
             let method = self.consume(Type::Identifier, "Expected superclass method name")?.clone();
 
-            return Ok(Expr::Super(SuperData { keyword, method }))
+            return Ok(Expr::Super(SuperData { slot: Cell::new(None), keyword, method }))
+        }
+
+        // A named function is only allowed as a decleration.
+        if self.check(Type::Fun) && self.check_next(Type::LeftParen) {
+            self.advance();
+            return self.lambda();
         }
 
         if matches!(self, Type::This) {
-            return Ok(Expr::This(ThisData { keyword: self.previous().clone() }));
+            return Ok(Expr::This(ThisData { slot: Cell::new(None), keyword: self.previous().clone() }));
         }
 
         if matches!(self, Type::Identifier) {
             return Ok(Expr::Variable(VariableData {
+                slot: Cell::new(None),
                 name: self.previous().clone()
             }))
         }
 
+        if matches!(self, Type::LeftBracket) {
+            let bracket = self.previous().clone();
+            let mut elements = vec![];
+
+            if !self.check(Type::RightBracket) {
+                loop {
+                    elements.push(self.expression()?);
+
+                    if !matches!(self, Type::Comma) {
+                        break;
+                    }
+                }
+            }
+
+            self.consume(Type::RightBracket, "Expected ']' after list elements")?;
+
+            return Ok(Expr::List(ListData { bracket, elements }));
+        }
+
+        if matches!(self, Type::LeftBrace) {
+            let brace = self.previous().clone();
+            let mut entries = vec![];
+
+            if !self.check(Type::RightBrace) {
+                loop {
+                    let key = self.expression()?;
+                    self.consume(Type::Colon, "Expected ':' after map key")?;
+                    let value = self.expression()?;
+                    entries.push((key, value));
+
+                    if !matches!(self, Type::Comma) {
+                        break;
+                    }
+                }
+            }
+
+            self.consume(Type::RightBrace, "Expected '}' after map entries")?;
+
+            return Ok(Expr::Map(MapData { brace, entries }));
+        }
+
         if matches!(self, Type::LeftParen) {
-            let expr = match self.expression() {
-                Ok(expr) => expr,
-                Err(error) => return Err(error),
-            };
+            let expr = self.expression()?;
 
             match self.consume(Type::RightParen, "Expected ')' after expression") {
                 Ok(_) => (),
@@ -715,30 +990,135 @@ impl Parser {
 
         Err(ParseError {
             token: self.peek().clone(),
-            message: "Expected expression".to_string()
+            message: "Expected expression".to_string(),
+            help: None,
         })
     }
 
     /// Tries to recover from a parse error.
+    /// Inside of a block, the closing brace of the block is never skipped so that the statements
+    /// after the block are not lost. The body of a broken statement is still parsed as a block to
+    /// report the errors in it.
     fn synchronize(&mut self) {
-        self.advance();
+        if self.depth > 0 && self.check(Type::RightBrace) {
+            return;
+        }
+
+        // A brace that the error occurred at can only start the body of the broken statement.
+        if self.check(Type::LeftBrace) {
+            self.body();
+            return;
+        } else {
+            self.advance();
+        }
+
+        // The number of braces of maps that were opened after the error.
+        let mut braces = 0;
 
         while !self.is_at_end() {
-            if self.previous().r#type == Type::Semicolon {
+            if braces == 0 && self.previous().r#type == Type::Semicolon {
                 return;
             }
 
-            match self.peek().r#type {
-                Type::Class => return,
-                Type::Fun => return,
-                Type::Var => return,
-                Type::For => return,
-                Type::If => return,
-                Type::While => return,
-                Type::Print => return,
-                Type::Return => return,
-                _ => self.advance()
+            let r#type = self.peek().r#type;
+            match r#type {
+                Type::LeftBrace if braces == 0 && self.starts_body() => {
+                    self.body();
+
+                    // The statement ends with its body, unless other clauses follow it.
+                    if !self.check(Type::Else) && !self.check(Type::Catch) && !self.check(Type::Finally) {
+                        return;
+                    }
+                    continue;
+                }
+                Type::LeftBrace => braces += 1,
+                Type::RightBrace if braces > 0 => braces -= 1,
+                Type::RightBrace if self.depth > 0 => return,
+                Type::Class | Type::Fun | Type::Var | Type::For | Type::If | Type::While
+                    | Type::Print | Type::Return | Type::Import | Type::Break | Type::Continue
+                    | Type::Throw | Type::Try if braces == 0 => return,
+                _ => (),
             };
+
+            self.advance();
         }
     }
+
+    /// Returns if the next brace starts the body of a statement rather than a map.
+    fn starts_body(&mut self) -> bool {
+        let previous = self.previous().r#type;
+        [Type::RightParen, Type::Else, Type::Try, Type::Finally].contains(&previous)
+    }
+
+    /// Parses the body of a broken statement to report the errors in it.
+    fn body(&mut self) {
+        self.advance();
+
+        if let Err(error) = self.block() {
+            self.errors.push(error);
+        }
+    }
+
+    /// Discards tokens until the next member of the class body. The next member starts after the
+    /// body of the broken method, or at a name that is followed by parameters after a statement.
+    fn synchronize_method(&mut self) {
+        let mut braces = 0;
+
+        while !self.is_at_end() {
+            let r#type = self.peek().r#type;
+            match r#type {
+                Type::LeftBrace => braces += 1,
+                Type::RightBrace if braces == 0 => return,
+                Type::RightBrace => {
+                    braces -= 1;
+
+                    if braces == 0 {
+                        self.advance();
+                        return;
+                    }
+                },
+                Type::Identifier if braces == 0 && self.check_next(Type::LeftParen)
+                    && self.previous().r#type == Type::Semicolon => return,
+                _ => (),
+            }
+
+            self.advance();
+        }
+    }
+
+    /// Discards the tokens of a broken argument. Returns true if the parser reached the comma or
+    /// the closing parenthesis that ends the argument, and false if the argument list does not end
+    /// before the end of the statement.
+    fn skip_argument(&mut self) -> bool {
+        // The number of parentheses, brackets and braces that were opened in the argument.
+        let mut nesting = 0;
+
+        while !self.is_at_end() {
+            match self.peek().r#type {
+                Type::Comma | Type::RightParen if nesting == 0 => return true,
+                Type::Semicolon if nesting == 0 => return false,
+                Type::RightBracket | Type::RightBrace if nesting == 0 => return false,
+                Type::LeftParen | Type::LeftBracket | Type::LeftBrace => nesting += 1,
+                Type::RightParen | Type::RightBracket | Type::RightBrace => nesting -= 1,
+                _ => (),
+            }
+
+            self.advance();
+        }
+
+        false
+    }
+}
+
+/// Returns a help note that names the keyword to use if the given name is a keyword of another
+/// language or a misspelled keyword.
+fn keyword_help(name: &str) -> Option<String> {
+    let keyword = match name {
+        "function" | "func" | "fn" | "def" => "fun",
+        "let" | "const" => "var",
+        // Only the keywords that are followed by a name or an expression can be misspelled here.
+        _ => return error::did_you_mean(name, ["class", "fun", "var", "print", "return", "throw", "import"]),
+    };
+
+    Some(format!("did you mean '{keyword}'?"))
 }
@@ -0,0 +1,217 @@
+//! Renders [`Diagnostic`]s for people and for tools.
+//!
+//! The human readable format shows the line of source code that the diagnostic refers to with
+//! the span underlined, followed by the help note and the trace of runtime errors:
+//! ```text
+//! error: Expected expression
+//!  --> main.rocks:1:10
+//!   |
+//! 1 | print 1 +;
+//!   |          ^
+//! ```
+//! The JSON format prints every diagnostic as an object on its own line. Lines and columns are
+//! one-based in both formats.
+
+use serde_json::{json, Value};
+
+use crate::error::{Diagnostic, Phase, Severity};
+use crate::token::Type;
+
+/// The escape codes of the colors that are used when printing to a terminal.
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+
+/// Represents how diagnostics are printed.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+    /// Source snippets with the span underlined.
+    #[default]
+    Human,
+    /// One JSON object per diagnostic.
+    Json,
+}
+
+/// Returns the name of the file that the diagnostic refers to.
+/// Runtime errors can occur in imported modules, so their file is the one of the innermost call
+/// that is in the source code. Every other diagnostic refers to the given file.
+fn file_of<'a>(diagnostic: &'a Diagnostic, file: Option<&'a str>) -> Option<&'a str> {
+    match diagnostic.phase {
+        Phase::Runtime => diagnostic.trace.iter()
+            .find(|frame| frame.location.is_some())
+            .map_or(file, |frame| frame.file.as_deref()),
+        _ => file,
+    }
+}
+
+/// Returns the line of the source code that the span of the diagnostic starts on, if the span
+/// really points into it. Functions declared by earlier runs, like in the REPL, point into other
+/// source code.
+fn line_of<'a>(diagnostic: &Diagnostic, source: &'a str) -> Option<&'a str> {
+    let line = source.lines().nth(diagnostic.span.start.line)?;
+
+    let Some(token) = &diagnostic.token else {
+        return Some(line);
+    };
+
+    // The source code of a string includes the quotes that its lexeme leaves out, and only its
+    // first line can be compared.
+    let text = match token.r#type {
+        Type::String => format!("\"{}\"", token.lexeme),
+        _ => token.lexeme.clone(),
+    };
+    let text = text.lines().next().unwrap_or_default();
+
+    let start = diagnostic.span.start.column;
+    let source: String = line.chars().skip(start).take(text.chars().count()).collect();
+    (source == text).then_some(line)
+}
+
+/// Renders the diagnostic in the human readable format. The source code is only shown if the
+/// diagnostic refers to the given file.
+/// ```
+/// use rocks_lang::render;
+/// use rocks_lang::parser::Parser;
+/// use rocks_lang::scanner::Scanner;
+/// use rocks_lang::error::Error;
+///
+/// let source = "var a = 1;\nprint a +;";
+/// let mut parser = Parser::new(Scanner::new(source).scan_tokens());
+/// parser.parse();
+///
+/// let diagnostic = parser.errors()[0].to_diagnostic();
+/// assert_eq!(render::human(&diagnostic, Some("main.rocks"), source, false), "\
+/// error: Expected expression
+///  --> main.rocks:2:10
+///   |
+/// 2 | print a +;
+///   |          ^
+/// ");
+/// ```
+pub fn human(diagnostic: &Diagnostic, file: Option<&str>, source: &str, color: bool) -> String {
+    let paint = |code: &'static str| if color { code } else { "" };
+    let reset = paint(RESET);
+    let (severity, accent) = match diagnostic.severity {
+        Severity::Error => ("error", paint(RED)),
+        Severity::Warning => ("warning", paint(YELLOW)),
+    };
+    let gutter_color = paint(BLUE);
+
+    let mut output = format!("{accent}{severity}{reset}{bold}: {}{reset}\n", diagnostic.message, bold = paint(BOLD));
+
+    // The source code could not be read, so there is nothing to point at.
+    if diagnostic.phase == Phase::Io {
+        if let Some(file) = file {
+            output += &format!(" {gutter_color}-->{reset} {file}\n");
+        }
+        return output;
+    }
+
+    let start = diagnostic.span.start;
+    let diagnostic_file = file_of(diagnostic, file);
+    let location = match diagnostic_file {
+        Some(file) => format!("{file}:{}:{}", start.line + 1, start.column + 1),
+        None => format!("line {}:{}", start.line + 1, start.column + 1),
+    };
+
+    let number = (start.line + 1).to_string();
+    let gutter = " ".repeat(number.len());
+    output += &format!("{gutter}{gutter_color}-->{reset} {location}\n");
+
+    let line = match diagnostic_file == file {
+        true => line_of(diagnostic, source),
+        false => None,
+    };
+
+    if let Some(line) = line {
+        // Tabs are kept so that the underline lines up with the source code.
+        let indent: String = line.chars()
+            .take(start.column)
+            .map(|char| if char == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        let end = diagnostic.span.end;
+        let width = match end.line == start.line && end.column > start.column {
+            true => end.column - start.column,
+            false => 1,
+        };
+
+        output += &format!("{gutter} {gutter_color}|{reset}\n");
+        output += &format!("{gutter_color}{number} |{reset} {line}\n");
+        output += &format!("{gutter} {gutter_color}|{reset} {indent}{accent}{}{reset}\n", "^".repeat(width));
+    }
+
+    if let Some(help) = &diagnostic.help {
+        output += &format!("{gutter} {gutter_color}={reset} {bold}help{reset}: {help}\n", bold = paint(BOLD));
+    }
+
+    // The trace of an error that did not occur in a call only repeats the location.
+    if diagnostic.trace.len() > 1 {
+        let mut frames = diagnostic.trace.iter().peekable();
+        while let Some(frame) = frames.next() {
+            output += &format!("    {frame}\n");
+
+            let mut repeated = 0;
+            while frames.next_if_eq(&frame).is_some() {
+                repeated += 1;
+            }
+
+            if repeated > 0 {
+                output += &format!("    ... repeated {repeated} more times\n");
+            }
+        }
+    }
+
+    output
+}
+
+/// Renders the diagnostic as a JSON object on a single line.
+/// ```
+/// use rocks_lang::render;
+/// use rocks_lang::scanner::Scanner;
+/// use rocks_lang::error::Error;
+///
+/// let mut scanner = Scanner::new("print @;");
+/// scanner.scan_tokens();
+///
+/// let diagnostic = scanner.errors()[0].to_diagnostic();
+/// let json: serde_json::Value = serde_json::from_str(&render::json(&diagnostic, None)).unwrap();
+/// assert_eq!(json["message"], "Unexpected character '@'");
+/// assert_eq!(json["span"]["start"]["column"], 7);
+/// ```
+pub fn json(diagnostic: &Diagnostic, file: Option<&str>) -> String {
+    let location = |line: usize, column: usize| json!({ "line": line + 1, "column": column + 1 });
+
+    let trace: Vec<Value> = diagnostic.trace.iter().map(|frame| json!({
+        "name": frame.name,
+        "file": frame.file,
+        "line": frame.location.map(|location| location.line + 1),
+        "column": frame.location.map(|location| location.column + 1),
+    })).collect();
+
+    json!({
+        "severity": match diagnostic.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        },
+        "phase": match diagnostic.phase {
+            Phase::Io => "io",
+            Phase::Scan => "scan",
+            Phase::Parse => "parse",
+            Phase::Resolve => "resolve",
+            Phase::Lint => "lint",
+            Phase::Runtime => "runtime",
+        },
+        "message": diagnostic.message,
+        "file": file_of(diagnostic, file),
+        "span": (diagnostic.phase != Phase::Io).then(|| json!({
+            "start": location(diagnostic.span.start.line, diagnostic.span.start.column),
+            "end": location(diagnostic.span.end.line, diagnostic.span.end.column),
+        })),
+        "token": diagnostic.token.as_ref().map(|token| &token.lexeme),
+        "help": diagnostic.help,
+        "trace": trace,
+    }).to_string()
+}
@@ -0,0 +1,544 @@
+//! The interactive prompt of the `rocks` binary.
+//!
+//! Every complete input is run as soon as it is entered and the values of its expression
+//! statements are printed. An input that is not complete yet, like a class declaration with an
+//! open brace or an unterminated string, continues on the next line with a `...` prompt:
+//! ```text
+//! > fun add(a, b) {
+//! ...     return a + b;
+//! ... }
+//! > add(1, 2)
+//! 3
+//! ```
+//! Lines that start with a colon are meta-commands, see `:help` for the list of them.
+//!
+//! In a terminal, the input is edited with the [`ReplHelper`], which highlights the tokens,
+//! completes names with the tab key and keeps the input open until its brackets are balanced.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::{Hinter, HistoryHinter};
+use rustyline::history::DefaultHistory;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{Context, Editor};
+
+use crate::ast::ASTPrinter;
+use crate::object::Object;
+use crate::parser::Parser;
+use crate::scanner::{Scanner, KEYWORDS};
+use crate::token::Type;
+use crate::vm::value::Value;
+use crate::{rocks, Engine, Termination};
+
+/// The meta-commands of the REPL with their usage and description.
+pub const COMMANDS: [(&str, &str); 6] = [
+    (":help", "Shows this message"),
+    (":load <file>", "Runs the file"),
+    (":reset", "Forgets every variable that was defined"),
+    (":env", "Lists the global variables"),
+    (":ast <expr>", "Prints the syntax tree of the expression"),
+    (":time", "Toggles printing how long every input takes to run"),
+];
+
+/// Returns if the given source code is not complete yet and continues on the next line.
+/// This is the case if a string is not terminated or if a parenthesis, a bracket or a brace is
+/// not closed.
+/// ```
+/// use rocks_lang::repl::is_incomplete;
+///
+/// assert!(is_incomplete("class Point {"));
+/// assert!(is_incomplete("print \"hello"));
+/// assert!(!is_incomplete("print (1 + 2);"));
+/// ```
+pub fn is_incomplete(source: &str) -> bool {
+    let source = format!("{source}\n");
+    let mut scanner = Scanner::new(&source);
+    let tokens = scanner.scan_tokens();
+
+    if scanner.errors().iter().any(|error| error.message == "Unterminated string") {
+        return true;
+    }
+
+    let depth = tokens.iter().fold(0, |depth: i32, token| match token.r#type {
+        Type::LeftParen | Type::LeftBracket | Type::LeftBrace => depth + 1,
+        Type::RightParen | Type::RightBracket | Type::RightBrace => depth - 1,
+        _ => depth,
+    });
+
+    depth > 0
+}
+
+/// The escape codes of the colors that the input is highlighted with.
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+const MAGENTA: &str = "\x1b[35m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const GRAY: &str = "\x1b[90m";
+
+/// Returns the given source code with its keywords, literals and comments colored.
+/// ```
+/// use rocks_lang::repl::highlight;
+///
+/// assert_eq!(highlight("print 1;"), "\x1b[35mprint\x1b[0m \x1b[33m1\x1b[0m;");
+/// ```
+pub fn highlight(source: &str) -> String {
+    let text = format!("{source}\n");
+    let mut scanner = Scanner::new(&text);
+    let tokens = scanner.scan_tokens();
+
+    let chars: Vec<char> = source.chars().collect();
+    // The index of the first character of every line.
+    let lines: Vec<usize> = std::iter::once(0)
+        .chain(chars.iter().enumerate().filter(|(_, char)| **char == '\n').map(|(index, _)| index + 1))
+        .collect();
+
+    let tokens = tokens.iter().filter_map(|token| {
+        let color = match token.r#type {
+            Type::String => GREEN,
+            Type::Number | Type::True | Type::False | Type::Null => YELLOW,
+            r#type if KEYWORDS.iter().any(|(_, keyword)| *keyword == r#type) => MAGENTA,
+            _ => return None,
+        };
+
+        // The lexeme of a string does not include its quotes.
+        let text = match token.r#type {
+            Type::String => format!("\"{}\"", token.lexeme),
+            _ => token.lexeme.clone(),
+        };
+
+        Some((token.location, text, color))
+    });
+    let comments = scanner.comments().iter().map(|comment| (comment.location, comment.text.clone(), GRAY));
+
+    let mut spans: Vec<(usize, usize, &str)> = tokens.chain(comments).filter_map(|(location, text, color)| {
+        let start = lines.get(location.line)? + location.column;
+        let end = start + text.chars().count();
+
+        // Tokens whose location does not point at their text, like strings that span multiple
+        // lines, are left uncolored.
+        chars.get(start..end)?.iter().copied().eq(text.chars()).then_some((start, end, color))
+    }).collect();
+    spans.sort();
+
+    let mut output = String::new();
+    let mut position = 0;
+    for (start, end, color) in spans {
+        output.extend(&chars[position..start]);
+        output.push_str(color);
+        output.extend(&chars[start..end]);
+        output.push_str(RESET);
+        position = end;
+    }
+
+    output.extend(&chars[position..]);
+    output
+}
+
+/// Returns if the given character can be a part of a name.
+fn is_name(char: char) -> bool {
+    char.is_alphanumeric() || char == '_'
+}
+
+/// Returns the names that are used in the given source code.
+fn names(source: &str) -> impl Iterator<Item = String> {
+    Scanner::new(&format!("{source}\n")).scan_tokens()
+        .into_iter()
+        .filter(|token| token.r#type == Type::Identifier)
+        .map(|token| token.lexeme)
+}
+
+/// Completes, highlights and validates the input of the [`Repl`] while it is typed.
+pub struct ReplHelper {
+    /// The names of the global variables and the names used by the incomplete input.
+    names: Vec<String>,
+    /// The names of the fields and methods of the global variables, by the name of the variable.
+    members: HashMap<String, Vec<String>>,
+    /// If true, the input is highlighted.
+    color: bool,
+    hinter: HistoryHinter,
+}
+
+impl ReplHelper {
+    /// Returns the start of the name that ends at the given position of the line and the names
+    /// that complete it. Names after a `.` are completed with the fields and methods of the
+    /// variable before it, other names with the keywords and the variables.
+    pub fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let before = &line[..pos];
+        let start = before.char_indices()
+            .rev()
+            .take_while(|(_, char)| is_name(*char))
+            .last()
+            .map_or(pos, |(index, _)| index);
+        let word = &line[start..pos];
+
+        let mut candidates: Vec<String> = match before[..start].strip_suffix('.') {
+            Some(object) => {
+                let object = &object[object.trim_end_matches(is_name).len()..];
+                self.members.get(object).cloned().unwrap_or_default()
+            },
+            None if word.is_empty() => vec![],
+            None => KEYWORDS.iter()
+                .map(|(keyword, _)| keyword.to_string())
+                .chain(self.names.iter().cloned())
+                // The name that is being completed is used by the line itself.
+                .chain(names(line).filter(|name| name != word))
+                .collect(),
+        };
+
+        candidates.retain(|candidate| candidate.starts_with(word));
+        candidates.sort();
+        candidates.dedup();
+
+        (start, candidates)
+    }
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.candidates(line, pos))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+
+    fn hint(&self, line: &str, pos: usize, ctx: &Context<'_>) -> Option<String> {
+        self.hinter.hint(line, pos, ctx)
+    }
+}
+
+impl Highlighter for ReplHelper {
+    fn highlight<'l>(&self, line: &'l str, _: usize) -> Cow<'l, str> {
+        match self.color && !line.trim_start().starts_with(':') {
+            true => Cow::Owned(highlight(line)),
+            false => Cow::Borrowed(line),
+        }
+    }
+
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        match self.color {
+            true => Cow::Owned(format!("{DIM}{hint}{RESET}")),
+            false => Cow::Borrowed(hint),
+        }
+    }
+
+    fn highlight_char(&self, _: &str, _: usize, _: bool) -> bool {
+        self.color
+    }
+}
+
+impl Validator for ReplHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        let input = ctx.input();
+
+        // Meta-commands always end with their line.
+        match !input.trim_start().starts_with(':') && is_incomplete(input) {
+            true => Ok(ValidationResult::Incomplete),
+            false => Ok(ValidationResult::Valid(None)),
+        }
+    }
+}
+
+impl rustyline::Helper for ReplHelper {}
+
+/// Adds the semicolon that ends the last statement of the given source code if it is missing,
+/// so that expressions can be typed without one.
+fn terminate(mut source: String) -> String {
+    let last = Scanner::new(&source).scan_tokens()
+        .into_iter()
+        .rfind(|token| token.r#type != Type::EOF)
+        .map(|token| token.r#type);
+
+    if matches!(last, Some(Type::Semicolon | Type::RightBrace) | None) {
+        return source;
+    }
+
+    // A comment on the last line would swallow the semicolon.
+    let last_line = source.trim_end().lines().last().unwrap_or_default();
+    let semicolon = if last_line.contains("//") { "\n;" } else { ";" };
+
+    let end = source.trim_end().len();
+    source.insert_str(end, semicolon);
+    source
+}
+
+/// Represents an interactive session that runs the input line by line.
+pub struct Repl<'r, 'w> {
+    rocks: &'r mut rocks<'w>,
+    /// The lines of the input that is not complete yet.
+    buffer: String,
+    /// If true, the time that every input takes to run is printed after it.
+    time: bool,
+}
+
+impl<'r, 'w> Repl<'r, 'w> {
+    /// Creates a new session that runs the input on the given instance.
+    pub fn new(rocks: &'r mut rocks<'w>) -> Self {
+        Repl { rocks, buffer: String::new(), time: false }
+    }
+
+    /// Reads the input from the standard input until it ends or a script exits. The history of
+    /// the input is kept in the home directory of the user, if there is one.
+    pub fn run(&mut self) -> Termination {
+        let mut rl: Editor<ReplHelper, DefaultHistory> = Editor::new().unwrap();
+        let color = env::var_os("NO_COLOR").is_none();
+
+        let history_path = home::home_dir().map(|home| home.join(".rocks.history"));
+        if let Some(path) = &history_path {
+            rl.load_history(path).ok();
+        }
+
+        let termination = loop {
+            // The names to complete change with every input.
+            rl.set_helper(Some(self.helper(color)));
+
+            match rl.readline(self.prompt()) {
+                Ok(line) => {
+                    rl.add_history_entry(line.as_str()).unwrap();
+                    if let Termination::Exit(code) = self.line(&line, &mut io::stdout()) {
+                        break Termination::Exit(code);
+                    }
+                },
+                // Interrupting an incomplete input only discards it.
+                Err(ReadlineError::Interrupted) if self.cancel() => continue,
+                Err(_) => break self.finish(&mut io::stdout()),
+            }
+        };
+
+        if let Some(path) = &history_path {
+            rl.save_history(path).ok();
+        }
+
+        termination
+    }
+
+    /// Returns the helper that edits the next line of input. The input is highlighted if `color`
+    /// is set.
+    pub fn helper(&self, color: bool) -> ReplHelper {
+        let mut names: Vec<String> = names(&self.buffer).collect();
+        let mut members = HashMap::new();
+
+        match &self.rocks.engine {
+            Engine::Interpreter(interpreter) => {
+                for (name, value) in interpreter.globals().borrow().iter() {
+                    names.push(name.clone());
+
+                    let fields = match value {
+                        Object::Instance(instance) => {
+                            let instance = instance.borrow();
+                            let methods = instance.class().borrow().method_names();
+                            instance.fields().map(|(field, _)| field.to_string()).chain(methods).collect()
+                        },
+                        Object::Module(module) => module.exports().to_vec(),
+                        _ => continue,
+                    };
+
+                    members.insert(name.clone(), fields);
+                }
+            },
+            Engine::Vm(vm) => {
+                for (name, value) in vm.globals().borrow().iter() {
+                    names.push(name.clone());
+
+                    let fields = match value {
+                        Value::Instance(instance) => {
+                            let instance = instance.borrow();
+                            let methods: Vec<String> = instance.class.borrow().methods.keys().cloned().collect();
+                            instance.fields.keys().cloned().chain(methods).collect()
+                        },
+                        Value::Module(module) => module.exports().to_vec(),
+                        _ => continue,
+                    };
+
+                    members.insert(name.clone(), fields);
+                }
+            },
+        }
+
+        ReplHelper { names, members, color, hinter: HistoryHinter::new() }
+    }
+
+    /// Returns the prompt of the next line.
+    pub fn prompt(&self) -> &'static str {
+        match self.buffer.is_empty() {
+            true => "> ",
+            false => "... ",
+        }
+    }
+
+    /// Discards the lines of the incomplete input. Returns false if there were none.
+    pub fn cancel(&mut self) -> bool {
+        let cancelled = !self.buffer.is_empty();
+        self.buffer.clear();
+        cancelled
+    }
+
+    /// Runs the incomplete input once the input has ended, so that what is missing from it is
+    /// reported. Returns how the script ended.
+    pub fn finish<W: Write>(&mut self, output: &mut W) -> Termination {
+        let source = std::mem::take(&mut self.buffer);
+        self.execute(source, output)
+    }
+
+    /// Handles a line of input. Meta-commands are run right away, the source code is run once
+    /// the input is complete. The output of meta-commands is written into the given writer while
+    /// the output of the scripts goes to the writer of the instance. Returns how the script that
+    /// ran (if any) ended.
+    pub fn line<W: Write>(&mut self, line: &str, output: &mut W) -> Termination {
+        if self.buffer.is_empty() && line.trim_start().starts_with(':') {
+            return self.command(line.trim(), output);
+        }
+
+        self.buffer.push_str(line);
+        self.buffer.push('\n');
+
+        if is_incomplete(&self.buffer) {
+            return Termination::Completed;
+        }
+
+        let source = std::mem::take(&mut self.buffer);
+        self.execute(source, output)
+    }
+
+    /// Runs the given input and prints its time if timing is on.
+    fn execute<W: Write>(&mut self, source: String, output: &mut W) -> Termination {
+        if source.trim().is_empty() {
+            return Termination::Completed;
+        }
+
+        let source = terminate(source);
+
+        let start = Instant::now();
+        // Diagnostics are already reported, there is nothing else to do with them.
+        let termination = self.rocks.execute(source, true).unwrap_or(Termination::Completed);
+        self.time(start, output);
+        termination
+    }
+
+    /// Runs the given meta-command.
+    fn command<W: Write>(&mut self, line: &str, output: &mut W) -> Termination {
+        let (command, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let argument = argument.trim();
+
+        match (command, argument) {
+            (":help", "") => {
+                for (usage, description) in COMMANDS {
+                    writeln!(output, "{usage:<14}{description}").expect("writer to not fail on write");
+                }
+            },
+            (":load", path) if !path.is_empty() => return self.load(path, output),
+            (":reset", "") => match &mut self.rocks.engine {
+                Engine::Interpreter(interpreter) => interpreter.reset(),
+                Engine::Vm(vm) => vm.reset(),
+            },
+            (":env", "") => {
+                for (name, value) in self.globals() {
+                    writeln!(output, "{name} = {value}").expect("writer to not fail on write");
+                }
+            },
+            (":ast", source) if !source.is_empty() => self.ast(source, output),
+            (":time", "") => {
+                self.time = !self.time;
+                let state = if self.time { "on" } else { "off" };
+                writeln!(output, "Timing is {state}").expect("writer to not fail on write");
+            },
+            _ => {
+                writeln!(output, "Unknown command '{line}', type ':help' for a list of commands")
+                    .expect("writer to not fail on write");
+            },
+        }
+
+        Termination::Completed
+    }
+
+    /// Runs the file at the given path.
+    fn load<W: Write>(&mut self, path: &str, output: &mut W) -> Termination {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(error) => {
+                writeln!(output, "Could not read '{path}': {error}").expect("writer to not fail on write");
+                return Termination::Completed;
+            },
+        };
+
+        // Diagnostics and imports are relative to the file while it runs.
+        self.rocks.file = Path::new(path).file_name().map(|name| name.to_string_lossy().to_string());
+        match &mut self.rocks.engine {
+            Engine::Interpreter(interpreter) => interpreter.set_script(Path::new(path)),
+            Engine::Vm(vm) => vm.set_script(Path::new(path)),
+        }
+
+        let start = Instant::now();
+        let termination = self.rocks.run(source).unwrap_or(Termination::Completed);
+
+        self.rocks.file = None;
+        match &mut self.rocks.engine {
+            Engine::Interpreter(interpreter) => interpreter.clear_script(),
+            Engine::Vm(vm) => vm.clear_script(),
+        }
+
+        self.time(start, output);
+        termination
+    }
+
+    /// Prints the syntax tree of the given expression.
+    fn ast<W: Write>(&mut self, source: &str, output: &mut W) {
+        let source = format!("{source}\n");
+        self.rocks.reporter.source(None, &source);
+
+        let mut scanner = Scanner::new(&source);
+        let tokens = scanner.scan_tokens();
+
+        if !scanner.errors().is_empty() {
+            self.rocks.report(scanner.errors()).ok();
+            return;
+        }
+
+        let mut parser = Parser::new(tokens);
+        match parser.parse_expression() {
+            Some(expr) => writeln!(output, "{}", ASTPrinter.print(expr)).expect("writer to not fail on write"),
+            None => {
+                self.rocks.report(parser.errors()).ok();
+            },
+        }
+    }
+
+    /// Returns the names and the values of the global variables that were defined by the
+    /// scripts, sorted by their name.
+    fn globals(&self) -> Vec<(String, String)> {
+        let mut globals: Vec<(String, String)> = match &self.rocks.engine {
+            Engine::Interpreter(interpreter) => interpreter.globals().borrow()
+                .iter()
+                .filter(|(_, value)| !matches!(value, Object::NativeFunction(_)))
+                .map(|(name, value)| (name.clone(), value.to_string()))
+                .collect(),
+            Engine::Vm(vm) => vm.globals().borrow()
+                .iter()
+                .filter(|(_, value)| !matches!(value, Value::NativeFunction(_)))
+                .map(|(name, value)| (name.clone(), value.to_string()))
+                .collect(),
+        };
+
+        globals.sort();
+        globals
+    }
+
+    /// Prints the time that passed since the given instant, if timing is on.
+    fn time<W: Write>(&self, start: Instant, output: &mut W) {
+        if self.time {
+            writeln!(output, "took {:?}", start.elapsed()).expect("writer to not fail on write");
+        }
+    }
+}
@@ -1,12 +1,68 @@
+use std::cell::Cell;
 use std::mem;
 use std::collections::HashMap;
 
-use crate::error::{Error, ResolveError};
+use crate::error::ResolveError;
 use crate::expr::{Expr, ExprVisitor};
 use crate::stmt::{Stmt, StmtVisitor};
-use crate::interpreter::Interpreter;
 use crate::token::Token;
 
+/// Represents where a local variable lives at runtime.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Slot {
+    /// The number of environments between the use of the variable and its declaration.
+    pub depth: usize,
+    /// The index of the variable in the environment it was declared in.
+    pub index: usize,
+}
+
+/// Represents what a declared name refers to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Kind {
+    Variable,
+    Parameter,
+    Function,
+    Class,
+    /// A module or a binding of a module that is bound by an import statement.
+    Import,
+}
+
+impl std::fmt::Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Kind::Variable => write!(f, "variable"),
+            Kind::Parameter => write!(f, "parameter"),
+            Kind::Function => write!(f, "function"),
+            Kind::Class => write!(f, "class"),
+            Kind::Import => write!(f, "import"),
+        }
+    }
+}
+
+/// Represents a name declared in the source code and every place that uses it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Symbol {
+    /// The name in the declaration.
+    pub name: Token,
+    pub kind: Kind,
+    /// The names in the expressions that read or assign the declared variable.
+    pub references: Vec<Token>,
+    /// If true, the name is declared in the global scope.
+    pub global: bool,
+    /// The name of the variable of an enclosing local scope that this declaration shadows.
+    pub shadows: Option<Token>,
+}
+
+/// Represents a variable declared in a local scope.
+struct Variable {
+    /// If false, the variable is declared but its initializer is not resolved yet.
+    defined: bool,
+    /// The index of the variable in its scope, in the order of declaration.
+    slot: usize,
+    /// The index of the symbol of the variable, implicit variables like 'this' have none.
+    symbol: Option<usize>,
+}
+
 enum FunctionType {
     None,
     Function,
@@ -25,25 +81,80 @@ enum LoopType {
     While,
 }
 
-pub struct Resolver<'a, 'w> {
-    interpreter: &'a mut Interpreter<'w>,
-    scopes: Vec<HashMap<String, bool>>,
+pub struct Resolver {
+    scopes: Vec<HashMap<String, Variable>>,
     current_function: FunctionType,
     current_class: ClassType,
     current_loop: LoopType,
+    labels: Vec<String>,
+    symbols: Vec<Symbol>,
+    /// The symbols of the global variables by name, the first declaration of a name is kept.
+    globals: HashMap<String, usize>,
+    /// The names that did not resolve to a local variable, they refer to globals.
+    unresolved: Vec<Token>,
+    errors: Vec<ResolveError>,
 }
 
-impl<'a, 'w> Resolver<'a, 'w> {
-    pub fn new(interpreter: &'a mut Interpreter<'w>) -> Self {
+impl Resolver {
+    pub fn new() -> Self {
         Resolver {
-            interpreter,
             scopes: vec![],
             current_function: FunctionType::None,
             current_class: ClassType::None,
             current_loop: LoopType::None,
+            labels: vec![],
+            symbols: vec![],
+            globals: HashMap::new(),
+            unresolved: vec![],
+            errors: vec![],
         }
     }
 
+    /// Creates a resolver for code that runs inside the given local scopes, listed from the
+    /// outermost to the innermost one with their variables in the order of declaration.
+    /// This is used by the [`debugger`](crate::debugger) to evaluate expressions in a paused
+    /// function.
+    pub fn with_scopes(scopes: Vec<Vec<String>>) -> Self {
+        let mut resolver = Resolver::new();
+
+        for names in scopes {
+            resolver.begin_scope();
+            for name in names {
+                match name.as_str() {
+                    "this" if matches!(resolver.current_class, ClassType::None) => {
+                        resolver.current_class = ClassType::Class;
+                    },
+                    "super" => resolver.current_class = ClassType::Subclass,
+                    _ => (),
+                }
+
+                resolver.define_implicit(&name);
+            }
+        }
+
+        resolver
+    }
+
+    /// Returns the declared names with the places that use them.
+    /// This is used by the [`language server`](crate::lsp) to find definitions and references.
+    /// Uses of globals are matched by name since globals can be used before they are declared.
+    pub fn symbols(&self) -> Vec<Symbol> {
+        let mut symbols = self.symbols.clone();
+
+        for name in &self.unresolved {
+            if let Some(&symbol) = self.globals.get(&name.lexeme) {
+                symbols[symbol].references.push(name.clone());
+            }
+        }
+
+        symbols
+    }
+
+    /// Returns the errors encountered while resolving.
+    pub fn errors(&self) -> &[ResolveError] {
+        &self.errors
+    }
+
     fn resolve_expr(&mut self, expr: &Expr) {
         expr.accept(self);
     }
@@ -58,20 +169,35 @@ impl<'a, 'w> Resolver<'a, 'w> {
         }
     }
 
-    fn resolve_function(&mut self, function: &Stmt, r#type: FunctionType) {
-        let Stmt::Function(function) = function else { unreachable!() };
-
+    fn resolve_function(&mut self, params: &[Token], body: &Vec<Stmt>, r#type: FunctionType) {
         let enclosing_function = mem::replace(&mut self.current_function, r#type);
+        // Loops do not continue into the functions declared inside them.
+        let enclosing_loop = mem::replace(&mut self.current_loop, LoopType::None);
+        let enclosing_labels = mem::take(&mut self.labels);
 
         self.begin_scope();
-        for param in &function.params {
-            self.declare(param);
+        for param in params {
+            self.declare(param, Kind::Parameter);
             self.define(param);
         }
-        self.resolve(&function.body);
+        self.resolve(body);
         self.end_scope();
 
         self.current_function = enclosing_function;
+        self.current_loop = enclosing_loop;
+        self.labels = enclosing_labels;
+    }
+
+    /// Reports an error if the given label does not belong to an enclosing loop.
+    fn resolve_label(&mut self, label: &Option<Token>) {
+        let Some(label) = label else { return };
+
+        if !self.labels.contains(&label.lexeme) {
+            self.errors.push(ResolveError {
+                token: label.clone(),
+                message: format!("Undefined label '{}'", label.lexeme),
+            });
+        }
     }
 
     fn begin_scope(&mut self) {
@@ -82,19 +208,37 @@ impl<'a, 'w> Resolver<'a, 'w> {
         self.scopes.pop();
     }
 
-    fn declare(&mut self, name: &Token) {
+    fn declare(&mut self, name: &Token, kind: Kind) {
+        let symbol = self.symbols.len();
+        let shadows = self.scopes.iter()
+            .rev()
+            .skip(1)
+            .find_map(|scope| scope.get(&name.lexeme))
+            .and_then(|variable| variable.symbol)
+            .map(|symbol| self.symbols[symbol].name.clone());
+
+        self.symbols.push(Symbol {
+            name: name.clone(),
+            kind,
+            references: vec![],
+            global: self.scopes.is_empty(),
+            shadows,
+        });
+
         if self.scopes.is_empty() {
+            self.globals.entry(name.lexeme.clone()).or_insert(symbol);
             return;
         }
 
         let scope = self.scopes.last_mut().expect("stack to be not empty");
         if scope.contains_key(&name.lexeme) {
-            ResolveError {
+            self.errors.push(ResolveError {
                 token: name.clone(),
                 message: format!("A variable is already defined with name '{}' in this scope", name.lexeme),
-            }.throw();
+            });
         }
-        scope.insert(name.lexeme.to_owned(), false);
+        let slot = scope.len();
+        scope.insert(name.lexeme.to_owned(), Variable { defined: false, slot, symbol: Some(symbol) });
     }
 
     fn define(&mut self, name: &Token) {
@@ -102,45 +246,65 @@ impl<'a, 'w> Resolver<'a, 'w> {
             return;
         }
 
-        self.scopes
+        if let Some(variable) = self.scopes
             .last_mut()
             .expect("stack to be not empty")
-            .insert(name.lexeme.to_owned(), true);
+            .get_mut(&name.lexeme) {
+            variable.defined = true;
+        }
     }
 
-    fn resolve_local(&mut self, name: &Token) {
-        for (i, scope) in self.scopes.iter().rev().enumerate() {
-            if scope.contains_key(&name.lexeme) {
-                self.interpreter.resolve(name, i);
+    /// Declares and defines a variable that is not written in the source code, like 'this'.
+    fn define_implicit(&mut self, name: &str) {
+        let scope = self.scopes.last_mut().expect("stack to be not empty");
+        let slot = scope.len();
+        scope.insert(name.to_string(), Variable { defined: true, slot, symbol: None });
+    }
+
+    fn resolve_local(&mut self, slot: &Cell<Option<Slot>>, name: &Token) {
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if let Some(variable) = scope.get(&name.lexeme) {
+                slot.set(Some(Slot { depth, index: variable.slot }));
+                if let Some(symbol) = variable.symbol {
+                    self.symbols[symbol].references.push(name.clone());
+                }
                 return;
             }
         }
+
+        self.unresolved.push(name.clone());
+    }
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl<'a, 'w> ExprVisitor<()> for Resolver<'a, 'w> {
+impl ExprVisitor<()> for Resolver {
     fn visit_variable_expr(&mut self, expr: &Expr) {
         let Expr::Variable(variable) = expr else { unreachable!() };
 
         if let Some(scope) = self.scopes.last() {
             if let Some(entry) = scope.get(&variable.name.lexeme) {
-                if !entry {
-                    ResolveError {
+                if !entry.defined {
+                    self.errors.push(ResolveError {
                         token: variable.name.to_owned(),
                         message: "Cannot read local variable in its own initializer".to_string(),
-                    }.throw();
+                    });
                 }
             }
         }
 
-        self.resolve_local(&variable.name);
+        self.resolve_local(&variable.slot, &variable.name);
     }
 
     fn visit_assign_expr(&mut self, expr: &Expr) {
         let Expr::Assign(assign) = expr else { unreachable!() };
 
         self.resolve_expr(&assign.value);
-        self.resolve_local(&assign.name);
+        self.resolve_local(&assign.slot, &assign.name);
     }
 
     fn visit_literal_expr(&mut self, expr: &Expr) {
@@ -202,15 +366,15 @@ impl<'a, 'w> ExprVisitor<()> for Resolver<'a, 'w> {
         let Expr::This(this) = expr else { unreachable!() };
 
         if let ClassType::None = self.current_class {
-            ResolveError {
+            self.errors.push(ResolveError {
                 token: this.keyword.clone(),
                 message: "Cannot use 'this' outside of a class".to_string(),
-            }.throw();
+            });
 
             return;
         }
 
-        self.resolve_local(&this.keyword);
+        self.resolve_local(&this.slot, &this.keyword);
     }
 
     fn visit_super_expr(&mut self, expr: &Expr) {
@@ -218,21 +382,59 @@ impl<'a, 'w> ExprVisitor<()> for Resolver<'a, 'w> {
 
         match self.current_class {
             ClassType::Subclass => (),
-            ClassType::None => ResolveError {
+            ClassType::None => self.errors.push(ResolveError {
                 token: super_expr.keyword.clone(),
                 message: "Cannot use 'super' outside of a class".to_string()
-            }.throw(),
-            _ => ResolveError {
+            }),
+            _ => self.errors.push(ResolveError {
                 token: super_expr.keyword.clone(),
                 message: "Cannot use 'super' in a class with no superclass".to_string(),
-            }.throw(),
+            }),
         }
 
-        self.resolve_local(&super_expr.keyword);
+        self.resolve_local(&super_expr.slot, &super_expr.keyword);
+    }
+
+    fn visit_lambda_expr(&mut self, expr: &Expr) {
+        let Expr::Lambda(lambda) = expr else { unreachable!() };
+
+        self.resolve_function(&lambda.params, &lambda.body, FunctionType::Function);
+    }
+
+    fn visit_list_expr(&mut self, expr: &Expr) {
+        let Expr::List(list) = expr else { unreachable!() };
+
+        for element in &list.elements {
+            self.resolve_expr(element);
+        }
+    }
+
+    fn visit_map_expr(&mut self, expr: &Expr) {
+        let Expr::Map(map) = expr else { unreachable!() };
+
+        for (key, value) in &map.entries {
+            self.resolve_expr(key);
+            self.resolve_expr(value);
+        }
+    }
+
+    fn visit_index_expr(&mut self, expr: &Expr) {
+        let Expr::Index(index) = expr else { unreachable!() };
+
+        self.resolve_expr(&index.object);
+        self.resolve_expr(&index.index);
+    }
+
+    fn visit_index_set_expr(&mut self, expr: &Expr) {
+        let Expr::IndexSet(index_set) = expr else { unreachable!() };
+
+        self.resolve_expr(&index_set.value);
+        self.resolve_expr(&index_set.object);
+        self.resolve_expr(&index_set.index);
     }
 }
 
-impl<'a, 'w> StmtVisitor<()> for Resolver<'a, 'w> {
+impl StmtVisitor<()> for Resolver {
     fn visit_block_stmt(&mut self, stmt: &Stmt) {
         let Stmt::Block(block) = stmt else { unreachable!() };
 
@@ -244,7 +446,7 @@ impl<'a, 'w> StmtVisitor<()> for Resolver<'a, 'w> {
     fn visit_var_stmt(&mut self, stmt: &Stmt) {
         let Stmt::Var(var) = stmt else { unreachable!() };
 
-        self.declare(&var.name);
+        self.declare(&var.name, Kind::Variable);
         if let Some(initializer) = &var.initializer {
             self.resolve_expr(initializer);
         }
@@ -254,10 +456,10 @@ impl<'a, 'w> StmtVisitor<()> for Resolver<'a, 'w> {
     fn visit_function_stmt(&mut self, stmt: &Stmt) {
         let Stmt::Function(function) = stmt else { unreachable!() };
 
-        self.declare(&function.name);
+        self.declare(&function.name, Kind::Function);
         self.define(&function.name);
 
-        self.resolve_function(stmt, FunctionType::Function);
+        self.resolve_function(&function.params, &function.body, FunctionType::Function);
     }
 
     fn visit_expression_stmt(&mut self, stmt: &Stmt) {
@@ -286,18 +488,18 @@ impl<'a, 'w> StmtVisitor<()> for Resolver<'a, 'w> {
         let Stmt::Return(return_stmt) = stmt else { unreachable!() };
 
         if let FunctionType::None = self.current_function {
-            ResolveError {
+            self.errors.push(ResolveError {
                 token: return_stmt.keyword.clone(),
                 message: "Cannot return from top-level code".to_string(),
-            }.throw();
+            });
         }
 
         if let Some(value) = &return_stmt.value {
             if let FunctionType::Initializer = self.current_function {
-                ResolveError {
+                self.errors.push(ResolveError {
                     token: return_stmt.keyword.clone(),
                     message: "Cannot return a value from an initializer".to_string(),
-                }.throw();
+                });
                 return;
             }
 
@@ -309,10 +511,72 @@ impl<'a, 'w> StmtVisitor<()> for Resolver<'a, 'w> {
         let Stmt::Break(break_stmt) = stmt else { unreachable!() };
 
         if let LoopType::None = self.current_loop {
-            ResolveError {
+            self.errors.push(ResolveError {
                 token: break_stmt.keyword.clone(),
                 message: "Cannot break outside of a loop".to_string(),
-            }.throw();
+            });
+            return;
+        }
+
+        self.resolve_label(&break_stmt.label);
+    }
+
+    fn visit_continue_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Continue(continue_stmt) = stmt else { unreachable!() };
+
+        if let LoopType::None = self.current_loop {
+            self.errors.push(ResolveError {
+                token: continue_stmt.keyword.clone(),
+                message: "Cannot continue outside of a loop".to_string(),
+            });
+            return;
+        }
+
+        self.resolve_label(&continue_stmt.label);
+    }
+
+    fn visit_throw_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Throw(throw_stmt) = stmt else { unreachable!() };
+
+        self.resolve_expr(&throw_stmt.value);
+    }
+
+    fn visit_try_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Try(try_stmt) = stmt else { unreachable!() };
+
+        self.begin_scope();
+        self.resolve(&try_stmt.body);
+        self.end_scope();
+
+        if let Some(catch) = &try_stmt.catch {
+            // The error variable shares the scope of the catch body.
+            self.begin_scope();
+            self.declare(&catch.name, Kind::Variable);
+            self.define(&catch.name);
+            self.resolve(&catch.body);
+            self.end_scope();
+        }
+
+        if let Some(finally) = &try_stmt.finally {
+            self.begin_scope();
+            self.resolve(finally);
+            self.end_scope();
+        }
+    }
+
+    fn visit_import_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Import(import_stmt) = stmt else { unreachable!() };
+
+        if !self.scopes.is_empty() {
+            self.errors.push(ResolveError {
+                token: import_stmt.keyword.clone(),
+                message: "Can only import at the top level".to_string(),
+            });
+            return;
+        }
+
+        for name in import_stmt.alias.iter().chain(&import_stmt.names) {
+            self.declare(name, Kind::Import);
         }
     }
 
@@ -320,10 +584,19 @@ impl<'a, 'w> StmtVisitor<()> for Resolver<'a, 'w> {
         let Stmt::While(while_stmt) = stmt else { unreachable!() };
 
         let enclosing_loop = mem::replace(&mut self.current_loop, LoopType::While);
+        if let Some(label) = &while_stmt.label {
+            self.labels.push(label.lexeme.clone());
+        }
 
         self.resolve_expr(&while_stmt.condition);
         self.resolve_stmt(&while_stmt.body);
+        if let Some(increment) = &while_stmt.increment {
+            self.resolve_expr(increment);
+        }
 
+        if while_stmt.label.is_some() {
+            self.labels.pop();
+        }
         self.current_loop = enclosing_loop;
     }
 
@@ -332,16 +605,16 @@ impl<'a, 'w> StmtVisitor<()> for Resolver<'a, 'w> {
 
         let enclosing_class = mem::replace(&mut self.current_class, ClassType::Class);
 
-        self.declare(&class_stmt.name);
+        self.declare(&class_stmt.name, Kind::Class);
         self.define(&class_stmt.name);
 
         if let Some(ref superclass) = class_stmt.superclass {
             if let Expr::Variable(variable) = superclass {
                 if class_stmt.name.lexeme == variable.name.lexeme {
-                    ResolveError {
+                    self.errors.push(ResolveError {
                         token: variable.name.clone(),
                         message: "A class cannot inherit from itself".to_string(),
-                    }.throw();
+                    });
                 }
             } else {
                 unreachable!();
@@ -352,17 +625,11 @@ impl<'a, 'w> StmtVisitor<()> for Resolver<'a, 'w> {
             self.resolve_expr(superclass);
 
             self.begin_scope();
-            self.scopes
-                .last_mut()
-                .expect("stack to be not empty")
-                .insert("super".to_string(), true);
+            self.define_implicit("super");
         }
 
         self.begin_scope();
-        self.scopes
-            .last_mut()
-            .expect("stack to be not empty")
-            .insert("this".to_string(), true);
+        self.define_implicit("this");
 
         for method in &class_stmt.methods {
             if let Stmt::Function(function) = method {
@@ -371,7 +638,7 @@ impl<'a, 'w> StmtVisitor<()> for Resolver<'a, 'w> {
                 } else {
                     FunctionType::Method
                 };
-                self.resolve_function(method, decleration);
+                self.resolve_function(&function.params, &function.body, decleration);
             } else {
                 unreachable!();
             }
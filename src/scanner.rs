@@ -2,9 +2,36 @@ use std::str::Chars;
 
 use peekmore::{PeekMore, PeekMoreIterator};
 
-use crate::token::{Token, Type, Location};
+use crate::token::{Comment, Token, Type, Location};
 use crate::literal::Literal;
-use crate::error::{Error, ScanError};
+use crate::error::ScanError;
+
+/// The reserved words of the language and the types of their tokens.
+pub const KEYWORDS: [(&str, Type); 23] = [
+    ("and",      Type::And),
+    ("class",    Type::Class),
+    ("else",     Type::Else),
+    ("false",    Type::False),
+    ("for",      Type::For),
+    ("fun",      Type::Fun),
+    ("if",       Type::If),
+    ("null",     Type::Null),
+    ("or",       Type::Or),
+    ("print",    Type::Print),
+    ("return",   Type::Return),
+    ("break",    Type::Break),
+    ("continue", Type::Continue),
+    ("throw",    Type::Throw),
+    ("try",      Type::Try),
+    ("catch",    Type::Catch),
+    ("finally",  Type::Finally),
+    ("import",   Type::Import),
+    ("super",    Type::Super),
+    ("this",     Type::This),
+    ("true",     Type::True),
+    ("var",      Type::Var),
+    ("while",    Type::While),
+];
 
 pub struct Scanner<'a> {
     source: PeekMoreIterator<Chars<'a>>,
@@ -13,6 +40,10 @@ pub struct Scanner<'a> {
     current: usize,
     line: usize,
     column_offset: usize,
+    /// The location of the token that is being scanned.
+    location: Location,
+    comments: Vec<Comment>,
+    errors: Vec<ScanError>,
 }
 
 impl<'a> Scanner<'a> {
@@ -24,7 +55,10 @@ impl<'a> Scanner<'a> {
             start: 0,
             current: 0,
             line: 0,
-            column_offset: 0
+            column_offset: 0,
+            location: Location::new(0, 0),
+            comments: vec!(),
+            errors: vec!(),
         }
     }
 
@@ -32,6 +66,7 @@ impl<'a> Scanner<'a> {
     pub fn scan_tokens(&mut self) -> Vec<Token> {
         while !self.is_at_end() {
             self.start = self.current;
+            self.location = Location::new(self.line, self.start - self.column_offset);
             self.scan_token();
         }
 
@@ -40,13 +75,24 @@ impl<'a> Scanner<'a> {
                 Type::EOF,
                 String::from(""),
                 None,
-                Location::new(self.line, 0)
+                Location::new(self.line, 0),
+                Location::new(self.line, 0),
             )
         );
 
         self.tokens.clone()
     }
 
+    /// Returns the comments in the source code in the order they appear.
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
+    /// Returns the errors encountered while scanning.
+    pub fn errors(&self) -> &[ScanError] {
+        &self.errors
+    }
+
     /// Returns the next character.
     fn advance(&mut self) -> char {
         match self.source.next() {
@@ -75,22 +121,25 @@ impl<'a> Scanner<'a> {
     }
 
     /// Returns if the next character is the expected character.
+    /// Unlike [`Scanner::peek_next`], this does not move the peek cursor so that it can be
+    /// called repeatedly to check for different characters.
     fn match_next(&mut self, expected: char) -> bool {
-        match self.source.peek_next() {
+        match self.source.peek_nth(1) {
             Some(char) if *char == expected => true,
             Some(_) => false,
             None => false,
         }
     }
 
-    /// Adds a new token to the list of tokens.
+    /// Adds a new token that ends at the current character to the list of tokens.
     fn add_token(&mut self, r#type: Type, lexeme: String, literal: Option<Literal>) {
         self.tokens.push(
             Token::new(
                 r#type,
                 lexeme,
                 literal,
-                Location::new(self.line, self.start - self.column_offset)
+                self.location,
+                Location::new(self.line, self.current - self.column_offset),
             )
         );
     }
@@ -117,7 +166,6 @@ impl<'a> Scanner<'a> {
     /// Handles a string literal.
     fn string(&mut self) {
         self.advance(); // Move past the starting double quotes.
-        let start = (self.line, self.start - self.column_offset);
 
         let mut value = Vec::new();
         while !self.is_at_end() {
@@ -128,6 +176,7 @@ impl<'a> Scanner<'a> {
 
                     if c == '\n' {
                         self.line += 1;
+                        self.column_offset = self.current;
                     }
                 },
                 None => { break; },
@@ -135,10 +184,10 @@ impl<'a> Scanner<'a> {
         }
 
         if self.is_at_end() {
-            ScanError {
-                location: Location::new(start.0, start.1),
+            self.errors.push(ScanError {
+                location: self.location,
                 message: String::from("Unterminated string"),
-            }.throw();
+            });
             return;
         }
 
@@ -166,10 +215,10 @@ impl<'a> Scanner<'a> {
                     value.push(self.advance());
                 }
             } else {
-                ScanError {
+                self.errors.push(ScanError {
                     location: Location::new(self.line, self.start - self.column_offset),
                     message: String::from("Unterminated number"),
-                }.throw();
+                });
                 return;
             }
         }
@@ -190,26 +239,9 @@ impl<'a> Scanner<'a> {
         }
 
         let value = String::from_iter(value);
-        let token_type = match value.as_str() {
-            "and"      => Type::And,
-            "class"    => Type::Class,
-            "else"     => Type::Else,
-            "false"    => Type::False,
-            "for"      => Type::For,
-            "fun"      => Type::Fun,
-            "if"       => Type::If,
-            "null"     => Type::Null,
-            "or"       => Type::Or,
-            "print"    => Type::Print,
-            "return"   => Type::Return,
-            "break"    => Type::Break,
-            "super"    => Type::Super,
-            "this"     => Type::This,
-            "true"     => Type::True,
-            "var"      => Type::Var,
-            "while"    => Type::While,
-            _          => Type::Identifier,
-        };
+        let token_type = KEYWORDS.iter()
+            .find(|(keyword, _)| *keyword == value)
+            .map_or(Type::Identifier, |(_, r#type)| *r#type);
 
         self.add_token(token_type, value, None);
     }
@@ -223,6 +255,9 @@ impl<'a> Scanner<'a> {
             ')' => self.add_single_char_token(Type::RightParen),
             '{' => self.add_single_char_token(Type::LeftBrace),
             '}' => self.add_single_char_token(Type::RightBrace),
+            '[' => self.add_single_char_token(Type::LeftBracket),
+            ']' => self.add_single_char_token(Type::RightBracket),
+            ':' => self.add_single_char_token(Type::Colon),
             ',' => self.add_single_char_token(Type::Comma),
             '.' => self.add_single_char_token(Type::Dot),
             '-' => self.add_single_char_token(Type::Minus),
@@ -241,6 +276,8 @@ impl<'a> Scanner<'a> {
             '=' => {
                 if self.match_next('=') {
                     self.add_double_char_token(Type::EqualEqual);
+                } else if self.match_next('>') {
+                    self.add_double_char_token(Type::Arrow);
                 } else {
                     self.add_single_char_token(Type::Equal)
                 };
@@ -261,9 +298,17 @@ impl<'a> Scanner<'a> {
             },
             '/' => {
                 if self.match_next('/') {
-                    while *self.peek() != '\n' && !self.is_at_end() {
-                        self.advance();
+                    let mut text = String::new();
+                    while !self.is_at_end() && *self.peek() != '\n' {
+                        text.push(self.advance());
                     }
+
+                    let trailing = self.tokens.last().is_some_and(|token| token.location.line == self.line);
+                    self.comments.push(Comment {
+                        text: text.trim_end().to_string(),
+                        location: Location::new(self.line, self.start - self.column_offset),
+                        trailing,
+                    });
                 } else {
                     self.add_single_char_token(Type::Slash);
                 }
@@ -294,10 +339,10 @@ impl<'a> Scanner<'a> {
             _ => {
                 self.advance();
 
-                ScanError {
+                self.errors.push(ScanError {
                     location: Location::new(self.line, self.start - self.column_offset),
                     message: format!("Unexpected character '{c}'"),
-                }.throw();
+                });
             },
         }
     }
@@ -0,0 +1,67 @@
+//! The `io` module.
+//!
+//! Paths are relative to the working directory of the host.
+//!
+//! Functions:
+//! - `read(path)` - Returns the contents of the file.
+//! - `lines(path)` - Returns the lines of the file as a list of strings.
+//! - `write(path, contents)` - Replaces the contents of the file, creating it if it is missing.
+//! - `append(path, contents)` - Appends to the contents of the file, creating it if it is missing.
+//! - `exists(path)` - Returns if there is a file or directory at the path.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use crate::config::Capability;
+use crate::literal::Literal;
+use crate::object::Arity;
+use crate::stdlib::{string, StdFunction, StdValue};
+
+/// Returns the functions of the module.
+pub fn functions<V: StdValue>() -> Vec<StdFunction<V>> {
+    vec![
+        StdFunction::new("read", Arity::Fixed(1), |arguments: Vec<V>| {
+            let contents = read(&string(&arguments[0])?)?;
+            Ok(V::from(Literal::String(contents)))
+        }).requires(Capability::Filesystem),
+        StdFunction::new("lines", Arity::Fixed(1), |arguments: Vec<V>| {
+            let lines: Vec<V> = read(&string(&arguments[0])?)?
+                .lines()
+                .map(|line| V::from(Literal::String(line.to_string())))
+                .collect();
+
+            Ok(V::from(lines))
+        }).requires(Capability::Filesystem),
+        StdFunction::new("write", Arity::Fixed(2), |arguments: Vec<V>| {
+            let path = string(&arguments[0])?;
+            let contents = string(&arguments[1])?;
+
+            fs::write(&path, contents)
+                .map_err(|err| format!("Could not write file '{path}': {err}"))?;
+
+            Ok(V::from(Literal::Null))
+        }).requires(Capability::Filesystem),
+        StdFunction::new("append", Arity::Fixed(2), |arguments: Vec<V>| {
+            let path = string(&arguments[0])?;
+            let contents = string(&arguments[1])?;
+
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .and_then(|mut file| file.write_all(contents.as_bytes()))
+                .map_err(|err| format!("Could not write file '{path}': {err}"))?;
+
+            Ok(V::from(Literal::Null))
+        }).requires(Capability::Filesystem),
+        StdFunction::new("exists", Arity::Fixed(1), |arguments: Vec<V>| {
+            let exists = Path::new(&string(&arguments[0])?).exists();
+            Ok(V::from(Literal::Bool(exists)))
+        }).requires(Capability::Filesystem),
+    ]
+}
+
+fn read(path: &str) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|err| format!("Could not read file '{path}': {err}"))
+}
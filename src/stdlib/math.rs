@@ -0,0 +1,118 @@
+//! The `math` module.
+//!
+//! Constants:
+//! - `pi` - The ratio of a circle's circumference to its diameter.
+//! - `e` - Euler's number.
+//!
+//! Functions:
+//! - `sqrt(x)`, `abs(x)`, `exp(x)`, `log(x)` - The usual functions of a number.
+//! - `floor(x)`, `ceil(x)`, `round(x)` - Rounds the number to a whole number.
+//! - `pow(x, y)` - Raises `x` to the power of `y`.
+//! - `sin(x)`, `cos(x)`, `tan(x)`, `asin(x)`, `acos(x)`, `atan(x)` - Trigonometric functions in
+//!   radians.
+//! - `atan2(y, x)` - Returns the angle of the point `(x, y)` in radians.
+//! - `min(x, ...)`, `max(x, ...)` - Returns the smallest or the largest of the numbers.
+//! - `random()` - Returns a pseudo-random number in the range `[0, 1)`.
+//! - `seed(x)` - Seeds the generator of `random()` so that it returns a repeatable sequence.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::literal::Literal;
+use crate::object::Arity;
+use crate::stdlib::{number, StdError, StdFunction, StdValue};
+
+/// Returns the constants of the module.
+pub fn constants() -> Vec<(&'static str, Literal)> {
+    vec![
+        ("pi", Literal::Number(std::f64::consts::PI)),
+        ("e", Literal::Number(std::f64::consts::E)),
+    ]
+}
+
+/// Returns the functions of the module.
+pub fn functions<V: StdValue>() -> Vec<StdFunction<V>> {
+    let state = Rc::new(Cell::new(mix(clock_seed())));
+    let random_state = Rc::clone(&state);
+
+    vec![
+        unary("sqrt", f64::sqrt),
+        unary("abs", f64::abs),
+        unary("exp", f64::exp),
+        unary("log", f64::ln),
+        unary("floor", f64::floor),
+        unary("ceil", f64::ceil),
+        unary("round", f64::round),
+        unary("sin", f64::sin),
+        unary("cos", f64::cos),
+        unary("tan", f64::tan),
+        unary("asin", f64::asin),
+        unary("acos", f64::acos),
+        unary("atan", f64::atan),
+        binary("pow", f64::powf),
+        binary("atan2", f64::atan2),
+        StdFunction::new("min", Arity::Variadic(1), |arguments: Vec<V>| {
+            fold(&arguments, f64::min)
+        }),
+        StdFunction::new("max", Arity::Variadic(1), |arguments: Vec<V>| {
+            fold(&arguments, f64::max)
+        }),
+        StdFunction::new("random", Arity::Fixed(0), move |_| {
+            // xorshift64*, the upper 53 bits are used as the mantissa of the result.
+            let mut x = random_state.get();
+            x ^= x >> 12;
+            x ^= x << 25;
+            x ^= x >> 27;
+            random_state.set(x);
+
+            let bits = x.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11;
+            Ok(V::from(Literal::Number(bits as f64 / (1u64 << 53) as f64)))
+        }),
+        StdFunction::new("seed", Arity::Fixed(1), move |arguments: Vec<V>| {
+            state.set(mix(number(&arguments[0])?.to_bits()));
+            Ok(V::from(Literal::Null))
+        }),
+    ]
+}
+
+fn unary<V: StdValue>(name: &'static str, function: fn(f64) -> f64) -> StdFunction<V> {
+    StdFunction::new(name, Arity::Fixed(1), move |arguments: Vec<V>| {
+        Ok(V::from(Literal::Number(function(number(&arguments[0])?))))
+    })
+}
+
+fn binary<V: StdValue>(name: &'static str, function: fn(f64, f64) -> f64) -> StdFunction<V> {
+    StdFunction::new(name, Arity::Fixed(2), move |arguments: Vec<V>| {
+        let result = function(number(&arguments[0])?, number(&arguments[1])?);
+        Ok(V::from(Literal::Number(result)))
+    })
+}
+
+fn fold<V: StdValue>(arguments: &[V], function: fn(f64, f64) -> f64) -> Result<V, StdError> {
+    let mut result = number(&arguments[0])?;
+    for argument in &arguments[1..] {
+        result = function(result, number(argument)?);
+    }
+
+    Ok(V::from(Literal::Number(result)))
+}
+
+fn clock_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+/// Scrambles the bits of a seed with splitmix64, the state of xorshift can never be zero.
+fn mix(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+
+    match z {
+        0 => 1,
+        z => z,
+    }
+}
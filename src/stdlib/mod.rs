@@ -0,0 +1,478 @@
+//! The standard library of native modules.
+//!
+//! Every module is a namespace of native functions (and constants) that scripts import by name:
+//! ```text
+//! import "math" as math;
+//! from "str" import split, join;
+//! ```
+//! Names of native modules take precedence over files with the same relative path.
+//!
+//! The modules are written once against the [`StdValue`] trait so that both the
+//! [`Interpreter`](crate::interpreter::Interpreter) and the [`Vm`](crate::vm::Vm) share the same
+//! implementation. Which modules are available to the scripts is decided by the [`Stdlib`] of the
+//! [`InterpreterConfig`], and functions that need a [`Capability`] can only be called if the
+//! configuration grants it.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use crate::config::{Capability, InterpreterConfig};
+use crate::error::RuntimeError;
+use crate::function::NativeFunction;
+use crate::interpreter::Interpreter;
+use crate::literal::Literal;
+use crate::map::Map;
+use crate::module::Module;
+use crate::object::{Arity, Object};
+use crate::token::Token;
+use crate::vm::Vm;
+use crate::vm::value::Value;
+
+pub mod math;
+pub mod string;
+pub mod io;
+pub mod os;
+pub mod time;
+
+/// Represents a native module of the standard library.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum StdModule {
+    /// Mathematical functions and constants, see [`math`].
+    Math,
+    /// String manipulation, see [`string`].
+    Str,
+    /// Reading and writing files, see [`io`].
+    Io,
+    /// Environment variables, arguments and exit codes, see [`os`].
+    Os,
+    /// Monotonic and wall clock time, see [`time`].
+    Time,
+}
+
+impl StdModule {
+    /// Every module of the standard library.
+    pub const ALL: [StdModule; 5] = [
+        StdModule::Math,
+        StdModule::Str,
+        StdModule::Io,
+        StdModule::Os,
+        StdModule::Time,
+    ];
+
+    /// Returns the name that scripts import the module with.
+    pub fn name(&self) -> &'static str {
+        match self {
+            StdModule::Math => "math",
+            StdModule::Str => "str",
+            StdModule::Io => "io",
+            StdModule::Os => "os",
+            StdModule::Time => "time",
+        }
+    }
+
+    /// Builds the namespace object of the module.
+    /// Native functions are converted to values of the backend with the given closure.
+    pub fn module<V, F>(&self, config: &InterpreterConfig, native: F) -> Module<V>
+    where
+        V: StdValue,
+        F: Fn(StdFunction<V>) -> V,
+    {
+        let constants = match self {
+            StdModule::Math => math::constants(),
+            _ => vec![],
+        };
+
+        let functions = match self {
+            StdModule::Math => math::functions(),
+            StdModule::Str => string::functions(),
+            StdModule::Io => io::functions(),
+            StdModule::Os => os::functions(config.stdlib.args()),
+            StdModule::Time => time::functions(),
+        };
+
+        let mut members: HashMap<String, V> = constants
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), V::from(value)))
+            .collect();
+
+        for function in functions {
+            members.insert(function.name.to_string(), native(function.gated(config)));
+        }
+
+        let exports = members.keys().cloned().collect();
+        Module::new(self.name().to_string(), Rc::new(RefCell::new(members)), exports)
+    }
+}
+
+/// Represents the set of native modules that are available to the scripts.
+/// By default, every module is enabled and the scripts are run without arguments.
+/// ```
+/// use rocks_lang::stdlib::{StdModule, Stdlib};
+///
+/// let stdlib = Stdlib::new(&[StdModule::Math, StdModule::Str]);
+/// assert!(stdlib.is_enabled(StdModule::Math));
+/// assert!(!stdlib.is_enabled(StdModule::Io));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Stdlib {
+    modules: Vec<StdModule>,
+    args: Vec<String>,
+}
+
+impl Stdlib {
+    /// Creates a new standard library that only enables the given modules.
+    pub fn new(modules: &[StdModule]) -> Self {
+        Stdlib { modules: modules.to_vec(), args: vec![] }
+    }
+
+    /// Sets the arguments that are returned by `os.args()`.
+    pub fn with_args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
+
+    /// Returns the enabled modules.
+    pub fn modules(&self) -> &[StdModule] {
+        &self.modules
+    }
+
+    /// Returns if the given module is enabled.
+    pub fn is_enabled(&self, module: StdModule) -> bool {
+        self.modules.contains(&module)
+    }
+
+    /// Returns the arguments of the scripts.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+}
+
+impl Default for Stdlib {
+    fn default() -> Self {
+        Self::new(&StdModule::ALL)
+    }
+}
+
+/// The implementation of a native function of the standard library.
+/// Failures are reported as messages, the backend turns them into runtime errors at the call
+/// site.
+pub type StdFn<V> = dyn Fn(Vec<V>) -> Result<V, StdError>;
+
+/// Represents the reasons that a native function of the standard library can return without a
+/// value for.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum StdError {
+    /// The function failed with the given message.
+    Message(String),
+    /// The script asked to exit with the given code, see [`os`].
+    /// The backend stops the run and hands the code to the caller of
+    /// [`rocks::run`](crate::rocks::run).
+    Exit(i32),
+}
+
+impl From<String> for StdError {
+    fn from(message: String) -> Self {
+        StdError::Message(message)
+    }
+}
+
+/// Represents a native function of the standard library.
+pub struct StdFunction<V> {
+    pub name: &'static str,
+    pub arity: Arity,
+    /// The capability that the function needs to be called (if any).
+    pub capability: Option<Capability>,
+    pub function: Rc<StdFn<V>>,
+}
+
+impl<V: StdValue> StdFunction<V> {
+    /// Creates a new function with the given name, arity and implementation.
+    pub fn new<F>(name: &'static str, arity: Arity, function: F) -> Self
+    where
+        F: Fn(Vec<V>) -> Result<V, StdError> + 'static
+    {
+        StdFunction { name, arity, capability: None, function: Rc::new(function) }
+    }
+
+    /// Marks the function as needing the given capability.
+    pub fn requires(mut self, capability: Capability) -> Self {
+        self.capability = Some(capability);
+        self
+    }
+
+    /// Replaces the implementation with one that always fails if the function needs a capability
+    /// that the given configuration denies.
+    pub fn gated(mut self, config: &InterpreterConfig) -> Self {
+        let Some(capability) = self.capability else {
+            return self;
+        };
+
+        if let Err(message) = config.check(capability, &format!("call '{}'", self.name)) {
+            self.function = Rc::new(move |_| Err(StdError::Message(message.clone())));
+        }
+
+        self
+    }
+}
+
+/// Returns the native functions that are defined in the global scope of every script.
+///
+/// Current functions:
+/// - `clock()` - Returns the current time in milliseconds.
+/// - `input()` - Reads a line of string from the standard input.
+pub fn globals<V: StdValue>() -> Vec<StdFunction<V>> {
+    vec![
+        StdFunction::new("clock", Arity::Fixed(0), |_| {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_millis();
+            Ok(V::from(Literal::Number(now as f64)))
+        }).requires(Capability::Clock),
+        StdFunction::new("input", Arity::Fixed(0), |_| {
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input).unwrap();
+            input.pop();  // Remove newline
+            Ok(V::from(Literal::String(input)))
+        }).requires(Capability::Stdin),
+    ]
+}
+
+/// Represents the values of a backend that the standard library works with.
+/// It is implemented for the [`Object`] of the interpreter and the [`Value`] of the vm.
+pub trait StdValue: Clone + Display + From<Literal> + From<Vec<Self>> + 'static {
+    /// Returns the literal of the value (if it is one).
+    fn literal(&self) -> Option<&Literal>;
+
+    /// Returns a copy of the elements of the value (if it is a list).
+    fn elements(&self) -> Option<Vec<Self>>;
+
+    /// Returns the shared elements of the value (if it is a list).
+    fn list(&self) -> Option<&Rc<RefCell<Vec<Self>>>>;
+
+    /// Returns the shared entries of the value (if it is a map).
+    fn map(&self) -> Option<&Rc<RefCell<Map<Self>>>>;
+
+    /// Returns the name of the type of the value.
+    fn type_name(&self) -> &str;
+
+    /// Wraps the given closure into a native function of the backend.
+    /// Unlike the functions of the modules, the closure can call back into the backend, which
+    /// the built-in methods of [`lists`](crate::list) need.
+    fn native<F>(name: &'static str, arity: Arity, function: F) -> Self
+    where
+        F: Fn(&mut dyn Caller<Self>, Vec<Self>) -> Result<Self, RuntimeError> + 'static;
+
+    /// Wraps the given closure into a native function of the backend that is bound to the
+    /// receiver, the closure gets the receiver with every call.
+    /// The function holds the receiver instead of the closure so that the cycle collector can see
+    /// the reference, which is how the methods of lists and maps are implemented.
+    fn method<F>(receiver: Self, name: &'static str, arity: Arity, function: F) -> Self
+    where
+        F: Fn(&mut dyn Caller<Self>, &Self, Vec<Self>) -> Result<Self, RuntimeError> + 'static;
+
+    /// Returns if the value is considered true in a condition.
+    /// Only literals can be true, every other value is considered false.
+    fn is_truthy(&self) -> bool {
+        self.literal().is_some_and(Literal::as_bool)
+    }
+}
+
+/// Represents a backend that native functions can call the values of the scripts with.
+pub trait Caller<V> {
+    /// Calls the given value with the given arguments and returns its result.
+    /// The given token is used to report errors that occur at the call site.
+    fn call(&mut self, callee: V, arguments: Vec<V>, token: &Token) -> Result<V, RuntimeError>;
+}
+
+/// Represents a native function that a host registers with
+/// [`rocks::register_native`](crate::rocks::register_native).
+/// Like the standard library, it is written once against [`StdValue`] so that it runs on both
+/// backends, and it can call back into the backend through the [`Caller`].
+/// ```
+/// use rocks_lang::error::RuntimeError;
+/// use rocks_lang::literal::Literal;
+/// use rocks_lang::stdlib::{Caller, Native, StdValue};
+///
+/// struct Twice;
+///
+/// impl Native for Twice {
+///     fn call<V: StdValue>(&self, _: &mut dyn Caller<V>, arguments: Vec<V>) -> Result<V, RuntimeError> {
+///         Ok(V::from(Literal::String(format!("{0}{0}", arguments[0]))))
+///     }
+/// }
+/// ```
+pub trait Native: 'static {
+    /// Calls the function with the given arguments, which are already checked against its arity.
+    fn call<V: StdValue>(&self, caller: &mut dyn Caller<V>, arguments: Vec<V>) -> Result<V, RuntimeError>;
+}
+
+/// Allows any closure over literals to be used as a native function.
+/// The closure can not call back into the backend, and calls with arguments that are not literals
+/// fail before it is called. Failures are reported as runtime errors at the call site.
+impl<F> Native for F
+where
+    F: Fn(Vec<Literal>) -> Result<Literal, String> + 'static
+{
+    fn call<V: StdValue>(&self, _: &mut dyn Caller<V>, arguments: Vec<V>) -> Result<V, RuntimeError> {
+        let arguments = arguments.iter()
+            .map(|argument| argument.literal().cloned()
+                .ok_or_else(|| format!("Expected a literal but got {}", argument.type_name())))
+            .collect::<Result<Vec<Literal>, String>>();
+
+        match arguments.and_then(self) {
+            Ok(literal) => Ok(V::from(literal)),
+            // The backend replaces the token with the one of the call site.
+            Err(message) => Err(RuntimeError { token: Token::from("native"), message, help: None, trace: vec![], value: None }),
+        }
+    }
+}
+
+impl Caller<Object> for Interpreter<'_> {
+    fn call(&mut self, callee: Object, arguments: Vec<Object>, token: &Token) -> Result<Object, RuntimeError> {
+        Interpreter::call(self, callee, arguments, token)
+    }
+}
+
+impl Caller<Value> for Vm<'_> {
+    fn call(&mut self, callee: Value, arguments: Vec<Value>, token: &Token) -> Result<Value, RuntimeError> {
+        Vm::call(self, callee, arguments, token)
+    }
+}
+
+impl StdValue for Object {
+    fn literal(&self) -> Option<&Literal> {
+        match self {
+            Object::Literal(literal) => Some(literal),
+            _ => None,
+        }
+    }
+
+    fn elements(&self) -> Option<Vec<Self>> {
+        match self {
+            Object::List(list) => Some(list.borrow().clone()),
+            _ => None,
+        }
+    }
+
+    fn list(&self) -> Option<&Rc<RefCell<Vec<Self>>>> {
+        match self {
+            Object::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    fn map(&self) -> Option<&Rc<RefCell<Map<Self>>>> {
+        match self {
+            Object::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    fn type_name(&self) -> &str {
+        self.type_str()
+    }
+
+    fn native<F>(name: &'static str, arity: Arity, function: F) -> Self
+    where
+        F: Fn(&mut dyn Caller<Self>, Vec<Self>) -> Result<Self, RuntimeError> + 'static
+    {
+        Object::from(NativeFunction::new(name, arity, move |interpreter, arguments| function(interpreter, arguments)))
+    }
+
+    fn method<F>(receiver: Self, name: &'static str, arity: Arity, function: F) -> Self
+    where
+        F: Fn(&mut dyn Caller<Self>, &Self, Vec<Self>) -> Result<Self, RuntimeError> + 'static
+    {
+        let native = NativeFunction::new(name, arity, move |interpreter, mut arguments| {
+            let receiver = arguments.remove(0);
+            function(interpreter, &receiver, arguments)
+        });
+
+        Object::from(native.bind(receiver))
+    }
+}
+
+impl StdValue for Value {
+    fn literal(&self) -> Option<&Literal> {
+        match self {
+            Value::Literal(literal) => Some(literal),
+            _ => None,
+        }
+    }
+
+    fn elements(&self) -> Option<Vec<Self>> {
+        match self {
+            Value::List(list) => Some(list.borrow().clone()),
+            _ => None,
+        }
+    }
+
+    fn list(&self) -> Option<&Rc<RefCell<Vec<Self>>>> {
+        match self {
+            Value::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    fn map(&self) -> Option<&Rc<RefCell<Map<Self>>>> {
+        match self {
+            Value::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    fn type_name(&self) -> &str {
+        self.type_str()
+    }
+
+    fn native<F>(name: &'static str, arity: Arity, function: F) -> Self
+    where
+        F: Fn(&mut dyn Caller<Self>, Vec<Self>) -> Result<Self, RuntimeError> + 'static
+    {
+        Value::from(crate::vm::value::NativeFunction::new(name, arity, move |vm, arguments| function(vm, arguments)))
+    }
+
+    fn method<F>(receiver: Self, name: &'static str, arity: Arity, function: F) -> Self
+    where
+        F: Fn(&mut dyn Caller<Self>, &Self, Vec<Self>) -> Result<Self, RuntimeError> + 'static
+    {
+        let native = crate::vm::value::NativeFunction::new(name, arity, move |vm, mut arguments| {
+            let receiver = arguments.remove(0);
+            function(vm, &receiver, arguments)
+        });
+
+        Value::from(native.bind(receiver))
+    }
+}
+
+/// Returns the number in the given value.
+fn number<V: StdValue>(value: &V) -> Result<f64, String> {
+    match value.literal() {
+        Some(Literal::Number(number)) => Ok(*number),
+        _ => Err(format!("Expected a number but got {}", value.type_name())),
+    }
+}
+
+/// Returns the non-negative whole number in the given value.
+fn index<V: StdValue>(value: &V) -> Result<usize, String> {
+    match number(value)? {
+        number if number >= 0.0 && number.fract() == 0.0 => Ok(number as usize),
+        number => Err(format!("Expected a non-negative integer but got {number}")),
+    }
+}
+
+/// Returns the string in the given value.
+fn string<V: StdValue>(value: &V) -> Result<String, String> {
+    match value.literal() {
+        Some(Literal::String(string)) => Ok(string.clone()),
+        _ => Err(format!("Expected a string but got {}", value.type_name())),
+    }
+}
+
+/// Returns the elements of the list in the given value.
+fn list<V: StdValue>(value: &V) -> Result<Vec<V>, String> {
+    value.elements().ok_or_else(|| format!("Expected a list but got {}", value.type_name()))
+}
@@ -0,0 +1,40 @@
+//! The `os` module.
+//!
+//! Functions:
+//! - `env(name)` - Returns the value of the environment variable or `null` if it is not set.
+//! - `args()` - Returns the arguments that were passed to the script as a list of strings.
+//! - `exit(code)` - Stops the script, the caller of the run gets the given status code.
+
+use crate::config::Capability;
+use crate::literal::Literal;
+use crate::object::Arity;
+use crate::stdlib::{number, string, StdError, StdFunction, StdValue};
+
+/// Returns the functions of the module.
+/// `args()` returns the given arguments.
+pub fn functions<V: StdValue>(args: &[String]) -> Vec<StdFunction<V>> {
+    let args = args.to_vec();
+
+    vec![
+        StdFunction::new("env", Arity::Fixed(1), |arguments: Vec<V>| {
+            let value = match std::env::var(string(&arguments[0])?) {
+                Ok(value) => Literal::String(value),
+                Err(_) => Literal::Null,
+            };
+
+            Ok(V::from(value))
+        }).requires(Capability::Environment),
+        StdFunction::new("args", Arity::Fixed(0), move |_| {
+            let args: Vec<V> = args.iter().map(|arg| V::from(Literal::String(arg.clone()))).collect();
+            Ok(V::from(args))
+        }),
+        StdFunction::new("exit", Arity::Fixed(1), |arguments: Vec<V>| {
+            let code = number(&arguments[0])?;
+            if code.fract() != 0.0 {
+                return Err(format!("Expected an integer exit code but got {code}").into());
+            }
+
+            Err(StdError::Exit(code as i32))
+        }).requires(Capability::Environment),
+    ]
+}
@@ -0,0 +1,106 @@
+//! The `str` module.
+//!
+//! Positions in strings count characters, not bytes.
+//!
+//! Functions:
+//! - `len(s)` - Returns the number of characters in the string.
+//! - `split(s, separator)` - Splits the string at every separator, an empty separator splits it
+//!   into characters.
+//! - `join(list, separator)` - Joins the elements of the list with the separator.
+//! - `trim(s)` - Removes the whitespace from both ends of the string.
+//! - `upper(s)`, `lower(s)` - Converts the string to upper or lower case.
+//! - `contains(s, pattern)` - Returns if the string contains the pattern.
+//! - `find(s, pattern)` - Returns the position of the first occurrence of the pattern or `-1`.
+//! - `replace(s, pattern, replacement)` - Replaces every occurrence of the pattern.
+//! - `substring(s, start, end)` - Returns the characters from `start` up to (but not including)
+//!   `end`.
+
+use crate::literal::Literal;
+use crate::object::Arity;
+use crate::stdlib::{index, list, string, StdFunction, StdValue};
+
+/// Returns the functions of the module.
+pub fn functions<V: StdValue>() -> Vec<StdFunction<V>> {
+    vec![
+        StdFunction::new("len", Arity::Fixed(1), |arguments: Vec<V>| {
+            let length = string(&arguments[0])?.chars().count();
+            Ok(V::from(Literal::Number(length as f64)))
+        }),
+        StdFunction::new("split", Arity::Fixed(2), |arguments: Vec<V>| {
+            let string = string(&arguments[0])?;
+            let separator = self::string(&arguments[1])?;
+
+            let parts: Vec<V> = match separator.is_empty() {
+                true => string.chars().map(|c| V::from(Literal::String(c.to_string()))).collect(),
+                false => string
+                    .split(&separator)
+                    .map(|part| V::from(Literal::String(part.to_string())))
+                    .collect(),
+            };
+
+            Ok(V::from(parts))
+        }),
+        StdFunction::new("join", Arity::Fixed(2), |arguments: Vec<V>| {
+            let elements = list(&arguments[0])?;
+            let separator = string(&arguments[1])?;
+
+            let joined = elements
+                .iter()
+                .map(|element| element.to_string())
+                .collect::<Vec<String>>()
+                .join(&separator);
+
+            Ok(V::from(Literal::String(joined)))
+        }),
+        map("trim", |string| string.trim().to_string()),
+        map("upper", |string| string.to_uppercase()),
+        map("lower", |string| string.to_lowercase()),
+        StdFunction::new("contains", Arity::Fixed(2), |arguments: Vec<V>| {
+            let contains = string(&arguments[0])?.contains(&string(&arguments[1])?);
+            Ok(V::from(Literal::Bool(contains)))
+        }),
+        StdFunction::new("find", Arity::Fixed(2), |arguments: Vec<V>| {
+            let string = string(&arguments[0])?;
+            let pattern = self::string(&arguments[1])?;
+
+            let position = match string.find(&pattern) {
+                Some(offset) => string[..offset].chars().count() as f64,
+                None => -1.0,
+            };
+
+            Ok(V::from(Literal::Number(position)))
+        }),
+        StdFunction::new("replace", Arity::Fixed(3), |arguments: Vec<V>| {
+            let string = string(&arguments[0])?;
+            let pattern = self::string(&arguments[1])?;
+            let replacement = self::string(&arguments[2])?;
+
+            if pattern.is_empty() {
+                return Err("Cannot replace an empty pattern".to_string().into());
+            }
+
+            Ok(V::from(Literal::String(string.replace(&pattern, &replacement))))
+        }),
+        StdFunction::new("substring", Arity::Fixed(3), |arguments: Vec<V>| {
+            let string = string(&arguments[0])?;
+            let start = index(&arguments[1])?;
+            let end = index(&arguments[2])?;
+            let length = string.chars().count();
+
+            if start > end || end > length {
+                return Err(format!(
+                    "Substring range {start}..{end} is out of bounds for a string of length {length}"
+                ).into());
+            }
+
+            let substring = string.chars().skip(start).take(end - start).collect();
+            Ok(V::from(Literal::String(substring)))
+        }),
+    ]
+}
+
+fn map<V: StdValue>(name: &'static str, function: fn(&str) -> String) -> StdFunction<V> {
+    StdFunction::new(name, Arity::Fixed(1), move |arguments: Vec<V>| {
+        Ok(V::from(Literal::String(function(&string(&arguments[0])?))))
+    })
+}
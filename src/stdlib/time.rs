@@ -0,0 +1,41 @@
+//! The `time` module.
+//!
+//! Functions:
+//! - `now()` - Returns the wall clock time in milliseconds since the Unix epoch.
+//! - `monotonic()` - Returns the milliseconds since the module was created. Unlike `now()`, it
+//!   never goes backwards, which makes it suitable to measure durations.
+//! - `sleep(ms)` - Blocks for the given number of milliseconds.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::config::Capability;
+use crate::literal::Literal;
+use crate::object::Arity;
+use crate::stdlib::{number, StdFunction, StdValue};
+
+/// Returns the functions of the module.
+pub fn functions<V: StdValue>() -> Vec<StdFunction<V>> {
+    let start = Instant::now();
+
+    vec![
+        StdFunction::new("now", Arity::Fixed(0), |_| {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_err(|err| err.to_string())?;
+
+            Ok(V::from(Literal::Number(now.as_secs_f64() * 1000.0)))
+        }).requires(Capability::Clock),
+        StdFunction::new("monotonic", Arity::Fixed(0), move |_| {
+            Ok(V::from(Literal::Number(start.elapsed().as_secs_f64() * 1000.0)))
+        }).requires(Capability::Clock),
+        StdFunction::new("sleep", Arity::Fixed(1), |arguments: Vec<V>| {
+            let milliseconds = number(&arguments[0])?;
+            if !(milliseconds >= 0.0 && milliseconds.is_finite()) {
+                return Err(format!("Cannot sleep for {milliseconds} milliseconds").into());
+            }
+
+            std::thread::sleep(Duration::from_secs_f64(milliseconds / 1000.0));
+            Ok(V::from(Literal::Null))
+        }).requires(Capability::Clock),
+    ]
+}
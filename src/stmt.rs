@@ -2,13 +2,13 @@ use crate::expr::Expr;
 use crate::token::Token;
 
 /// Represents an [`expression`](Stmt::Expression) statement's data in the language.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct ExpressionData {
     pub expr: Expr,
 }
 
 /// Represents a [`function`](Stmt::Function) statement's data in the language.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct FunctionData {
     /// The function's name.
     pub name: Token,
@@ -19,7 +19,7 @@ pub struct FunctionData {
 }
 
 /// Represents an [`if`](Stmt::If) statement's data in the language.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct IfData {
     /// The condition to check.
     pub condition: Expr,
@@ -30,14 +30,16 @@ pub struct IfData {
 }
 
 /// Represents a [`print`](Stmt::Print) statement's data in the language.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct PrintData {
+    /// The `print` keyword.
+    pub keyword: Token,
     /// The expression to print.
     pub expr: Expr,
 }
 
 /// Represents a [`return`](Stmt::Return) statement's data in the language.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct ReturnData {
     /// The 'return' keyword.
     pub keyword: Token,
@@ -46,13 +48,61 @@ pub struct ReturnData {
 }
 
 /// Represents a [`break`](Stmt::Break) statement's data in the language.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct BreakData {
     pub keyword: Token,
+    /// The label of the loop to break out of, the innermost loop if there is none.
+    pub label: Option<Token>,
+}
+
+/// Represents a [`continue`](Stmt::Continue) statement's data in the language.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ContinueData {
+    pub keyword: Token,
+    /// The label of the loop to continue, the innermost loop if there is none.
+    pub label: Option<Token>,
+}
+
+/// Represents a [`throw`](Stmt::Throw) statement's data in the language.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ThrowData {
+    pub keyword: Token,
+    pub value: Expr,
+}
+
+/// Represents the catch clause of a [`try`](Stmt::Try) statement in the language.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CatchData {
+    /// The name of the variable that holds the caught error.
+    pub name: Token,
+    pub body: Vec<Stmt>,
+}
+
+/// Represents a [`try`](Stmt::Try) statement's data in the language.
+/// At least one of the catch and finally clauses is present.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TryData {
+    pub keyword: Token,
+    pub body: Vec<Stmt>,
+    pub catch: Option<CatchData>,
+    /// The statements to execute after the body and the catch clause, no matter how they exit.
+    pub finally: Option<Vec<Stmt>>,
+}
+
+/// Represents an [`import`](Stmt::Import) statement's data in the language.
+/// Either the whole module is bound to the alias, or the given names are bound to the bindings
+/// of the module with the same names.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ImportData {
+    pub keyword: Token,
+    /// The string literal that holds the path of the module.
+    pub path: Token,
+    pub alias: Option<Token>,
+    pub names: Vec<Token>,
 }
 
 /// Represents a [`var`](Stmt::Var) statement's data in the language.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct VarData {
     /// The variable's name.
     pub name: Token,
@@ -62,23 +112,43 @@ pub struct VarData {
 }
 
 /// Represents a [`while`](Stmt::While) statement's data in the language.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct WhileData {
+    /// The `while` (or `for`) keyword.
+    pub keyword: Token,
     /// The condition to check.
     pub condition: Expr,
     /// The statement to execute while the condition is true.
     pub body: Box<Stmt>,
+    /// The expression to evaluate after each iteration (optional).
+    /// This is the increment clause of a `for` loop, it is also evaluated when the body continues.
+    pub increment: Option<Expr>,
+    /// The label of the loop (optional).
+    /// This is used by break and continue statements to target an outer loop.
+    pub label: Option<Token>,
+}
+
+impl WhileData {
+    /// Returns if a break or continue statement with the given label targets this loop.
+    /// Statements without a label always target the innermost loop.
+    pub fn is_target(&self, label: &Option<Token>) -> bool {
+        match (label, &self.label) {
+            (None, _) => true,
+            (Some(label), Some(own)) => label.lexeme == own.lexeme,
+            (Some(_), None) => false,
+        }
+    }
 }
 
 /// Represents a [`block`](Stmt::Block) statement's data in the language.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct BlockData {
     /// The statements in the block.
     pub statements: Vec<Stmt>,
 }
 
 /// Represents a [`class`](Stmt::Class) statement's data in the language.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct ClassData {
     /// The class's name.
     pub name: Token,
@@ -89,7 +159,7 @@ pub struct ClassData {
 }
 
 /// Represents a statement in the language.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Stmt {
     /// An [`expression`](crate::expr::Expr) statement.
     /// This is the only statement that evaluates to a value.
@@ -116,6 +186,18 @@ pub enum Stmt {
     /// This is used to break out of a loop.
     Break(BreakData),
 
+    /// A continue statement.
+    /// This is used to skip to the next iteration of a loop.
+    Continue(ContinueData),
+
+    /// A throw statement.
+    /// This is used to raise an error that can be caught by a try statement.
+    Throw(ThrowData),
+
+    /// A try statement.
+    /// This is used to catch errors and to run cleanup code.
+    Try(TryData),
+
     /// A var statement.
     /// This is used to declare a variable.
     Var(VarData),
@@ -131,9 +213,34 @@ pub enum Stmt {
     /// A class statement.
     /// This is used to declare a class.
     Class(ClassData),
+
+    /// An import statement.
+    /// This is used to bind the top-level bindings of another file.
+    Import(ImportData),
 }
 
 impl Stmt {
+    /// Returns a token that locates the statement in the source code.
+    /// Blocks and expression statements of literals have no token of their own.
+    pub fn token(&self) -> Option<&Token> {
+        match self {
+            Stmt::Expression(data) => data.expr.token(),
+            Stmt::Function(data) => Some(&data.name),
+            Stmt::If(data) => data.condition.token(),
+            Stmt::Print(data) => Some(&data.keyword),
+            Stmt::Return(data) => Some(&data.keyword),
+            Stmt::Break(data) => Some(&data.keyword),
+            Stmt::Continue(data) => Some(&data.keyword),
+            Stmt::Throw(data) => Some(&data.keyword),
+            Stmt::Try(data) => Some(&data.keyword),
+            Stmt::Var(data) => Some(&data.name),
+            Stmt::While(data) => Some(&data.keyword),
+            Stmt::Block(_) => None,
+            Stmt::Class(data) => Some(&data.name),
+            Stmt::Import(data) => Some(&data.keyword),
+        }
+    }
+
     /// Accepts a visitor and returns the result of the visit.
     /// This is used to implement the visitor pattern.
     pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> T {
@@ -146,10 +253,14 @@ impl Stmt {
             Print(_) => visitor.visit_print_stmt(self),
             Return(_) => visitor.visit_return_stmt(self),
             Break(_) => visitor.visit_break_stmt(self),
+            Continue(_) => visitor.visit_continue_stmt(self),
+            Throw(_) => visitor.visit_throw_stmt(self),
+            Try(_) => visitor.visit_try_stmt(self),
             Var(_) => visitor.visit_var_stmt(self),
             While(_) => visitor.visit_while_stmt(self),
             Block(_) => visitor.visit_block_stmt(self),
             Class(_) => visitor.visit_class_stmt(self),
+            Import(_) => visitor.visit_import_stmt(self),
         }
     }
 }
@@ -162,8 +273,12 @@ pub trait StmtVisitor<T> {
     fn visit_print_stmt(&mut self, stmt: &Stmt) -> T;
     fn visit_return_stmt(&mut self, stmt: &Stmt) -> T;
     fn visit_break_stmt(&mut self, stmt: &Stmt) -> T;
+    fn visit_continue_stmt(&mut self, stmt: &Stmt) -> T;
+    fn visit_throw_stmt(&mut self, stmt: &Stmt) -> T;
+    fn visit_try_stmt(&mut self, stmt: &Stmt) -> T;
     fn visit_var_stmt(&mut self, stmt: &Stmt) -> T;
     fn visit_while_stmt(&mut self, stmt: &Stmt) -> T;
     fn visit_block_stmt(&mut self, stmt: &Stmt) -> T;
     fn visit_class_stmt(&mut self, stmt: &Stmt) -> T;
+    fn visit_import_stmt(&mut self, stmt: &Stmt) -> T;
 }
@@ -7,27 +7,30 @@ use crate::literal::Literal;
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum Type {
     // Single-character tokens.
-    LeftParen, RightParen, LeftBrace, RightBrace,
-    Comma, Dot, Minus, Plus, Semicolon, Slash, Star,
+    LeftParen, RightParen, LeftBrace, RightBrace, LeftBracket, RightBracket,
+    Colon, Comma, Dot, Minus, Plus, Semicolon, Slash, Star,
 
     // One or two character tokens.
     Bang, BangEqual,
     Equal, EqualEqual,
     Greater, GreaterEqual,
     Less, LessEqual,
+    Arrow,
 
     // Literals.
     Identifier, String, Number,
 
     // Keywords.
     And, Class, Else, False, Fun, For, If, Null, Or,
-    Print, Return, Break, Super, This, True, Var, While,
+    Print, Return, Break, Continue, Super, This, True, Var, While,
+    Throw, Try, Catch, Finally, Import,
 
     EOF
 }
 
 /// Represents a location in the source code.
-#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+/// Locations are ordered by their line first and then by their column.
+#[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Copy, Clone, Hash)]
 pub struct Location {
     pub line: usize,
     pub column: usize,
@@ -46,6 +49,19 @@ impl Display for Location {
     }
 }
 
+/// Represents a comment in the source code.
+/// Comments are not tokens, the scanner keeps them aside for the tools that reproduce the source
+/// code like the [`formatter`](crate::formatter).
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Comment {
+    /// The text of the comment, including the leading slashes.
+    pub text: String,
+    /// Location of the comment in the source code.
+    pub location: Location,
+    /// If true, the comment follows a token on the same line.
+    pub trailing: bool,
+}
+
 /// Represents a token in the language.
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Token {
@@ -57,6 +73,9 @@ pub struct Token {
     pub literal: Option<Literal>,
     /// Location of the token in the source code.
     pub location: Location,
+    /// Location right after the end of the token in the source code.
+    /// The lexeme can be shorter than the source code, e.g. strings do not include their quotes.
+    pub end: Location,
 }
 
 impl Token {
@@ -66,15 +85,17 @@ impl Token {
         lexeme: String,
         literal: Option<Literal>,
         location: Location,
+        end: Location,
     ) -> Token {
-        Token { r#type, lexeme, literal, location }
+        Token { r#type, lexeme, literal, location, end }
     }
 }
 
 /// Convenience methods for creating dummy tokens.
 impl From<&str> for Token {
     fn from(token: &str) -> Self {
-        Token::new(Type::Identifier, token.to_string(), None, Location::new(0, 0))
+        let end = Location::new(0, token.chars().count());
+        Token::new(Type::Identifier, token.to_string(), None, Location::new(0, 0), end)
     }
 }
 
@@ -84,9 +105,9 @@ impl Display for Token {
     }
 }
 
-/// This is required for the `HashMap` to work for [`Interpreter::locals`](crate::interpreter::Interpreter).
-/// Since no two tokens can have the same type, lexeme, and location, we can use them as the key for
-/// the `HashMap`.
+/// This allows tokens to be used as the key of a `HashMap`.
+/// Since no two tokens can have the same type, lexeme, and location, they uniquely identify a
+/// piece of source code.
 impl Hash for Token {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.r#type.hash(state);
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::rc::Rc;
+
+use crate::token::Token;
+use crate::vm::value::{Function, Value};
+
+/// Represents a single instruction of the [`Vm`](crate::vm::Vm).
+/// Operands are stored inline. Operands that refer to names or values refer to an index in the
+/// constant pool of the chunk, jump targets refer to an index in the code of the chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpCode {
+    /// Pushes the constant at the given index.
+    Constant(usize),
+    Null,
+    True,
+    False,
+    Pop,
+
+    /// Pushes the local variable at the given stack slot (relative to the current frame).
+    GetLocal(usize),
+    SetLocal(usize),
+    /// Pushes the global variable with the name at the given constant index.
+    GetGlobal(usize),
+    DefineGlobal(usize),
+    SetGlobal(usize),
+    /// Pushes the upvalue at the given index of the current closure.
+    GetUpvalue(usize),
+    SetUpvalue(usize),
+    /// Pushes the property with the name at the given constant index.
+    GetProperty(usize),
+    SetProperty(usize),
+    /// Pushes the superclass method with the name at the given constant index.
+    GetSuper(usize),
+
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Not,
+    Negate,
+
+    Print,
+    /// Jumps to the given instruction.
+    Jump(usize),
+    /// Jumps to the given instruction if the value on top of the stack is false.
+    /// The value is left on the stack.
+    JumpIfFalse(usize),
+    /// Jumps back to the given instruction.
+    Loop(usize),
+    /// Calls the value below the given number of arguments.
+    Call(usize),
+    /// Creates a closure from the function at the given index of the function pool.
+    Closure(usize),
+    /// Moves the local variable on top of the stack into its upvalue and pops it.
+    CloseUpvalue,
+    Return,
+
+    /// Installs a handler that jumps to the given instruction when a runtime error occurs.
+    /// The handler pushes the caught error after discarding everything above the current stack.
+    Try(usize),
+    /// Removes the most recently installed handler.
+    EndTry,
+    /// Raises the value on top of the stack as a runtime error.
+    Throw,
+    /// Pushes the namespace object of the module whose path is the literal of the instruction's
+    /// token. The module is executed the first time it is imported.
+    Import,
+
+    /// Creates a class with the name at the given constant index.
+    Class(usize),
+    /// Copies the methods of the superclass into the subclass on top of the stack.
+    Inherit,
+    /// Adds the closure on top of the stack as a method with the name at the given constant index.
+    Method(usize),
+
+    /// Creates a list from the given number of values on top of the stack.
+    List(usize),
+    /// Creates a map from the given number of key and value pairs on top of the stack.
+    Map(usize),
+    /// Checks that the value on top of the stack can be used as a map key.
+    /// The value is left on the stack.
+    MapKey,
+    Index,
+    IndexSet,
+}
+
+/// Represents a sequence of bytecode instructions and the constants they refer to.
+/// Every instruction has a token associated with it that is used to report runtime errors.
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<OpCode>,
+    pub tokens: Vec<Token>,
+    pub constants: Vec<Value>,
+    /// The functions that are declared inside this chunk.
+    pub functions: Vec<Rc<Function>>,
+    /// The local variables that are most similar to the globals that the instructions at the
+    /// given indices access. They are suggested if the global is not defined.
+    pub similar: HashMap<usize, String>,
+}
+
+impl Chunk {
+    /// Appends an instruction to the chunk and returns its index.
+    pub fn write(&mut self, op: OpCode, token: &Token) -> usize {
+        self.code.push(op);
+        self.tokens.push(token.clone());
+        self.code.len() - 1
+    }
+
+    /// Adds a constant to the constant pool and returns its index.
+    pub fn add_constant(&mut self, value: Value) -> usize {
+        self.constants.push(value);
+        self.constants.len() - 1
+    }
+
+    /// Adds a function to the function pool and returns its index.
+    pub fn add_function(&mut self, function: Function) -> usize {
+        self.functions.push(Rc::new(function));
+        self.functions.len() - 1
+    }
+
+    /// Returns a human readable listing of the instructions in the chunk.
+    pub fn disassemble(&self, name: &str) -> String {
+        let mut string = String::new();
+        writeln!(string, "== {name} ==").unwrap();
+
+        for (offset, op) in self.code.iter().enumerate() {
+            let line = self.tokens[offset].location.line + 1;
+            let operand = match op {
+                OpCode::Constant(index)
+                | OpCode::GetGlobal(index)
+                | OpCode::DefineGlobal(index)
+                | OpCode::SetGlobal(index)
+                | OpCode::GetProperty(index)
+                | OpCode::SetProperty(index)
+                | OpCode::GetSuper(index)
+                | OpCode::Class(index)
+                | OpCode::Method(index) => format!(" '{}'", self.constants[*index]),
+                OpCode::Closure(index) => format!(" {}", self.functions[*index]),
+                _ => String::new(),
+            };
+
+            writeln!(string, "{offset:04} {line:>4} {op:?}{operand}").unwrap();
+        }
+
+        string
+    }
+}
@@ -0,0 +1,844 @@
+use crate::error;
+use crate::expr::{Expr, ExprVisitor};
+use crate::literal::Literal;
+use crate::stmt::{Stmt, StmtVisitor};
+use crate::token::{Token, Type};
+use crate::vm::chunk::{Chunk, OpCode};
+use crate::vm::value::{Function, UpvalueDescriptor, Value};
+
+#[derive(PartialEq)]
+enum FunctionType {
+    Script,
+    Function,
+    Method,
+    Initializer,
+}
+
+/// Represents a local variable that lives in a stack slot of the current function.
+struct Local {
+    name: String,
+    depth: usize,
+    is_captured: bool,
+}
+
+/// Represents a loop that is being compiled.
+struct Loop {
+    /// The number of locals that were declared when the loop started.
+    locals: usize,
+    /// The indices of the jumps emitted by break statements inside the loop.
+    breaks: Vec<usize>,
+    /// The indices of the jumps emitted by continue statements inside the loop.
+    continues: Vec<usize>,
+    /// The label of the loop, if there is one.
+    label: Option<String>,
+}
+
+/// Represents a protected part of a try statement that is being compiled.
+/// Jumping out of it with break, continue or return has to remove its handler and run the
+/// finally clause first.
+#[derive(Clone)]
+struct Protected {
+    /// The number of locals that were declared when the try statement started.
+    locals: usize,
+    /// The number of loops that were entered when the try statement started.
+    loops: usize,
+    /// If a handler is installed while the protected part runs.
+    handler: bool,
+    finally: Option<Vec<Stmt>>,
+}
+
+/// Represents the state of a function that is being compiled.
+struct FunctionState {
+    function: Function,
+    r#type: FunctionType,
+    locals: Vec<Local>,
+    scope_depth: usize,
+    loops: Vec<Loop>,
+    protected: Vec<Protected>,
+}
+
+impl FunctionState {
+    fn new(name: String, r#type: FunctionType) -> Self {
+        // The first slot holds the function that is being called, or the instance for methods.
+        let slot = match r#type {
+            FunctionType::Method | FunctionType::Initializer => "this",
+            _ => "",
+        };
+
+        FunctionState {
+            function: Function { name, arity: 0, chunk: Chunk::default(), upvalues: vec![] },
+            r#type,
+            locals: vec![Local { name: slot.to_string(), depth: 0, is_captured: false }],
+            scope_depth: 0,
+            loops: vec![],
+            protected: vec![],
+        }
+    }
+
+    fn resolve_local(&self, name: &str) -> Option<usize> {
+        self.locals.iter().rposition(|local| local.name == name)
+    }
+
+    fn add_upvalue(&mut self, is_local: bool, index: usize) -> usize {
+        let upvalues = &mut self.function.upvalues;
+
+        if let Some(position) = upvalues.iter().position(|u| u.is_local == is_local && u.index == index) {
+            return position;
+        }
+
+        upvalues.push(UpvalueDescriptor { is_local, index });
+        upvalues.len() - 1
+    }
+}
+
+/// Compiles the statements produced by the [`Parser`](crate::parser::Parser) into bytecode.
+/// The statements are expected to be checked by the [`Resolver`](crate::resolver::Resolver)
+/// beforehand, so compiling them can not fail.
+///
+/// Every top-level statement is compiled into its own script function. This allows the
+/// [`Vm`](crate::vm::Vm) to abort a single statement on a runtime error and continue with the
+/// next one, just like the [`Interpreter`](crate::interpreter::Interpreter) does.
+pub struct Compiler {
+    functions: Vec<FunctionState>,
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Compiler { functions: vec![] }
+    }
+
+    /// Compiles the given statements into a list of script functions.
+    pub fn compile(&mut self, statements: &[Stmt]) -> Vec<Function> {
+        statements.iter().map(|statement| {
+            self.functions.push(FunctionState::new(String::new(), FunctionType::Script));
+            statement.accept(self);
+
+            let token = Token::from("");
+            self.emit(OpCode::Null, &token);
+            self.emit(OpCode::Return, &token);
+
+            self.functions.pop().expect("script to be compiled").function
+        }).collect()
+    }
+
+    fn current(&mut self) -> &mut FunctionState {
+        self.functions.last_mut().expect("a function to be compiled")
+    }
+
+    fn chunk(&mut self) -> &mut Chunk {
+        &mut self.current().function.chunk
+    }
+
+    fn emit(&mut self, op: OpCode, token: &Token) -> usize {
+        self.chunk().write(op, token)
+    }
+
+    fn emit_constant(&mut self, value: Value, token: &Token) {
+        let index = self.chunk().add_constant(value);
+        self.emit(OpCode::Constant(index), token);
+    }
+
+    fn identifier(&mut self, name: &Token) -> usize {
+        self.chunk().add_constant(Value::from(name.lexeme.clone()))
+    }
+
+    /// Points the jump at the given index to the next instruction.
+    fn patch_jump(&mut self, index: usize) {
+        let target = self.chunk().code.len();
+
+        match &mut self.chunk().code[index] {
+            OpCode::Jump(offset) | OpCode::JumpIfFalse(offset) | OpCode::Try(offset) => *offset = target,
+            _ => unreachable!("only jumps can be patched"),
+        }
+    }
+
+    /// Returns the index of the loop targeted by a break or continue statement with the given
+    /// label. Statements without a label target the innermost loop.
+    fn target_loop(&mut self, label: &Option<Token>) -> usize {
+        let loops = &self.current().loops;
+
+        match label {
+            Some(label) => loops.iter()
+                .rposition(|r#loop| r#loop.label.as_ref() == Some(&label.lexeme))
+                .expect("label to be resolved"),
+            None => loops.len().checked_sub(1).expect("jump to be inside a loop"),
+        }
+    }
+
+    /// Emits the instructions to leave the try statements and to discard the locals declared
+    /// inside the given loop before jumping out of its body. The locals are not forgotten since
+    /// the rest of the body still refers to them by their slots.
+    fn leave_loop(&mut self, target: usize, token: &Token) {
+        let count = self.current().protected.iter().filter(|part| part.loops > target).count();
+        self.leave_protected(count, token);
+
+        let start = self.current().loops[target].locals;
+        let locals = self.current().locals.split_off(start);
+        for local in locals.iter().rev() {
+            self.pop_local(local, token);
+        }
+        self.current().locals.extend(locals);
+    }
+
+    /// Emits the instructions to leave the given number of innermost protected parts of try
+    /// statements. Their handlers are removed and their finally clauses are run in place.
+    fn leave_protected(&mut self, count: usize, token: &Token) {
+        let protected = std::mem::take(&mut self.current().protected);
+
+        for (index, part) in protected.iter().enumerate().rev().take(count) {
+            if part.handler {
+                self.emit(OpCode::EndTry, token);
+            }
+
+            let Some(finally) = &part.finally else { continue };
+
+            // The finally clause can only jump out of the try statements that surround it and it
+            // can not see the locals declared inside its own try statement.
+            self.current().protected = protected[..index].to_vec();
+            let hidden = self.current().locals[part.locals..]
+                .iter_mut()
+                .map(|local| std::mem::take(&mut local.name))
+                .collect::<Vec<String>>();
+
+            self.block(finally, token);
+
+            for (local, name) in self.current().locals[part.locals..].iter_mut().zip(hidden) {
+                local.name = name;
+            }
+        }
+
+        self.current().protected = protected;
+    }
+
+    /// Compiles the given statements as a protected part of a try statement.
+    fn protect(&mut self, statements: &[Stmt], part: Protected) {
+        self.current().protected.push(part);
+        for statement in statements {
+            self.compile_stmt(statement);
+        }
+        self.current().protected.pop();
+    }
+
+    /// Compiles the given finally clause and rethrows the error in the last local afterwards.
+    fn finally_and_rethrow(&mut self, finally: &[Stmt], token: &Token) {
+        let slot = self.current().locals.len() - 1;
+
+        self.block(finally, token);
+        self.emit(OpCode::GetLocal(slot), token);
+        self.emit(OpCode::Throw, token);
+    }
+
+    /// Compiles the given statements in a new scope.
+    fn block(&mut self, statements: &[Stmt], token: &Token) {
+        self.begin_scope();
+        for statement in statements {
+            self.compile_stmt(statement);
+        }
+        self.end_scope(token);
+    }
+
+    fn compile_expr(&mut self, expr: &Expr) {
+        expr.accept(self);
+    }
+
+    fn compile_stmt(&mut self, stmt: &Stmt) {
+        stmt.accept(self);
+    }
+
+    fn begin_scope(&mut self) {
+        self.current().scope_depth += 1;
+    }
+
+    fn end_scope(&mut self, token: &Token) {
+        let state = self.current();
+        state.scope_depth -= 1;
+
+        let depth = state.scope_depth;
+        while self.current().locals.last().is_some_and(|local| local.depth > depth) {
+            let local = self.current().locals.pop().expect("local to exist");
+            self.pop_local(&local, token);
+        }
+    }
+
+    fn pop_local(&mut self, local: &Local, token: &Token) {
+        match local.is_captured {
+            true => self.emit(OpCode::CloseUpvalue, token),
+            false => self.emit(OpCode::Pop, token),
+        };
+    }
+
+    /// Returns if variables are currently declared as globals.
+    fn is_global_scope(&mut self) -> bool {
+        self.functions.len() == 1 && self.current().scope_depth == 0
+    }
+
+    /// Declares a local variable in the current scope.
+    /// The value of the variable is expected to be on top of the stack.
+    fn add_local(&mut self, name: &Token) {
+        let depth = self.current().scope_depth;
+        self.current().locals.push(Local { name: name.lexeme.clone(), depth, is_captured: false });
+    }
+
+    /// Defines a variable with the value on top of the stack.
+    fn define_variable(&mut self, name: &Token) {
+        if self.is_global_scope() {
+            let index = self.identifier(name);
+            self.emit(OpCode::DefineGlobal(index), name);
+        } else {
+            self.add_local(name);
+        }
+    }
+
+    fn resolve_upvalue(&mut self, function: usize, name: &str) -> Option<usize> {
+        if function == 0 {
+            return None;
+        }
+
+        let enclosing = &mut self.functions[function - 1];
+        if let Some(index) = enclosing.resolve_local(name) {
+            enclosing.locals[index].is_captured = true;
+            return Some(self.functions[function].add_upvalue(true, index));
+        }
+
+        let index = self.resolve_upvalue(function - 1, name)?;
+        Some(self.functions[function].add_upvalue(false, index))
+    }
+
+    fn get_variable(&mut self, name: &Token) {
+        let op = if let Some(index) = self.current().resolve_local(&name.lexeme) {
+            OpCode::GetLocal(index)
+        } else if let Some(index) = self.resolve_upvalue(self.functions.len() - 1, &name.lexeme) {
+            OpCode::GetUpvalue(index)
+        } else {
+            OpCode::GetGlobal(self.identifier(name))
+        };
+
+        let index = self.emit(op, name);
+        if let OpCode::GetGlobal(_) = op {
+            self.similar_local(index, name);
+        }
+    }
+
+    fn set_variable(&mut self, name: &Token) {
+        let op = if let Some(index) = self.current().resolve_local(&name.lexeme) {
+            OpCode::SetLocal(index)
+        } else if let Some(index) = self.resolve_upvalue(self.functions.len() - 1, &name.lexeme) {
+            OpCode::SetUpvalue(index)
+        } else {
+            OpCode::SetGlobal(self.identifier(name))
+        };
+
+        let index = self.emit(op, name);
+        if let OpCode::SetGlobal(_) = op {
+            self.similar_local(index, name);
+        }
+    }
+
+    /// Records the local variable in scope that is most similar to the global that the given
+    /// instruction accesses. Locals are not known by name at runtime.
+    fn similar_local(&mut self, index: usize, name: &Token) {
+        let locals = self.functions.iter().flat_map(|function| &function.locals);
+        if let Some(local) = error::most_similar(&name.lexeme, locals.map(|local| local.name.as_str())) {
+            let local = local.to_string();
+            self.chunk().similar.insert(index, local);
+        }
+    }
+
+    fn emit_return(&mut self, token: &Token) {
+        if self.current().r#type == FunctionType::Initializer {
+            self.emit(OpCode::GetLocal(0), token);
+        } else {
+            self.emit(OpCode::Null, token);
+        }
+
+        self.emit(OpCode::Return, token);
+    }
+
+    /// Compiles the given function and emits the instruction that creates its closure.
+    fn function(&mut self, name: &Token, params: &[Token], body: &[Stmt], r#type: FunctionType) {
+        self.functions.push(FunctionState::new(name.lexeme.clone(), r#type));
+        self.current().function.arity = params.len();
+        self.begin_scope();
+
+        for param in params {
+            self.add_local(param);
+        }
+
+        for statement in body {
+            self.compile_stmt(statement);
+        }
+
+        // No need to end the scope, returning discards the whole frame.
+        self.emit_return(name);
+
+        let state = self.functions.pop().expect("function to be compiled");
+        let index = self.chunk().add_function(state.function);
+        self.emit(OpCode::Closure(index), name);
+    }
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExprVisitor<()> for Compiler {
+    fn visit_literal_expr(&mut self, expr: &Expr) {
+        let Expr::Literal(literal) = expr else { unreachable!() };
+
+        // Literals have no token, but they also can not cause runtime errors.
+        let token = Token::from("");
+        match literal {
+            Literal::Null => self.emit(OpCode::Null, &token),
+            Literal::Bool(true) => self.emit(OpCode::True, &token),
+            Literal::Bool(false) => self.emit(OpCode::False, &token),
+            _ => {
+                self.emit_constant(Value::from(literal.clone()), &token);
+                return;
+            }
+        };
+    }
+
+    fn visit_logical_expr(&mut self, expr: &Expr) {
+        let Expr::Logical(logical) = expr else { unreachable!() };
+        let token = &logical.operator;
+
+        self.compile_expr(&logical.left);
+
+        match logical.operator.r#type {
+            Type::And => {
+                let end = self.emit(OpCode::JumpIfFalse(0), token);
+                self.emit(OpCode::Pop, token);
+                self.compile_expr(&logical.right);
+                self.patch_jump(end);
+            },
+            Type::Or => {
+                let right = self.emit(OpCode::JumpIfFalse(0), token);
+                let end = self.emit(OpCode::Jump(0), token);
+                self.patch_jump(right);
+                self.emit(OpCode::Pop, token);
+                self.compile_expr(&logical.right);
+                self.patch_jump(end);
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    fn visit_unary_expr(&mut self, expr: &Expr) {
+        let Expr::Unary(unary) = expr else { unreachable!() };
+        self.compile_expr(&unary.expr);
+
+        let op = match unary.operator.r#type {
+            Type::Minus => OpCode::Negate,
+            Type::Bang => OpCode::Not,
+            _ => unreachable!(),
+        };
+
+        self.emit(op, &unary.operator);
+    }
+
+    fn visit_binary_expr(&mut self, expr: &Expr) {
+        let Expr::Binary(binary) = expr else { unreachable!() };
+        self.compile_expr(&binary.left);
+        self.compile_expr(&binary.right);
+
+        let op = match binary.operator.r#type {
+            Type::Plus => OpCode::Add,
+            Type::Minus => OpCode::Subtract,
+            Type::Slash => OpCode::Divide,
+            Type::Star => OpCode::Multiply,
+            Type::EqualEqual => OpCode::Equal,
+            Type::BangEqual => OpCode::NotEqual,
+            Type::Greater => OpCode::Greater,
+            Type::GreaterEqual => OpCode::GreaterEqual,
+            Type::Less => OpCode::Less,
+            Type::LessEqual => OpCode::LessEqual,
+            _ => unreachable!(),
+        };
+
+        self.emit(op, &binary.operator);
+    }
+
+    fn visit_call_expr(&mut self, expr: &Expr) {
+        let Expr::Call(call) = expr else { unreachable!() };
+        self.compile_expr(&call.callee);
+
+        for argument in &call.arguments {
+            self.compile_expr(argument);
+        }
+
+        self.emit(OpCode::Call(call.arguments.len()), &call.paren);
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &Expr) {
+        let Expr::Grouping(grouping) = expr else { unreachable!() };
+        self.compile_expr(&grouping.expr);
+    }
+
+    fn visit_variable_expr(&mut self, expr: &Expr) {
+        let Expr::Variable(variable) = expr else { unreachable!() };
+        self.get_variable(&variable.name);
+    }
+
+    fn visit_assign_expr(&mut self, expr: &Expr) {
+        let Expr::Assign(assign) = expr else { unreachable!() };
+        self.compile_expr(&assign.value);
+        self.set_variable(&assign.name);
+    }
+
+    fn visit_get_expr(&mut self, expr: &Expr) {
+        let Expr::Get(get) = expr else { unreachable!() };
+        self.compile_expr(&get.object);
+
+        let index = self.identifier(&get.name);
+        self.emit(OpCode::GetProperty(index), &get.name);
+    }
+
+    fn visit_set_expr(&mut self, expr: &Expr) {
+        let Expr::Set(set) = expr else { unreachable!() };
+        self.compile_expr(&set.object);
+        self.compile_expr(&set.value);
+
+        let index = self.identifier(&set.name);
+        self.emit(OpCode::SetProperty(index), &set.name);
+    }
+
+    fn visit_this_expr(&mut self, expr: &Expr) {
+        let Expr::This(this) = expr else { unreachable!() };
+        self.get_variable(&this.keyword);
+    }
+
+    fn visit_super_expr(&mut self, expr: &Expr) {
+        let Expr::Super(super_expr) = expr else { unreachable!() };
+
+        let this = Token { lexeme: "this".to_string(), ..super_expr.keyword.clone() };
+        self.get_variable(&this);
+        self.get_variable(&super_expr.keyword);
+
+        let index = self.identifier(&super_expr.method);
+        self.emit(OpCode::GetSuper(index), &super_expr.method);
+    }
+
+    fn visit_lambda_expr(&mut self, expr: &Expr) {
+        let Expr::Lambda(lambda) = expr else { unreachable!() };
+        self.function(&lambda.name(), &lambda.params, &lambda.body, FunctionType::Function);
+    }
+
+    fn visit_list_expr(&mut self, expr: &Expr) {
+        let Expr::List(list) = expr else { unreachable!() };
+
+        for element in &list.elements {
+            self.compile_expr(element);
+        }
+
+        self.emit(OpCode::List(list.elements.len()), &list.bracket);
+    }
+
+    fn visit_map_expr(&mut self, expr: &Expr) {
+        let Expr::Map(map) = expr else { unreachable!() };
+
+        for (key, value) in &map.entries {
+            self.compile_expr(key);
+            self.emit(OpCode::MapKey, &map.brace);
+            self.compile_expr(value);
+        }
+
+        self.emit(OpCode::Map(map.entries.len()), &map.brace);
+    }
+
+    fn visit_index_expr(&mut self, expr: &Expr) {
+        let Expr::Index(index) = expr else { unreachable!() };
+        self.compile_expr(&index.object);
+        self.compile_expr(&index.index);
+        self.emit(OpCode::Index, &index.bracket);
+    }
+
+    fn visit_index_set_expr(&mut self, expr: &Expr) {
+        let Expr::IndexSet(index_set) = expr else { unreachable!() };
+        self.compile_expr(&index_set.object);
+        self.compile_expr(&index_set.index);
+        self.compile_expr(&index_set.value);
+        self.emit(OpCode::IndexSet, &index_set.bracket);
+    }
+}
+
+impl StmtVisitor<()> for Compiler {
+    fn visit_expression_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Expression(data) = stmt else { unreachable!() };
+        self.compile_expr(&data.expr);
+        self.emit(OpCode::Pop, &Token::from(""));
+    }
+
+    fn visit_function_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Function(data) = stmt else { unreachable!() };
+
+        if self.is_global_scope() {
+            self.function(&data.name, &data.params, &data.body, FunctionType::Function);
+            self.define_variable(&data.name);
+        } else {
+            // Declare the local first so that the function can refer to itself.
+            self.add_local(&data.name);
+            self.function(&data.name, &data.params, &data.body, FunctionType::Function);
+        }
+    }
+
+    fn visit_if_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::If(data) = stmt else { unreachable!() };
+        let token = Token::from("");
+
+        self.compile_expr(&data.condition);
+        let else_jump = self.emit(OpCode::JumpIfFalse(0), &token);
+        self.emit(OpCode::Pop, &token);
+        self.compile_stmt(&data.then_branch);
+
+        let end_jump = self.emit(OpCode::Jump(0), &token);
+        self.patch_jump(else_jump);
+        self.emit(OpCode::Pop, &token);
+
+        if let Some(else_branch) = &data.else_branch {
+            self.compile_stmt(else_branch);
+        }
+
+        self.patch_jump(end_jump);
+    }
+
+    fn visit_print_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Print(data) = stmt else { unreachable!() };
+        self.compile_expr(&data.expr);
+        self.emit(OpCode::Print, &Token::from(""));
+    }
+
+    fn visit_return_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Return(data) = stmt else { unreachable!() };
+
+        match &data.value {
+            Some(value) => self.compile_expr(value),
+            None if self.current().r#type == FunctionType::Initializer => {
+                self.emit(OpCode::GetLocal(0), &data.keyword);
+            },
+            None => { self.emit(OpCode::Null, &data.keyword); },
+        }
+
+        // The returned value stays on the stack while the finally clauses run.
+        let count = self.current().protected.len();
+        if count > 0 {
+            self.add_local(&Token::from(""));
+            self.leave_protected(count, &data.keyword);
+            self.current().locals.pop();
+        }
+
+        self.emit(OpCode::Return, &data.keyword);
+    }
+
+    fn visit_break_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Break(data) = stmt else { unreachable!() };
+
+        let target = self.target_loop(&data.label);
+        self.leave_loop(target, &data.keyword);
+
+        let jump = self.emit(OpCode::Jump(0), &data.keyword);
+        self.current().loops[target].breaks.push(jump);
+    }
+
+    fn visit_import_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Import(data) = stmt else { unreachable!() };
+
+        self.emit(OpCode::Import, &data.path);
+
+        // Imports are only allowed at the top level, so their bindings are always globals.
+        match &data.alias {
+            Some(alias) => self.define_variable(alias),
+            None => {
+                self.begin_scope();
+                self.add_local(&Token::from(""));
+                let slot = self.current().locals.len() - 1;
+
+                for name in &data.names {
+                    let index = self.identifier(name);
+                    self.emit(OpCode::GetLocal(slot), name);
+                    self.emit(OpCode::GetProperty(index), name);
+                    self.emit(OpCode::DefineGlobal(index), name);
+                }
+
+                self.end_scope(&data.keyword);
+            },
+        }
+    }
+
+    fn visit_throw_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Throw(data) = stmt else { unreachable!() };
+
+        self.compile_expr(&data.value);
+        self.emit(OpCode::Throw, &data.keyword);
+    }
+
+    fn visit_try_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Try(data) = stmt else { unreachable!() };
+        let token = &data.keyword;
+
+        let part = Protected {
+            locals: self.current().locals.len(),
+            loops: self.current().loops.len(),
+            handler: true,
+            finally: data.finally.clone(),
+        };
+
+        let handler = self.emit(OpCode::Try(0), token);
+        self.begin_scope();
+        self.protect(&data.body, part.clone());
+        self.end_scope(token);
+        self.emit(OpCode::EndTry, token);
+        let mut exits = vec![self.emit(OpCode::Jump(0), token)];
+
+        // The handler starts with the caught error on top of the stack.
+        self.patch_jump(handler);
+        self.begin_scope();
+        match (&data.catch, &data.finally) {
+            (Some(catch), None) => {
+                self.add_local(&catch.name);
+                for statement in &catch.body {
+                    self.compile_stmt(statement);
+                }
+            },
+            (Some(catch), Some(finally)) => {
+                self.add_local(&catch.name);
+
+                // Errors inside the catch clause are rethrown after the finally clause.
+                let rethrow = self.emit(OpCode::Try(0), token);
+                self.protect(&catch.body, part);
+                self.emit(OpCode::EndTry, token);
+                self.end_scope(token);
+                exits.push(self.emit(OpCode::Jump(0), token));
+
+                self.patch_jump(rethrow);
+                self.begin_scope();
+                self.add_local(&Token::from(""));
+                self.add_local(&Token::from(""));
+                self.finally_and_rethrow(finally, token);
+            },
+            (None, Some(finally)) => {
+                self.add_local(&Token::from(""));
+                self.finally_and_rethrow(finally, token);
+            },
+            (None, None) => unreachable!("try statement to have a catch or a finally clause"),
+        }
+        self.end_scope(token);
+
+        for exit in exits {
+            self.patch_jump(exit);
+        }
+
+        if let Some(finally) = &data.finally {
+            self.block(finally, token);
+        }
+    }
+
+    fn visit_continue_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Continue(data) = stmt else { unreachable!() };
+
+        let target = self.target_loop(&data.label);
+        self.leave_loop(target, &data.keyword);
+
+        let jump = self.emit(OpCode::Jump(0), &data.keyword);
+        self.current().loops[target].continues.push(jump);
+    }
+
+    fn visit_var_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Var(data) = stmt else { unreachable!() };
+
+        match &data.initializer {
+            Some(initializer) => self.compile_expr(initializer),
+            None => { self.emit(OpCode::Null, &data.name); },
+        }
+
+        self.define_variable(&data.name);
+    }
+
+    fn visit_while_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::While(data) = stmt else { unreachable!() };
+        let token = Token::from("");
+
+        let locals = self.current().locals.len();
+        let label = data.label.as_ref().map(|label| label.lexeme.clone());
+        self.current().loops.push(Loop { locals, breaks: vec![], continues: vec![], label });
+
+        let start = self.chunk().code.len();
+        self.compile_expr(&data.condition);
+        let exit_jump = self.emit(OpCode::JumpIfFalse(0), &token);
+        self.emit(OpCode::Pop, &token);
+        self.compile_stmt(&data.body);
+
+        // Continue statements jump past the body to the increment.
+        let continues = std::mem::take(
+            &mut self.current().loops.last_mut().expect("loop to be compiled").continues
+        );
+        for jump in continues {
+            self.patch_jump(jump);
+        }
+
+        if let Some(increment) = &data.increment {
+            self.compile_expr(increment);
+            self.emit(OpCode::Pop, &token);
+        }
+        self.emit(OpCode::Loop(start), &token);
+
+        self.patch_jump(exit_jump);
+        self.emit(OpCode::Pop, &token);
+
+        let r#loop = self.current().loops.pop().expect("loop to be compiled");
+        for jump in r#loop.breaks {
+            self.patch_jump(jump);
+        }
+    }
+
+    fn visit_block_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Block(data) = stmt else { unreachable!() };
+
+        self.begin_scope();
+        for statement in &data.statements {
+            self.compile_stmt(statement);
+        }
+        self.end_scope(&Token::from(""));
+    }
+
+    fn visit_class_stmt(&mut self, stmt: &Stmt) {
+        let Stmt::Class(data) = stmt else { unreachable!() };
+
+        let index = self.identifier(&data.name);
+        self.emit(OpCode::Class(index), &data.name);
+        self.define_variable(&data.name);
+
+        if let Some(superclass) = &data.superclass {
+            self.compile_expr(superclass);
+
+            // The superclass is kept in a local variable that methods capture as 'super'.
+            self.begin_scope();
+            self.add_local(&Token::from("super"));
+
+            self.get_variable(&data.name);
+            self.emit(OpCode::Inherit, &data.name);
+        }
+
+        self.get_variable(&data.name);
+
+        for method in &data.methods {
+            let Stmt::Function(function) = method else { unreachable!() };
+
+            let r#type = match function.name.lexeme.as_str() {
+                "init" => FunctionType::Initializer,
+                _ => FunctionType::Method,
+            };
+
+            self.function(&function.name, &function.params, &function.body, r#type);
+            let index = self.identifier(&function.name);
+            self.emit(OpCode::Method(index), &function.name);
+        }
+
+        self.emit(OpCode::Pop, &data.name);
+
+        if data.superclass.is_some() {
+            self.end_scope(&data.name);
+        }
+    }
+}
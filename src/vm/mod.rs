@@ -0,0 +1,1004 @@
+//! A bytecode backend for the language.
+//!
+//! The [`Compiler`](compiler::Compiler) turns the statements produced by the parser into
+//! [`Chunk`](chunk::Chunk)s of [`OpCode`]s which are then executed by the [`Vm`] on a value stack.
+//! Local variables live in stack slots and are accessed by their index, variables that are
+//! captured by closures are moved into [`Upvalue`]s once they go out of scope.
+//!
+//! The VM produces the same output and the same runtime errors as the
+//! [`Interpreter`](crate::interpreter::Interpreter).
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::config::{Capability, InterpreterConfig, Limits, Meter};
+use crate::error::{self, RuntimeError, TraceFrame};
+use crate::gc::Heap;
+use crate::literal::Literal;
+use crate::list;
+use crate::map::{self, Map};
+use crate::module::{Loader, Module};
+use crate::object::Arity;
+use crate::token::Token;
+use crate::Termination;
+
+use chunk::OpCode;
+use compiler::Compiler;
+use value::{BoundMethod, Class, Closure, Function, Globals, Instance, NativeFunction, Upvalue, Value};
+
+pub mod chunk;
+pub mod compiler;
+pub mod native;
+pub mod value;
+
+/// Represents a function call that is being executed.
+struct CallFrame {
+    closure: Rc<Closure>,
+    /// The index of the next instruction.
+    ip: usize,
+    /// The index of the first stack slot that belongs to this call.
+    slots: usize,
+    /// The class that is being created, if this is a call to its initializer.
+    class: Option<Rc<RefCell<Class>>>,
+}
+
+/// Represents a call to a native function that is being executed.
+/// Natives run on the native stack, these frames only exist to show them in the traces.
+struct NativeFrame {
+    name: String,
+    /// The number of frames when the native function was called.
+    frames: usize,
+    /// The token of the call that the native function is making back into the script (if any).
+    call: Option<Token>,
+}
+
+/// Represents an installed try statement handler.
+struct Handler {
+    /// The number of frames when the handler was installed.
+    frames: usize,
+    /// The size of the stack when the handler was installed.
+    stack: usize,
+    /// The index of the first instruction of the handler.
+    ip: usize,
+}
+
+pub struct Vm<'w> {
+    frames: Vec<CallFrame>,
+    /// The native functions that are being executed, the innermost one is the last.
+    native_frames: Vec<NativeFrame>,
+    handlers: Vec<Handler>,
+    stack: Vec<Value>,
+    /// The globals of the script, modules get their own.
+    globals: Globals,
+    /// The name of the file of the script, if it is run from a file.
+    file: Option<Rc<str>>,
+    /// The native functions that every module starts with.
+    natives: HashMap<String, Value>,
+    modules: Loader<Value>,
+    config: InterpreterConfig,
+    meter: Meter,
+    /// The objects that can form reference cycles.
+    heap: Heap,
+    /// Upvalues that still point to the stack, ordered by their stack slot.
+    open_upvalues: Vec<Rc<RefCell<Upvalue>>>,
+    /// The code that the script exited the current run with (if it did).
+    exit: Option<i32>,
+    writer: Box<dyn std::io::Write + 'w>,
+}
+
+impl<'w> Vm<'w> {
+    /// Creates a new vm that grants every capability to the scripts.
+    pub fn new<W: std::io::Write>(writer: &'w mut W) -> Self {
+        Self::with_config(writer, InterpreterConfig::default())
+    }
+
+    /// Creates a new vm whose natives are installed according to the given configuration.
+    pub fn with_config<W: std::io::Write>(writer: &'w mut W, config: InterpreterConfig) -> Self {
+        let mut vm = Vm {
+            frames: vec![],
+            native_frames: vec![],
+            handlers: vec![],
+            stack: vec![],
+            globals: Rc::new(RefCell::new(HashMap::new())),
+            file: None,
+            natives: HashMap::new(),
+            modules: Loader::new(),
+            config: InterpreterConfig::default(),
+            meter: Meter::new(Limits::default()),
+            heap: Heap::new(None),
+            open_upvalues: vec![],
+            exit: None,
+            writer: Box::new(writer),
+        };
+
+        vm.set_config(config);
+        vm
+    }
+
+    /// Replaces the configuration and reinstalls the natives and the native modules according to
+    /// it.
+    pub fn set_config(&mut self, config: InterpreterConfig) {
+        for native in native::get_globals(&config) {
+            self.define_native(native);
+        }
+
+        let modules = config.stdlib.modules()
+            .iter()
+            .map(|module| module.module(&config, |function| Value::from(NativeFunction::from(function))))
+            .collect();
+
+        self.modules.set_natives(modules);
+        self.meter = Meter::new(config.limits);
+        self.heap = Heap::new(config.limits.allocations);
+        self.config = config;
+    }
+
+    /// Defines a native function in the global scope of every script.
+    pub fn define_native(&mut self, native: NativeFunction) {
+        let name = native.name.clone();
+        let native = Value::from(native);
+
+        self.natives.insert(name.clone(), native.clone());
+        self.define_global(&name, native);
+    }
+
+    /// Collects the objects that are only reachable through reference cycles and returns how
+    /// many of them were collected.
+    pub fn collect_garbage(&mut self) -> usize {
+        self.heap.collect()
+    }
+
+    /// Tracks the given value if it is stored on the heap and can be part of a cycle.
+    fn track(&mut self, value: &Value) {
+        match value {
+            Value::Closure(closure) => self.heap.track(closure),
+            Value::Class(class) => self.heap.track(class),
+            Value::Instance(instance) => self.heap.track(instance),
+            Value::BoundMethod(bound) => self.heap.track(bound),
+            Value::NativeFunction(function) if function.receiver.is_some() => self.heap.track(function),
+            Value::List(list) => self.heap.track(list),
+            Value::Map(map) => self.heap.track(map),
+            _ => (),
+        }
+    }
+
+    /// Returns if a limit of the run was exceeded or the script exited, which stops the run.
+    fn is_stopped(&self) -> bool {
+        self.meter.is_exceeded() || self.heap.is_exceeded() || self.exit.is_some()
+    }
+
+    /// Ends the current run with the given exit code.
+    /// Returns the error that unwinds the run from the given call site, it can not be caught and
+    /// it is not reported.
+    pub fn exit(&mut self, code: i32, token: Token) -> RuntimeError {
+        self.exit = Some(code);
+
+        RuntimeError {
+            token,
+            message: format!("Exited with code {code}"),
+            help: None,
+            trace: vec![],
+            value: None,
+        }
+    }
+
+    /// Returns the globals of the script.
+    pub fn globals(&self) -> &Globals {
+        &self.globals
+    }
+
+    /// Forgets the global variables that were defined by the scripts, only the natives remain.
+    pub fn reset(&mut self) {
+        self.globals = Rc::new(RefCell::new(self.natives.clone()));
+    }
+
+    /// Defines a new variable with the given name and value in the global scope.
+    pub fn define_global(&mut self, name: &str, value: Value) {
+        self.globals.borrow_mut().insert(name.to_string(), value);
+    }
+
+    /// Sets the file of the script that is being run, imports are resolved relative to it.
+    pub fn set_script(&mut self, path: &Path) {
+        self.modules.set_script(path);
+        self.file = path.file_name().map(|name| Rc::from(name.to_string_lossy()));
+    }
+
+    /// Forgets the file of the script, for code that is not run from a file.
+    pub fn clear_script(&mut self) {
+        self.modules.clear_script();
+        self.file = None;
+    }
+
+    /// Executes the given scripts in order.
+    /// A runtime error aborts the script it occurred in but the execution continues with the next
+    /// one, unless a limit of the run was exceeded. Returns how the run ended with all of its
+    /// runtime errors, a script can exit after some of the scripts failed.
+    pub fn interpret(&mut self, scripts: Vec<Function>) -> (Termination, Vec<RuntimeError>) {
+        let mut errors = vec![];
+        self.meter.restart();
+        self.heap.restart();
+        self.exit = None;
+
+        for script in scripts {
+            let closure = Rc::new(Closure {
+                function: Rc::new(script),
+                upvalues: vec![],
+                globals: Rc::clone(&self.globals),
+                file: self.file.clone(),
+            });
+            self.stack.push(Value::Closure(Rc::clone(&closure)));
+            self.frames.push(CallFrame { closure, ip: 0, slots: 0, class: None });
+
+            match self.run(0) {
+                Ok(_) => { self.pop(); },
+                Err(error) => {
+                    self.frames.clear();
+                    self.handlers.clear();
+                    self.stack.clear();
+                    self.open_upvalues.clear();
+
+                    // Exiting is not an error, the run just ends.
+                    if self.exit.is_some() {
+                        break;
+                    }
+
+                    errors.push(error);
+
+                    if self.is_stopped() {
+                        break;
+                    }
+                },
+            }
+        }
+
+        match self.exit {
+            Some(code) => (Termination::Exit(code), errors),
+            None => (Termination::Completed, errors),
+        }
+    }
+
+    /// Calls the given value with the given arguments and runs it to completion.
+    /// This allows native functions to call back into the script.
+    /// The given token is used to report errors that occur at the call site.
+    pub fn call(&mut self, callee: Value, arguments: Vec<Value>, token: &Token) -> Result<Value, RuntimeError> {
+        let depth = self.frames.len();
+        let count = arguments.len();
+
+        // The native function that calls back shows up in the traces at this call.
+        let native = self.native_frames.last_mut().filter(|native| native.frames == depth);
+        if let Some(native) = native {
+            native.call = Some(token.clone());
+        }
+
+        self.push(callee);
+        self.stack.extend(arguments);
+        let result = self.call_value(count, token).and_then(|_| match self.frames.len() > depth {
+            true => self.run(depth),
+            false => Ok(()),
+        });
+
+        if let Some(native) = self.native_frames.last_mut().filter(|native| native.frames == depth) {
+            native.call = None;
+        }
+
+        result.map(|_| self.pop())
+    }
+
+    /// Fails with a stack overflow if another call would exceed the call depth limit.
+    /// Calls are counted like in the interpreter, native calls count too and the script that is
+    /// being run does not.
+    fn check_depth(&self, token: &Token) -> Result<(), RuntimeError> {
+        if self.frames.len() - 1 + self.native_frames.len() >= self.config.limits.call_depth {
+            return Err(RuntimeError {
+                token: token.clone(),
+                message: "Stack overflow".to_string(),
+                help: None,
+                trace: vec![],
+                value: None,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn push(&mut self, value: Value) {
+        self.stack.push(value);
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("stack to be not empty")
+    }
+
+    fn peek(&self, distance: usize) -> &Value {
+        &self.stack[self.stack.len() - 1 - distance]
+    }
+
+    fn frame(&self) -> &CallFrame {
+        self.frames.last().expect("a function to be running")
+    }
+
+    /// Returns the token of the instruction that is being executed.
+    fn token(&self) -> Token {
+        let frame = self.frame();
+        frame.closure.function.chunk.tokens[frame.ip - 1].clone()
+    }
+
+    fn error(&self, message: String) -> RuntimeError {
+        RuntimeError { token: self.token(), message, help: None, trace: vec![], value: None }
+    }
+
+    /// Returns the error for the global with the given name that is not defined. The error
+    /// suggests the global or the local variable in scope with the most similar name.
+    fn undefined_variable(&self, name: &str) -> RuntimeError {
+        let frame = self.frame();
+        let globals = frame.closure.globals.borrow();
+        let local = frame.closure.function.chunk.similar.get(&(frame.ip - 1));
+        let candidates = globals.keys().chain(local).map(String::as_str);
+
+        RuntimeError {
+            help: error::did_you_mean(name, candidates),
+            ..self.error(format!("Undefined variable '{name}'"))
+        }
+    }
+
+    /// Returns the calls that are being executed, the innermost one first. Each call is at the
+    /// instruction that it is executing, native functions are at the call they are making back
+    /// into the script or have no location if they are the innermost call.
+    fn trace(&self) -> Vec<TraceFrame> {
+        let mut trace = vec![];
+        let mut natives = self.native_frames.iter().rev().peekable();
+
+        for (index, frame) in self.frames.iter().enumerate().rev() {
+            // Natives that were called by this frame are above it.
+            while let Some(native) = natives.next_if(|native| native.frames == index + 1) {
+                trace.push(TraceFrame {
+                    name: native.name.clone(),
+                    file: native.call.as_ref().and(frame.closure.file.as_ref()).map(|file| file.to_string()),
+                    location: native.call.as_ref().map(|call| call.location),
+                });
+            }
+
+            let name = match (&frame.class, frame.closure.function.name.as_str()) {
+                (Some(class), _) => class.borrow().name.clone(),
+                (None, "") => "<script>".to_string(),
+                (None, name) => name.to_string(),
+            };
+
+            trace.push(TraceFrame {
+                name,
+                file: frame.closure.file.as_ref().map(|file| file.to_string()),
+                location: Some(frame.closure.function.chunk.tokens[frame.ip - 1].location),
+            });
+        }
+
+        trace
+    }
+
+    fn constant(&self, index: usize) -> &Value {
+        &self.frame().closure.function.chunk.constants[index]
+    }
+
+    fn identifier(&self, index: usize) -> String {
+        match self.constant(index) {
+            Value::Literal(Literal::String(name)) => name.clone(),
+            _ => unreachable!("identifiers are stored as strings"),
+        }
+    }
+
+    /// Calls the value below the given number of arguments on the stack.
+    /// Functions that are defined in the script get a new frame, every other value is called
+    /// immediately and replaced by its result.
+    fn call_value(&mut self, count: usize, token: &Token) -> Result<(), RuntimeError> {
+        let callee = self.peek(count).clone();
+        let callee_slot = self.stack.len() - count - 1;
+
+        match callee {
+            Value::Closure(closure) => self.call_closure(closure, count, token, None),
+            Value::BoundMethod(bound) => {
+                self.stack[callee_slot] = bound.receiver.clone();
+                self.call_closure(Rc::clone(&bound.method), count, token, None)
+            },
+            Value::Class(class) => {
+                let instance = Instance { class: Rc::clone(&class), fields: HashMap::new() };
+                let instance = Value::Instance(Rc::new(RefCell::new(instance)));
+                self.track(&instance);
+                self.stack[callee_slot] = instance;
+
+                let initializer = class.borrow().methods.get("init").cloned();
+                match initializer {
+                    Some(initializer) => self.call_closure(initializer, count, token, Some(class)),
+                    None if count != 0 => Err(RuntimeError {
+                        token: token.clone(),
+                        message: format!("Expected 0 arguments but got {count}"),
+                        help: None,
+                        trace: vec![],
+                        value: None,
+                    }),
+                    None => self.check_depth(token),
+                }
+            },
+            Value::NativeFunction(function) => {
+                if !function.arity.accepts(count) {
+                    return Err(RuntimeError {
+                        token: token.clone(),
+                        message: format!("Expected {} but got {}", function.arity, count),
+                        help: None,
+                        trace: vec![],
+                        value: None,
+                    });
+                }
+
+                self.check_depth(token)?;
+
+                let mut arguments = self.stack.split_off(callee_slot + 1);
+                self.pop();
+
+                if let Some(receiver) = &function.receiver {
+                    arguments.insert(0, receiver.clone());
+                }
+
+                self.native_frames.push(NativeFrame { name: function.name.clone(), frames: self.frames.len(), call: None });
+                let result = (function.function)(self, arguments).map_err(|mut error| {
+                    // Native functions have no location in the source code,
+                    // report their errors at the call site instead.
+                    error.token = token.clone();
+                    if error.trace.is_empty() {
+                        error.trace = self.trace();
+                    }
+                    error
+                });
+                self.native_frames.pop();
+                let result = result?;
+
+                // Natives create lists and maps without access to the heap.
+                self.track(&result);
+                self.push(result);
+                Ok(())
+            },
+            // Calling null evaluates to null, just like in the interpreter.
+            Value::Literal(Literal::Null) => {
+                self.stack.truncate(callee_slot);
+                self.push(Value::from(Literal::Null));
+                Ok(())
+            },
+            _ => Err(RuntimeError {
+                token: token.clone(),
+                message: "Can only call functions and classes".to_string(),
+                help: None,
+                trace: vec![],
+                value: None,
+            }),
+        }
+    }
+
+    fn call_closure(
+        &mut self,
+        closure: Rc<Closure>,
+        count: usize,
+        token: &Token,
+        class: Option<Rc<RefCell<Class>>>,
+    ) -> Result<(), RuntimeError> {
+        if closure.function.arity != count {
+            return Err(RuntimeError {
+                token: token.clone(),
+                message: format!("Expected {} but got {}", Arity::Fixed(closure.function.arity), count),
+                help: None,
+                trace: vec![],
+                value: None,
+            });
+        }
+
+        self.check_depth(token)?;
+
+        let slots = self.stack.len() - count - 1;
+        self.frames.push(CallFrame { closure, ip: 0, slots, class });
+
+        Ok(())
+    }
+
+    /// Returns the upvalue that points to the given stack slot, creating it if needed.
+    fn capture_upvalue(&mut self, slot: usize) -> Rc<RefCell<Upvalue>> {
+        let existing = self.open_upvalues.iter().find(|upvalue| {
+            matches!(*upvalue.borrow(), Upvalue::Open(index) if index == slot)
+        });
+
+        if let Some(upvalue) = existing {
+            return Rc::clone(upvalue);
+        }
+
+        let upvalue = Rc::new(RefCell::new(Upvalue::Open(slot)));
+        self.heap.track(&upvalue);
+        self.open_upvalues.push(Rc::clone(&upvalue));
+        upvalue
+    }
+
+    /// Closes every open upvalue that points to the given stack slot or above.
+    fn close_upvalues(&mut self, from: usize) {
+        let stack = &self.stack;
+
+        self.open_upvalues.retain(|upvalue| {
+            let Upvalue::Open(slot) = *upvalue.borrow() else { unreachable!() };
+
+            if slot < from {
+                return true;
+            }
+
+            *upvalue.borrow_mut() = Upvalue::Closed(stack[slot].clone());
+            false
+        });
+    }
+
+    fn binary(&mut self, op: OpCode) -> Result<(), RuntimeError> {
+        let right = self.pop();
+        let left = self.pop();
+
+        let result = match (op, &left, &right) {
+            (OpCode::Equal, _, _) => Some(Value::from(left == right)),
+            (OpCode::NotEqual, _, _) => Some(Value::from(left != right)),
+            (_, Value::Literal(l), Value::Literal(r)) => match op {
+                OpCode::Add => l.clone() + r.clone(),
+                OpCode::Subtract => l.clone() - r.clone(),
+                OpCode::Multiply => l.clone() * r.clone(),
+                OpCode::Divide => l.clone() / r.clone(),
+                OpCode::Greater => l.partial_cmp(r).map(|x| Literal::Bool(x == Ordering::Greater)),
+                OpCode::GreaterEqual => l.partial_cmp(r).map(|x| Literal::Bool(x != Ordering::Less)),
+                OpCode::Less => l.partial_cmp(r).map(|x| Literal::Bool(x == Ordering::Less)),
+                OpCode::LessEqual => l.partial_cmp(r).map(|x| Literal::Bool(x != Ordering::Greater)),
+                _ => unreachable!(),
+            }.map(Value::from),
+            _ => None,
+        };
+
+        match result {
+            Some(result) => {
+                self.push(result);
+                Ok(())
+            },
+            None => Err(self.error(format!(
+                "Binary operation '{}' is not supported between {} type and {} type",
+                self.token().lexeme,
+                left.type_str(),
+                right.type_str()
+            ))),
+        }
+    }
+
+    fn unary(&mut self, op: OpCode) -> Result<(), RuntimeError> {
+        let value = self.pop();
+
+        let result = match (op, &value) {
+            (OpCode::Negate, Value::Literal(literal)) => -literal.clone(),
+            (OpCode::Not, Value::Literal(literal)) => !literal.clone(),
+            _ => None,
+        };
+
+        match result {
+            Some(result) => {
+                self.push(Value::from(result));
+                Ok(())
+            },
+            None => Err(self.error(format!(
+                "Unary operation '{}' is not supported for {} type",
+                self.token().lexeme,
+                value.type_str()
+            ))),
+        }
+    }
+
+    fn get_property(&mut self, name: String) -> Result<(), RuntimeError> {
+        let object = self.pop();
+
+        let value = match &object {
+            Value::Instance(instance) => {
+                let instance = instance.borrow();
+
+                if let Some(field) = instance.fields.get(&name) {
+                    field.clone()
+                } else if let Some(method) = instance.class.borrow().methods.get(&name) {
+                    let method = Value::BoundMethod(Rc::new(BoundMethod {
+                        receiver: object.clone(),
+                        method: Rc::clone(method),
+                    }));
+                    self.track(&method);
+                    method
+                } else {
+                    let class = instance.class.borrow();
+                    let candidates = instance.fields.keys().chain(class.methods.keys()).map(String::as_str);
+
+                    return Err(RuntimeError {
+                        help: error::did_you_mean(&name, candidates),
+                        ..self.error(format!("Undefined property '{name}'"))
+                    });
+                }
+            },
+            Value::List(_) => {
+                let method = list::get_method(&object, &self.token())?;
+                self.track(&method);
+                method
+            },
+            Value::Map(_) => {
+                let method = map::get_method(&object, &self.token())?;
+                self.track(&method);
+                method
+            },
+            Value::Error(error) => error.get(&self.token())?,
+            Value::Module(module) => module.get(&self.token())?,
+            _ => return Err(self.error("Only instances have properties".to_string())),
+        };
+
+        self.push(value);
+        Ok(())
+    }
+
+    fn index(&mut self) -> Result<(), RuntimeError> {
+        let index = self.pop();
+        let object = self.pop();
+
+        let value = match object {
+            Value::List(list) => {
+                let list = list.borrow();
+                list[list::index(&list, &index, &self.token())?].clone()
+            },
+            Value::Map(map) => {
+                let key = map::key(&index, &self.token())?;
+                let value = map.borrow().get(&key).cloned();
+                value.ok_or_else(|| self.error(format!("Undefined key '{key}'")))?
+            },
+            _ => return Err(self.error(format!("Cannot index into {} type", object.type_str()))),
+        };
+
+        self.push(value);
+        Ok(())
+    }
+
+    fn index_set(&mut self) -> Result<(), RuntimeError> {
+        let value = self.pop();
+        let index = self.pop();
+        let object = self.pop();
+
+        match object {
+            Value::List(list) => {
+                let mut list = list.borrow_mut();
+                let position = list::index(&list, &index, &self.token())?;
+                list[position] = value.clone();
+            },
+            Value::Map(map) => {
+                let key = map::key(&index, &self.token())?;
+                map.borrow_mut().insert(key, value.clone());
+            },
+            _ => return Err(self.error(format!("Cannot index into {} type", object.type_str()))),
+        };
+
+        self.push(value);
+        Ok(())
+    }
+
+    /// Returns the namespace object of the module that the given path refers to.
+    /// The module is executed the first time it is imported, in its own global scope.
+    fn import(&mut self, path: &Token) -> Result<Rc<Module<Value>>, RuntimeError> {
+        if let Some(module) = self.modules.native(path) {
+            return Ok(module);
+        }
+
+        self.config.check(Capability::Filesystem, &format!("import '{}'", path.lexeme))
+            .map_err(|message| RuntimeError { token: path.clone(), message, help: None, trace: vec![], value: None })?;
+
+        let file = self.modules.resolve(path)?;
+        if let Some(module) = self.modules.get(&file) {
+            return Ok(module);
+        }
+
+        let source = self.modules.load(&file, path)?;
+        let globals = Rc::new(RefCell::new(self.natives.clone()));
+        let module_file: Option<Rc<str>> = file.file_name().map(|name| Rc::from(name.to_string_lossy()));
+
+        let result = Compiler::new().compile(&source.statements).into_iter().try_for_each(|script| {
+            let closure = Rc::new(Closure {
+                function: Rc::new(script),
+                upvalues: vec![],
+                globals: Rc::clone(&globals),
+                file: module_file.clone(),
+            });
+            self.push(Value::Closure(Rc::clone(&closure)));
+            self.frames.push(CallFrame { closure, ip: 0, slots: self.stack.len() - 1, class: None });
+
+            self.run(self.frames.len() - 1)?;
+            self.pop();
+            Ok(())
+        });
+
+        let module = result.is_ok().then(|| {
+            Rc::new(Module::new(source.name.clone(), globals, source.exports()))
+        });
+        self.modules.finish(module.clone());
+
+        result.map(|_| module.expect("module to be executed"))
+    }
+
+    /// Executes instructions until the frame count drops back to the given depth.
+    /// Runtime errors are caught by the handlers that were installed above the given depth.
+    fn run(&mut self, depth: usize) -> Result<(), RuntimeError> {
+        loop {
+            match self.execute(depth) {
+                Ok(()) => return Ok(()),
+                Err(error) => self.catch(error, depth)?,
+            }
+        }
+    }
+
+    /// Unwinds the stack to the most recently installed handler and jumps to it with the given
+    /// error on top of the stack. The error is returned if there is no handler above the given
+    /// depth.
+    fn catch(&mut self, mut error: RuntimeError, depth: usize) -> Result<(), RuntimeError> {
+        // Exceeding the instruction budget, the timeout or the allocation cap stops the run, it
+        // can not be caught. The same goes for exiting.
+        let handler = self.handlers.last().filter(|handler| handler.frames > depth && !self.is_stopped());
+
+        // Errors that leave the call they occurred in get the trace of the calls that led to
+        // them, just like in the interpreter.
+        if error.trace.is_empty() && handler.is_none_or(|handler| handler.frames < self.frames.len()) {
+            error.trace = self.trace();
+            error.trace[0].location = Some(error.token.location);
+        }
+
+        if handler.is_none() {
+            return Err(error);
+        }
+
+        let handler = self.handlers.pop().expect("handler to exist");
+        self.frames.truncate(handler.frames);
+        self.close_upvalues(handler.stack);
+        self.stack.truncate(handler.stack);
+        self.push(Value::Error(Rc::new(error)));
+        self.frames.last_mut().expect("a function to be running").ip = handler.ip;
+
+        Ok(())
+    }
+
+    /// Executes instructions until the frame count drops back to the given depth.
+    fn execute(&mut self, depth: usize) -> Result<(), RuntimeError> {
+        loop {
+            let frame = self.frames.last_mut().expect("a function to be running");
+            let op = frame.closure.function.chunk.code[frame.ip];
+            frame.ip += 1;
+
+            if let Err(message) = self.meter.tick().and_then(|_| self.heap.check()) {
+                return Err(self.error(message));
+            }
+
+            match op {
+                OpCode::Constant(index) => {
+                    let constant = self.constant(index).clone();
+                    self.push(constant);
+                },
+                OpCode::Null => self.push(Value::from(Literal::Null)),
+                OpCode::True => self.push(Value::from(true)),
+                OpCode::False => self.push(Value::from(false)),
+                OpCode::Pop => { self.pop(); },
+
+                OpCode::GetLocal(slot) => {
+                    let value = self.stack[self.frame().slots + slot].clone();
+                    self.push(value);
+                },
+                OpCode::SetLocal(slot) => {
+                    let index = self.frame().slots + slot;
+                    self.stack[index] = self.peek(0).clone();
+                },
+                OpCode::GetGlobal(index) => {
+                    let name = self.identifier(index);
+                    let value = self.frame().closure.globals.borrow().get(&name).cloned();
+                    match value {
+                        Some(value) => self.push(value),
+                        None => return Err(self.undefined_variable(&name)),
+                    }
+                },
+                OpCode::DefineGlobal(index) => {
+                    let name = self.identifier(index);
+                    let value = self.pop();
+                    self.frame().closure.globals.borrow_mut().insert(name, value);
+                },
+                OpCode::SetGlobal(index) => {
+                    let name = self.identifier(index);
+                    let value = self.peek(0).clone();
+                    let mut globals = self.frame().closure.globals.borrow_mut();
+                    match globals.get_mut(&name) {
+                        Some(variable) => *variable = value,
+                        None => {
+                            drop(globals);
+                            return Err(self.undefined_variable(&name));
+                        },
+                    }
+                },
+                OpCode::GetUpvalue(index) => {
+                    let upvalue = Rc::clone(&self.frame().closure.upvalues[index]);
+                    let value = match &*upvalue.borrow() {
+                        Upvalue::Open(slot) => self.stack[*slot].clone(),
+                        Upvalue::Closed(value) => value.clone(),
+                    };
+                    self.push(value);
+                },
+                OpCode::SetUpvalue(index) => {
+                    let upvalue = Rc::clone(&self.frame().closure.upvalues[index]);
+                    let value = self.peek(0).clone();
+                    match &mut *upvalue.borrow_mut() {
+                        Upvalue::Open(slot) => self.stack[*slot] = value,
+                        Upvalue::Closed(closed) => *closed = value,
+                    };
+                },
+                OpCode::GetProperty(index) => {
+                    let name = self.identifier(index);
+                    self.get_property(name)?;
+                },
+                OpCode::SetProperty(index) => {
+                    let value = self.pop();
+                    let object = self.pop();
+
+                    let Value::Instance(instance) = object else {
+                        return Err(self.error("Only instances can have fields".to_string()));
+                    };
+
+                    instance.borrow_mut().fields.insert(self.identifier(index), value.clone());
+                    self.push(value);
+                },
+                OpCode::GetSuper(index) => {
+                    let name = self.identifier(index);
+                    let Value::Class(superclass) = self.pop() else { unreachable!() };
+                    let receiver = self.pop();
+
+                    let method = superclass.borrow().methods.get(&name).cloned();
+                    match method {
+                        Some(method) => {
+                            let method = Value::BoundMethod(Rc::new(BoundMethod { receiver, method }));
+                            self.track(&method);
+                            self.push(method);
+                        },
+                        None => {
+                            let superclass = superclass.borrow();
+                            return Err(RuntimeError {
+                                help: error::did_you_mean(&name, superclass.methods.keys().map(String::as_str)),
+                                ..self.error(format!("Undefined property '{name}'"))
+                            });
+                        },
+                    }
+                },
+
+                OpCode::Equal | OpCode::NotEqual
+                | OpCode::Greater | OpCode::GreaterEqual
+                | OpCode::Less | OpCode::LessEqual
+                | OpCode::Add | OpCode::Subtract
+                | OpCode::Multiply | OpCode::Divide => self.binary(op)?,
+                OpCode::Not | OpCode::Negate => self.unary(op)?,
+
+                OpCode::Print => {
+                    let value = self.pop();
+                    writeln!(self.writer, "{value}").expect("writer to not fail on write");
+                },
+                OpCode::Jump(target) | OpCode::Loop(target) => {
+                    self.frames.last_mut().expect("a function to be running").ip = target;
+                },
+                OpCode::JumpIfFalse(target) => {
+                    if !self.peek(0).is_truthy() {
+                        self.frames.last_mut().expect("a function to be running").ip = target;
+                    }
+                },
+                OpCode::Call(count) => {
+                    let token = self.token();
+                    self.call_value(count, &token)?;
+                },
+                OpCode::Closure(index) => {
+                    let frame = self.frame();
+                    let function = Rc::clone(&frame.closure.function.chunk.functions[index]);
+                    let enclosing = Rc::clone(&frame.closure);
+                    let slots = frame.slots;
+
+                    let upvalues = function.upvalues
+                        .iter()
+                        .map(|upvalue| match upvalue.is_local {
+                            true => self.capture_upvalue(slots + upvalue.index),
+                            false => Rc::clone(&enclosing.upvalues[upvalue.index]),
+                        })
+                        .collect();
+
+                    let globals = Rc::clone(&enclosing.globals);
+                    let file = enclosing.file.clone();
+                    let closure = Value::Closure(Rc::new(Closure { function, upvalues, globals, file }));
+                    self.track(&closure);
+                    self.push(closure);
+                },
+                OpCode::CloseUpvalue => {
+                    self.close_upvalues(self.stack.len() - 1);
+                    self.pop();
+                },
+                OpCode::Return => {
+                    let result = self.pop();
+                    let frame = self.frames.pop().expect("a function to be running");
+
+                    self.close_upvalues(frame.slots);
+                    self.stack.truncate(frame.slots);
+                    self.push(result);
+
+                    // Handlers that were installed by the returning function are discarded.
+                    let frames = self.frames.len();
+                    while self.handlers.last().is_some_and(|handler| handler.frames > frames) {
+                        self.handlers.pop();
+                    }
+
+                    if self.frames.len() == depth {
+                        return Ok(());
+                    }
+                },
+
+                OpCode::Try(ip) => {
+                    self.handlers.push(Handler {
+                        frames: self.frames.len(),
+                        stack: self.stack.len(),
+                        ip,
+                    });
+                },
+                OpCode::EndTry => { self.handlers.pop(); },
+                OpCode::Import => {
+                    let module = self.import(&self.token())?;
+                    self.push(Value::Module(module));
+                },
+                OpCode::Throw => {
+                    // Rethrowing a caught error keeps its original message and location.
+                    return Err(match self.pop() {
+                        Value::Error(error) => (*error).clone(),
+                        value => RuntimeError {
+                            value: Some(Rc::new(value.clone())),
+                            ..self.error(value.to_string())
+                        },
+                    });
+                },
+
+                OpCode::Class(index) => {
+                    let class = Class { name: self.identifier(index), methods: HashMap::new() };
+                    let class = Value::Class(Rc::new(RefCell::new(class)));
+                    self.track(&class);
+                    self.push(class);
+                },
+                OpCode::Inherit => {
+                    let Value::Class(subclass) = self.pop() else { unreachable!() };
+                    let Value::Class(superclass) = self.peek(0) else {
+                        return Err(self.error("Superclass must be a class".to_string()));
+                    };
+
+                    let methods = superclass.borrow().methods.clone();
+                    subclass.borrow_mut().methods.extend(methods);
+                },
+                OpCode::Method(index) => {
+                    let Value::Closure(method) = self.pop() else { unreachable!() };
+                    let Value::Class(class) = self.peek(0) else { unreachable!() };
+                    class.borrow_mut().methods.insert(self.identifier(index), method);
+                },
+
+                OpCode::List(count) => {
+                    let elements = self.stack.split_off(self.stack.len() - count);
+                    let list = Value::from(elements);
+                    self.track(&list);
+                    self.push(list);
+                },
+                OpCode::Map(count) => {
+                    let entries = self.stack.split_off(self.stack.len() - count * 2);
+
+                    let mut map = Map::default();
+                    for entry in entries.chunks(2) {
+                        let key = map::key(&entry[0], &self.token())?;
+                        map.insert(key, entry[1].clone());
+                    }
+
+                    let map = Value::from(map);
+                    self.track(&map);
+                    self.push(map);
+                },
+                OpCode::MapKey => {
+                    map::key(self.peek(0), &self.token())?;
+                },
+                OpCode::Index => self.index()?,
+                OpCode::IndexSet => self.index_set()?,
+            }
+        }
+    }
+}
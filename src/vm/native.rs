@@ -0,0 +1,31 @@
+
+use crate::config::InterpreterConfig;
+use crate::error::RuntimeError;
+use crate::stdlib::{self, StdError, StdFunction};
+use crate::token::Token;
+use crate::vm::value::{NativeFunction, Value};
+
+/// Returns the native functions that are defined in the global scope of the [`Vm`](crate::vm::Vm).
+/// These are the same functions that the interpreter defines in
+/// [`NativeFunction::get_globals`](crate::function::NativeFunction::get_globals).
+pub fn get_globals(config: &InterpreterConfig) -> Vec<NativeFunction> {
+    stdlib::globals()
+        .into_iter()
+        .map(|function| NativeFunction::from(function.gated(config)))
+        .collect()
+}
+
+impl From<StdFunction<Value>> for NativeFunction {
+    /// Wraps a function of the [`stdlib`](crate::stdlib), its failures are turned into runtime
+    /// errors.
+    fn from(value: StdFunction<Value>) -> Self {
+        let StdFunction { name, arity, function, .. } = value;
+
+        NativeFunction::new(name, arity, move |vm, arguments| {
+            function(arguments).map_err(|error| match error {
+                StdError::Message(message) => RuntimeError { token: Token::from(name), message, help: None, trace: vec![], value: None },
+                StdError::Exit(code) => vm.exit(code, Token::from(name)),
+            })
+        })
+    }
+}
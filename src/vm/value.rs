@@ -0,0 +1,375 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::mem;
+use std::rc::Rc;
+
+use crate::error::RuntimeError;
+use crate::gc::{Collect, Trace, Tracer};
+use crate::literal::Literal;
+use crate::map::Map;
+use crate::module::Module;
+use crate::object::{fmt_once, Arity};
+use crate::vm::Vm;
+use crate::vm::chunk::Chunk;
+
+/// Represents a value on the stack of the [`Vm`].
+/// This is the counterpart of [`Object`](crate::object::Object) for the bytecode backend.
+/// Literals, lists and maps behave the same in both backends, but functions and classes are
+/// represented by their compiled form.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Literal(Literal),
+    Closure(Rc<Closure>),
+    NativeFunction(Rc<NativeFunction>),
+    Class(Rc<RefCell<Class>>),
+    Instance(Rc<RefCell<Instance>>),
+    BoundMethod(Rc<BoundMethod>),
+    List(Rc<RefCell<Vec<Value>>>),
+    Map(Rc<RefCell<Map<Value>>>),
+    Error(Rc<RuntimeError>),
+    Module(Rc<Module<Value>>),
+}
+
+impl Value {
+    /// Returns the value as a boolean if it is a literal.
+    /// Follows the same rules as [`Literal::as_bool`].
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Literal(literal) => Some(literal.as_bool()),
+            _ => None,
+        }
+    }
+
+    /// Returns if the value is considered true in a condition.
+    /// Only literals can be true, every other value is considered false.
+    pub fn is_truthy(&self) -> bool {
+        self.as_bool().is_some_and(|x| x)
+    }
+
+    pub fn type_str(&self) -> &str {
+        match self {
+            Value::Literal(literal) => literal.type_str(),
+            Value::Closure(_) => "function",
+            Value::NativeFunction(_) => "native function",
+            Value::Class(_) => "class",
+            Value::Instance(_) => "instance",
+            Value::BoundMethod(_) => "function",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+            Value::Error(_) => "error",
+            Value::Module(_) => "module",
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Literal(left), Value::Literal(right)) => left == right,
+            (Value::Closure(left), Value::Closure(right)) => Rc::ptr_eq(&left.function, &right.function),
+            (Value::NativeFunction(left), Value::NativeFunction(right)) => left.name == right.name,
+            (Value::Class(left), Value::Class(right)) => left.borrow().name == right.borrow().name,
+            (Value::BoundMethod(left), Value::BoundMethod(right)) => {
+                Rc::ptr_eq(&left.method.function, &right.method.function)
+            },
+            (Value::List(left), Value::List(right)) => Rc::ptr_eq(left, right),
+            (Value::Map(left), Value::Map(right)) => Rc::ptr_eq(left, right),
+            (Value::Error(left), Value::Error(right)) => Rc::ptr_eq(left, right),
+            (Value::Module(left), Value::Module(right)) => Rc::ptr_eq(left, right),
+            // Currently there is no support for comparing instances
+            _ => false,
+        }
+    }
+}
+
+impl From<Literal> for Value {
+    fn from(literal: Literal) -> Self {
+        Value::Literal(literal)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(number: f64) -> Self {
+        Value::Literal(Literal::Number(number))
+    }
+}
+
+impl From<bool> for Value {
+    fn from(boolean: bool) -> Self {
+        Value::Literal(Literal::Bool(boolean))
+    }
+}
+
+impl From<String> for Value {
+    fn from(string: String) -> Self {
+        Value::Literal(Literal::String(string))
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(value: Vec<Value>) -> Self {
+        Value::List(Rc::new(RefCell::new(value)))
+    }
+}
+
+impl From<Map<Value>> for Value {
+    fn from(value: Map<Value>) -> Self {
+        Value::Map(Rc::new(RefCell::new(value)))
+    }
+}
+
+impl From<NativeFunction> for Value {
+    fn from(value: NativeFunction) -> Self {
+        Value::NativeFunction(Rc::new(value))
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Literal(literal) => write!(f, "{literal}"),
+            Value::Closure(closure) => write!(f, "{}", closure.function),
+            Value::NativeFunction(function) => write!(f, "<native function {}>", function.name),
+            Value::Class(class) => write!(f, "<class {}>", class.borrow().name),
+            Value::Instance(instance) => write!(f, "<instance {}>", instance.borrow().class.borrow().name),
+            Value::BoundMethod(bound) => write!(f, "{}", bound.method.function),
+            Value::List(list) => fmt_once(Rc::as_ptr(list), f, "[...]", |f| {
+                let elements = list.borrow()
+                    .iter()
+                    .map(|element| element.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "[{elements}]")
+            }),
+            Value::Map(map) => write!(f, "{}", map.borrow()),
+            Value::Error(error) => write!(f, "<error {}>", error.message),
+            Value::Module(module) => write!(f, "{module}"),
+        }
+    }
+}
+
+/// Describes where a closure captures one of its upvalues from when it is created.
+#[derive(Debug, Clone, Copy)]
+pub struct UpvalueDescriptor {
+    /// If true, the upvalue is a local variable of the enclosing function.
+    /// Otherwise, it is one of the upvalues of the enclosing function.
+    pub is_local: bool,
+    /// The stack slot of the local variable or the index of the upvalue.
+    pub index: usize,
+}
+
+/// Represents a compiled function.
+/// This is the result of compiling a [`function`](crate::stmt::Stmt::Function) statement or a
+/// top-level script. It is turned into a [`Closure`] at runtime.
+#[derive(Debug)]
+pub struct Function {
+    /// The function's name (empty for scripts).
+    pub name: String,
+    /// The number of parameters the function takes.
+    pub arity: usize,
+    /// The function's compiled body.
+    pub chunk: Chunk,
+    /// The variables that the function captures from its enclosing functions.
+    pub upvalues: Vec<UpvalueDescriptor>,
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.name.is_empty() {
+            write!(f, "<script>")
+        } else {
+            write!(f, "<function {}>", self.name)
+        }
+    }
+}
+
+/// Represents a variable captured by a closure.
+/// An upvalue is open while the variable still lives on the stack and closed once the variable
+/// goes out of scope, at which point the upvalue takes ownership of the value.
+#[derive(Debug)]
+pub enum Upvalue {
+    Open(usize),
+    Closed(Value),
+}
+
+/// The global variables of a file. Every module has its own globals which are shared with the
+/// closures created in it.
+pub type Globals = Rc<RefCell<HashMap<String, Value>>>;
+
+/// Represents a function together with the variables it captured.
+#[derive(Debug)]
+pub struct Closure {
+    pub function: Rc<Function>,
+    pub upvalues: Vec<Rc<RefCell<Upvalue>>>,
+    pub globals: Globals,
+    /// The name of the file that the function was declared in, if it was run from a file.
+    pub file: Option<Rc<str>>,
+}
+
+/// The signature of the Rust closure that implements a [`NativeFunction`].
+pub type NativeFn = dyn Fn(&mut Vm, Vec<Value>) -> Result<Value, RuntimeError>;
+
+/// Represents a function that is implemented in Rust.
+/// See [`NativeFunction`](crate::function::NativeFunction) for the interpreter's counterpart.
+pub struct NativeFunction {
+    pub name: String,
+    pub arity: Arity,
+    pub function: Rc<NativeFn>,
+    /// The value that the function is bound to (if any), it is passed as the first argument.
+    /// Bound functions are tracked by the heap like [`BoundMethod`]s.
+    pub receiver: Option<Value>,
+}
+
+impl NativeFunction {
+    /// Creates a new native function with the given name, arity and implementation.
+    pub fn new<F>(name: &str, arity: Arity, function: F) -> Self
+    where
+        F: Fn(&mut Vm, Vec<Value>) -> Result<Value, RuntimeError> + 'static
+    {
+        NativeFunction { name: name.to_string(), arity, function: Rc::new(function), receiver: None }
+    }
+
+    /// Binds the function to the given receiver, which is passed as the first argument of every
+    /// call. The arity does not include the receiver.
+    pub fn bind(mut self, receiver: Value) -> Self {
+        self.receiver = Some(receiver);
+        self
+    }
+}
+
+impl Debug for NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native function {}>", self.name)
+    }
+}
+
+/// Represents a class.
+/// Methods of the superclass are copied into the subclass when it is created, so there is no
+/// need to walk the inheritance chain at runtime.
+#[derive(Debug)]
+pub struct Class {
+    pub name: String,
+    pub methods: HashMap<String, Rc<Closure>>,
+}
+
+/// Represents an instance of a class.
+#[derive(Debug)]
+pub struct Instance {
+    pub class: Rc<RefCell<Class>>,
+    pub fields: HashMap<String, Value>,
+}
+
+/// Represents a method that is bound to the instance it was accessed on.
+#[derive(Debug)]
+pub struct BoundMethod {
+    pub receiver: Value,
+    pub method: Rc<Closure>,
+}
+
+impl Trace for Value {
+    fn trace(&self, tracer: &mut Tracer) {
+        match self {
+            Value::Closure(closure) => tracer.edge(closure),
+            Value::Class(class) => tracer.edge(class),
+            Value::Instance(instance) => tracer.edge(instance),
+            Value::BoundMethod(bound) => tracer.edge(bound),
+            // Only bound natives are tracked, the edges to the others are ignored.
+            Value::NativeFunction(function) => tracer.edge(function),
+            Value::List(list) => tracer.edge(list),
+            Value::Map(map) => tracer.edge(map),
+            // Modules are kept alive by the loader, errors can not form cycles.
+            Value::Literal(_) | Value::Error(_) | Value::Module(_) => (),
+        }
+    }
+}
+
+impl Trace for Closure {
+    fn trace(&self, tracer: &mut Tracer) {
+        self.upvalues.iter().for_each(|upvalue| tracer.edge(upvalue));
+    }
+}
+
+impl Collect for Closure {
+    fn clear(&self) {
+        // Closures can not be modified, their cycles go through upvalues which are cleared.
+    }
+}
+
+impl Trace for RefCell<Upvalue> {
+    fn trace(&self, tracer: &mut Tracer) {
+        match self.try_borrow() {
+            Ok(upvalue) => if let Upvalue::Closed(value) = &*upvalue {
+                value.trace(tracer);
+            },
+            Err(_) => tracer.pin(),
+        }
+    }
+}
+
+impl Collect for RefCell<Upvalue> {
+    fn clear(&self) {
+        // Open upvalues are referenced by the vm, so only closed ones can be garbage.
+        let _value = self.try_borrow_mut()
+            .map(|mut upvalue| mem::replace(&mut *upvalue, Upvalue::Closed(Value::from(Literal::Null))));
+    }
+}
+
+impl Trace for RefCell<Class> {
+    fn trace(&self, tracer: &mut Tracer) {
+        match self.try_borrow() {
+            Ok(class) => class.methods.values().for_each(|method| tracer.edge(method)),
+            Err(_) => tracer.pin(),
+        }
+    }
+}
+
+impl Collect for RefCell<Class> {
+    fn clear(&self) {
+        let _methods = self.try_borrow_mut().map(|mut class| mem::take(&mut class.methods));
+    }
+}
+
+impl Trace for RefCell<Instance> {
+    fn trace(&self, tracer: &mut Tracer) {
+        let Ok(instance) = self.try_borrow() else {
+            return tracer.pin();
+        };
+
+        tracer.edge(&instance.class);
+        instance.fields.values().for_each(|field| field.trace(tracer));
+    }
+}
+
+impl Collect for RefCell<Instance> {
+    fn clear(&self) {
+        let _fields = self.try_borrow_mut().map(|mut instance| mem::take(&mut instance.fields));
+    }
+}
+
+impl Trace for BoundMethod {
+    fn trace(&self, tracer: &mut Tracer) {
+        self.receiver.trace(tracer);
+        tracer.edge(&self.method);
+    }
+}
+
+impl Collect for BoundMethod {
+    fn clear(&self) {
+        // Bound methods can not be modified, their cycles go through the receiver which is cleared.
+    }
+}
+
+impl Trace for NativeFunction {
+    fn trace(&self, tracer: &mut Tracer) {
+        if let Some(receiver) = &self.receiver {
+            receiver.trace(tracer);
+        }
+    }
+}
+
+impl Collect for NativeFunction {
+    fn clear(&self) {
+        // Like bound methods, their cycles go through the receiver which is cleared.
+    }
+}
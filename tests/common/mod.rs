@@ -3,7 +3,7 @@ macro_rules! tests {
     ($file:ident in $scope:ident is OK $($expected:expr)*) => {
         #[test]
         fn $file() {
-            use rocks_lang::rocks;
+            use rocks_lang::{rocks, Backend};
 
             let mut expected = vec![$($expected),*];
 
@@ -17,30 +17,80 @@ macro_rules! tests {
                 }
             };
 
-            let mut output = Vec::new();
-            let mut rocks = rocks::new(&mut output);
+            // every script must behave the same on both backends
+            for backend in [Backend::Interpreter, Backend::Vm] {
+                let mut output = Vec::new();
+                let mut rocks = rocks::with_backend(&mut output, backend);
 
-            rocks.run_file(format!("tests/target/{}/{}.rocks", stringify!($scope), stringify!($file)));
+                rocks.run_file(format!("tests/target/{}/{}.rocks", stringify!($scope), stringify!($file)))
+                    .expect("script to run without errors");
 
-            // drop rocks here to avoid compiler's drop check error before accessing output
-            drop(rocks);
-            assert_eq!(expected, std::str::from_utf8(&output).unwrap());
+                // drop rocks here to avoid compiler's drop check error before accessing output
+                drop(rocks);
+                assert_eq!(expected, std::str::from_utf8(&output).unwrap(), "on {backend:?} backend");
+            }
         }
     };
 
     ($file:ident in $scope:ident is ERR $($expected:expr)+) => {
         #[test]
         fn $file() {
-            use assert_cmd::Command;
+            use rocks_lang::{rocks, Backend};
+
             // output concats expected with new line
-            let output = vec![$($expected),+].join("\n");
-            let file = format!("tests/target/{}/{}.rocks", stringify!($scope), stringify!($file));
-
-            Command::cargo_bin("rocks").unwrap()
-                .arg(file)
-                .assert()
-                .stderr(format!("{output}\n"))
-                .failure();
+            let expected = vec![$($expected),+].join("\n");
+
+            // every script must fail the same way on both backends
+            for backend in [Backend::Interpreter, Backend::Vm] {
+                let mut output = Vec::new();
+                let mut rocks = rocks::with_backend(&mut output, backend).with_reporter(|_: &_| {});
+
+                let diagnostics = rocks
+                    .run_file(format!("tests/target/{}/{}.rocks", stringify!($scope), stringify!($file)))
+                    .expect_err("script to fail");
+
+                let actual = diagnostics.iter()
+                    .map(|diagnostic| diagnostic.to_string())
+                    .collect::<Vec<String>>()
+                    .join("\n");
+
+                assert_eq!(expected, actual, "on {backend:?} backend");
+            }
         }
     };
 }
+
+/// The result of running sources on one backend.
+#[allow(dead_code)]
+pub struct Run {
+    /// What the sources printed.
+    pub output: String,
+    /// The diagnostics of every source, in order.
+    pub diagnostics: Vec<String>,
+    /// The number of objects that a collection found after the sources ran.
+    pub collected: usize,
+}
+
+/// Runs the sources one after another on both backends with the given configuration.
+#[allow(dead_code)]
+pub fn run(config: rocks_lang::config::InterpreterConfig, sources: &[&str]) -> Vec<Run> {
+    use rocks_lang::{rocks, Backend};
+
+    [Backend::Interpreter, Backend::Vm].into_iter().map(|backend| {
+        let mut output = Vec::new();
+        let mut rocks = rocks::with_backend(&mut output, backend)
+            .with_config(config.clone())
+            .with_reporter(|_: &_| {});
+
+        let mut diagnostics = vec![];
+        for source in sources {
+            if let Err(errors) = rocks.run(source.to_string()) {
+                diagnostics.extend(errors.iter().map(|diagnostic| diagnostic.to_string()));
+            }
+        }
+        let collected = rocks.collect_garbage();
+
+        drop(rocks);
+        Run { output: String::from_utf8(output).unwrap(), diagnostics, collected }
+    }).collect()
+}
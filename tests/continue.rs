@@ -0,0 +1,52 @@
+#[macro_use]
+mod common;
+
+#[cfg(test)]
+mod r#continue {
+    tests! {
+        closure_in_body in continue is OK
+        "0"
+        "1"
+        "3"
+    }
+
+    tests! {
+        for_loop in continue is OK
+        "0"
+        "2"
+        "4"
+    }
+
+    tests! {
+        inside_function in continue is ERR
+        "[line 3:5] Error at 'continue': Cannot continue outside of a loop"
+    }
+
+    tests! {
+        missing_semicolon in continue is ERR
+        "[line 1:25] Error at '}': Expected ';' after continue"
+    }
+
+    tests! {
+        nested in continue is OK
+        "0"
+        "2"
+        "10"
+        "12"
+        "20"
+        "22"
+    }
+
+    tests! {
+        outside_loop in continue is ERR
+        "[line 1:1] Error at 'continue': Cannot continue outside of a loop"
+    }
+
+    tests! {
+        while_loop in continue is OK
+        "1"
+        "3"
+        "4"
+        "5"
+    }
+}
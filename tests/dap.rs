@@ -0,0 +1,370 @@
+#[cfg(test)]
+mod dap {
+    use std::io::{self, BufRead, BufReader, PipeReader, PipeWriter, Read, Write};
+    use std::thread::{self, JoinHandle};
+    use std::{env, fs, process};
+
+    use rocks_lang::debugger::dap;
+    use serde_json::{json, Value};
+
+    const SOURCE: &str = "\
+fun add(a, b) {
+    var sum = a + b;
+    return sum;
+}
+var x = [1, 2];
+var y = add(x[0], 2);
+print y;
+";
+
+    /// Writes the source to a temporary script and returns its path.
+    fn script(name: &str, source: &str) -> String {
+        let path = env::temp_dir().join(format!("rocks-dap-{}-{name}.rocks", process::id()));
+        fs::write(&path, source).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    /// A client that talks to a server running on its own thread.
+    struct Session {
+        input: PipeWriter,
+        output: BufReader<PipeReader>,
+        server: JoinHandle<()>,
+        seq: u64,
+        /// Every message that the server sent so far.
+        messages: Vec<Value>,
+    }
+
+    impl Session {
+        fn start() -> Self {
+            let (server_input, input) = io::pipe().unwrap();
+            let (output, server_output) = io::pipe().unwrap();
+            let server = thread::spawn(move || dap::serve(BufReader::new(server_input), server_output));
+
+            Session { input, output: BufReader::new(output), server, seq: 1, messages: vec![] }
+        }
+
+        /// Sends the request and waits for its response.
+        fn request(&mut self, mut request: Value) {
+            let seq = self.seq;
+            self.seq += 1;
+
+            request["seq"] = json!(seq);
+            request["type"] = json!("request");
+            let body = request.to_string();
+            write!(self.input, "Content-Length: {}\r\n\r\n{body}", body.len()).unwrap();
+
+            self.wait(|message| message["type"] == "response" && message["request_seq"] == seq);
+        }
+
+        /// Reads the messages of the server until one of them matches the predicate.
+        fn wait(&mut self, predicate: impl Fn(&Value) -> bool) {
+            loop {
+                let mut length = 0;
+                loop {
+                    let mut header = String::new();
+                    assert!(self.output.read_line(&mut header).unwrap() > 0, "server closed the connection");
+                    match header.trim().strip_prefix("Content-Length: ") {
+                        Some(value) => length = value.parse().unwrap(),
+                        None => break,
+                    }
+                }
+
+                let mut body = vec![0; length];
+                self.output.read_exact(&mut body).unwrap();
+                let message: Value = serde_json::from_slice(&body).unwrap();
+
+                let matches = predicate(&message);
+                self.messages.push(message);
+                if matches {
+                    return;
+                }
+            }
+        }
+
+        /// Closes the connection and returns every message that the server sent.
+        fn finish(mut self) -> Vec<Value> {
+            drop(self.input);
+            self.server.join().unwrap();
+
+            let mut rest = String::new();
+            self.output.read_to_string(&mut rest).unwrap();
+            self.messages.extend(rest.split("Content-Length: ")
+                .skip(1)
+                .map(|message| serde_json::from_str::<Value>(message.split_once("\r\n\r\n").unwrap().1).unwrap()));
+
+            self.messages
+        }
+    }
+
+    /// Serves the given requests like a client would and returns the messages that the server
+    /// sent. After a request that resumes the script, the next one is only sent once the script
+    /// stopped or terminated.
+    fn serve(requests: Vec<Value>) -> Vec<Value> {
+        let mut session = Session::start();
+
+        for request in requests {
+            let resumes = ["configurationDone", "continue", "next", "stepIn", "stepOut"]
+                .contains(&request["command"].as_str().unwrap());
+            session.request(request);
+
+            if resumes {
+                session.wait(|message| message["event"] == "stopped" || message["event"] == "terminated");
+            }
+        }
+
+        session.finish()
+    }
+
+    fn request(command: &str, arguments: Value) -> Value {
+        json!({ "command": command, "arguments": arguments })
+    }
+
+    /// Returns the requests that launch the script with breakpoints at the given lines.
+    fn launch(path: &str, lines: &[usize], stop_on_entry: bool) -> Vec<Value> {
+        let breakpoints: Vec<Value> = lines.iter().map(|line| json!({ "line": line })).collect();
+
+        vec![
+            request("initialize", json!({ "adapterID": "rocks" })),
+            request("launch", json!({ "program": path, "stopOnEntry": stop_on_entry })),
+            request("setBreakpoints", json!({ "source": { "path": path }, "breakpoints": breakpoints })),
+            request("configurationDone", json!({})),
+        ]
+    }
+
+    fn response<'a>(messages: &'a [Value], command: &str) -> &'a Value {
+        messages.iter()
+            .find(|message| message["type"] == "response" && message["command"] == command)
+            .unwrap_or_else(|| panic!("no response to '{command}' in {messages:#?}"))
+    }
+
+    fn events<'a>(messages: &'a [Value], event: &str) -> Vec<&'a Value> {
+        messages.iter().filter(|message| message["event"] == event).collect()
+    }
+
+    fn output(messages: &[Value], category: &str) -> String {
+        events(messages, "output").iter()
+            .filter(|event| event["body"]["category"] == category)
+            .map(|event| event["body"]["output"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn breakpoint() {
+        let path = script("breakpoint", SOURCE);
+        let mut requests = launch(&path, &[3], false);
+        requests.extend([
+            request("stackTrace", json!({ "threadId": 1 })),
+            request("scopes", json!({ "frameId": 0 })),
+            request("variables", json!({ "variablesReference": 1 })),
+            request("continue", json!({ "threadId": 1 })),
+            request("disconnect", json!({})),
+        ]);
+
+        let messages = serve(requests);
+
+        assert_eq!(response(&messages, "setBreakpoints")["body"]["breakpoints"][0]["verified"], true);
+
+        let stopped = events(&messages, "stopped");
+        assert_eq!(stopped.len(), 1);
+        assert_eq!(stopped[0]["body"]["reason"], "breakpoint");
+
+        let frames = &response(&messages, "stackTrace")["body"]["stackFrames"];
+        let frames: Vec<(&str, u64)> = frames.as_array().unwrap().iter()
+            .map(|frame| (frame["name"].as_str().unwrap(), frame["line"].as_u64().unwrap()))
+            .collect();
+        assert_eq!(frames, [("add", 3), ("<script>", 6)]);
+
+        let variables = &response(&messages, "variables")["body"]["variables"];
+        let variables: Vec<(&str, &str)> = variables.as_array().unwrap().iter()
+            .map(|variable| (variable["name"].as_str().unwrap(), variable["value"].as_str().unwrap()))
+            .collect();
+        assert_eq!(variables, [("a", "1"), ("b", "2"), ("sum", "3")]);
+
+        assert_eq!(output(&messages, "stdout"), "3\n");
+        assert_eq!(events(&messages, "exited")[0]["body"]["exitCode"], 0);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn step() {
+        let path = script("step", SOURCE);
+        let mut requests = launch(&path, &[6], true);
+        requests.extend([
+            request("continue", json!({ "threadId": 1 })),
+            request("stepIn", json!({ "threadId": 1 })),
+            request("next", json!({ "threadId": 1 })),
+            request("stepOut", json!({ "threadId": 1 })),
+            request("continue", json!({ "threadId": 1 })),
+            request("disconnect", json!({})),
+        ]);
+
+        let messages = serve(requests);
+
+        let reasons: Vec<&str> = events(&messages, "stopped").iter()
+            .map(|event| event["body"]["reason"].as_str().unwrap())
+            .collect();
+        assert_eq!(reasons, ["entry", "breakpoint", "step", "step", "step"]);
+        assert_eq!(output(&messages, "stdout"), "3\n");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn evaluate() {
+        let path = script("evaluate", SOURCE);
+        let mut requests = launch(&path, &[3], false);
+        requests.extend([
+            request("evaluate", json!({ "expression": "sum * 2", "frameId": 0 })),
+            request("evaluate", json!({ "expression": "x", "frameId": 1 })),
+            request("variables", json!({ "variablesReference": 1 })),
+            request("evaluate", json!({ "expression": "missing", "frameId": 0 })),
+            request("terminate", json!({})),
+            request("disconnect", json!({})),
+        ]);
+
+        let messages = serve(requests);
+
+        let evaluations: Vec<&Value> = messages.iter().filter(|message| message["command"] == "evaluate").collect();
+        assert_eq!(evaluations[0]["body"]["result"], "6");
+        assert_eq!(evaluations[1]["body"]["result"], "[1, 2]");
+        assert_eq!(evaluations[1]["body"]["variablesReference"], 1);
+        assert_eq!(evaluations[2]["success"], false);
+        assert!(evaluations[2]["message"].as_str().unwrap().contains("Undefined variable 'missing'"));
+
+        let elements = &response(&messages, "variables")["body"]["variables"];
+        assert_eq!(elements[1]["value"], "2");
+
+        // The script was terminated before it printed anything.
+        assert_eq!(output(&messages, "stdout"), "");
+        assert!(output(&messages, "stderr").contains("Execution was stopped"));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn running() {
+        let path = script("running", "var i = 0;\nwhile (true) {\n    i = i + 1;\n    if (i == 1) print \"running\";\n}\n");
+
+        let mut session = Session::start();
+        for request in launch(&path, &[], false) {
+            session.request(request);
+        }
+
+        // The script runs forever without breakpoints, it can only be paused and terminated.
+        session.wait(|message| message["event"] == "output");
+        session.request(request("pause", json!({ "threadId": 1 })));
+        session.wait(|message| message["event"] == "stopped");
+        session.request(request("evaluate", json!({ "expression": "i > 0", "frameId": 0 })));
+        session.request(request("continue", json!({ "threadId": 1 })));
+        session.request(request("terminate", json!({})));
+        session.wait(|message| message["event"] == "terminated");
+        session.request(request("disconnect", json!({})));
+
+        let messages = session.finish();
+        assert_eq!(events(&messages, "stopped")[0]["body"]["reason"], "pause");
+        assert_eq!(response(&messages, "evaluate")["body"]["result"], "true");
+        assert_eq!(response(&messages, "terminate")["success"], true);
+        assert_eq!(output(&messages, "stdout"), "running\n");
+        assert!(output(&messages, "stderr").contains("Execution was stopped"));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn module_frames() {
+        let module = script("frames-lib", "fun run(x) {\n    return x + 1;\n}\n");
+        let name = module.rsplit(std::path::MAIN_SEPARATOR).next().unwrap();
+        let path = script("frames", &format!("import \"{name}\" as lib;\nprint lib.run(1);\n"));
+
+        let mut requests = launch(&path, &[], true);
+        requests.insert(3, request("setBreakpoints", json!({ "source": { "path": module }, "breakpoints": [{ "line": 2 }] })));
+        requests.extend([
+            request("stepIn", json!({ "threadId": 1 })),
+            request("stackTrace", json!({ "threadId": 1 })),
+            request("continue", json!({ "threadId": 1 })),
+            request("disconnect", json!({})),
+        ]);
+
+        let messages = serve(requests);
+
+        let responses: Vec<&Value> = messages.iter().filter(|message| message["command"] == "setBreakpoints").collect();
+        assert_eq!(responses[0]["body"]["breakpoints"].as_array().unwrap().len(), 0);
+        assert_eq!(responses[1]["body"]["breakpoints"][0]["verified"], false);
+
+        // The step pauses at the first statement of the module, which is called by the import.
+        let frames = &response(&messages, "stackTrace")["body"]["stackFrames"];
+        let frames: Vec<(&str, &str, u64)> = frames.as_array().unwrap().iter()
+            .map(|frame| (frame["name"].as_str().unwrap(), frame["source"]["path"].as_str().unwrap(), frame["line"].as_u64().unwrap()))
+            .collect();
+
+        let module = fs::canonicalize(&module).unwrap();
+        let program = fs::canonicalize(&path).unwrap();
+        assert_eq!(frames, [
+            ("<script>", module.to_str().unwrap(), 1),
+            ("<script>", program.to_str().unwrap(), 1),
+        ]);
+
+        assert_eq!(output(&messages, "stdout"), "2\n");
+        fs::remove_file(module).unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn module_globals() {
+        let module = script("globals-lib", "var secret = 1;\nfun run(x) {\n    return x + secret;\n}\n");
+        let name = module.rsplit(std::path::MAIN_SEPARATOR).next().unwrap();
+        let path = script("globals", &format!("var main = 5;\nimport \"{name}\" as lib;\nprint lib.run(1);\n"));
+
+        let mut requests = launch(&path, &[], true);
+        requests.extend([
+            request("next", json!({ "threadId": 1 })),
+            request("stepIn", json!({ "threadId": 1 })),
+            request("scopes", json!({ "frameId": 1 })),
+            request("variables", json!({ "variablesReference": 2 })),
+            request("evaluate", json!({ "expression": "main", "frameId": 1 })),
+            request("evaluate", json!({ "expression": "main", "frameId": 0 })),
+            request("continue", json!({ "threadId": 1 })),
+            request("disconnect", json!({})),
+        ]);
+
+        let messages = serve(requests);
+
+        // The caller of the module sees the globals of the script, not the ones of the module.
+        let globals = &response(&messages, "variables")["body"]["variables"];
+        let globals: Vec<&str> = globals.as_array().unwrap().iter().map(|variable| variable["name"].as_str().unwrap()).collect();
+        assert!(globals.contains(&"main"), "{globals:?}");
+
+        let evaluations: Vec<&Value> = messages.iter().filter(|message| message["command"] == "evaluate").collect();
+        assert_eq!(evaluations[0]["body"]["result"], "5");
+        assert_eq!(evaluations[1]["success"], false);
+
+        assert_eq!(output(&messages, "stdout"), "2\n");
+        fs::remove_file(module).unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn breakpoints_before_launch() {
+        let path = script("before-launch", SOURCE);
+        let messages = serve(vec![
+            request("initialize", json!({})),
+            request("setBreakpoints", json!({ "source": { "path": path }, "breakpoints": [{ "line": 3 }] })),
+            request("disconnect", json!({})),
+        ]);
+
+        // The launched script is not known yet, so the breakpoint can not be verified.
+        assert_eq!(response(&messages, "setBreakpoints")["body"]["breakpoints"][0]["verified"], false);
+        assert!(events(&messages, "initialized").is_empty());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn missing_program() {
+        let messages = serve(vec![
+            request("initialize", json!({})),
+            request("launch", json!({ "program": "/does/not/exist.rocks" })),
+            request("disconnect", json!({})),
+        ]);
+
+        assert_eq!(response(&messages, "initialize")["body"]["supportsConfigurationDoneRequest"], true);
+        assert_eq!(response(&messages, "launch")["success"], false);
+        assert_eq!(response(&messages, "disconnect")["success"], true);
+    }
+}
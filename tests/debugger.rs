@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod debugger {
+    use std::{env, fs, process};
+
+    use rocks_lang::rocks;
+    use rocks_lang::debugger::Debugger;
+
+    const SOURCE: &str = "\
+fun add(a, b) {
+    var sum = a + b;
+    return sum;
+}
+var x = 1;
+var y = add(x, 2);
+print y;
+";
+
+    /// Runs the source under the debugger with the given commands and returns the output of the
+    /// script, the output of the debugger and the diagnostics.
+    fn debug(source: &str, commands: &str) -> (String, String, Vec<String>) {
+        let mut output = Vec::new();
+        let mut log = Vec::new();
+        let debugger = Debugger::new(source, commands.as_bytes(), &mut log);
+        let mut rocks = rocks::new(&mut output).with_hook(debugger).unwrap().with_reporter(|_: &_| {});
+
+        let diagnostics = match rocks.run(source.to_string()) {
+            Ok(_) => vec![],
+            Err(errors) => errors.iter().map(|diagnostic| diagnostic.to_string()).collect(),
+        };
+
+        drop(rocks);
+        (String::from_utf8(output).unwrap(), String::from_utf8(log).unwrap(), diagnostics)
+    }
+
+    /// Returns the lines that the debugger paused at.
+    fn pauses(log: &str) -> Vec<usize> {
+        log.split("Paused at line ")
+            .skip(1)
+            .map(|pause| pause.split(':').next().unwrap().parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn breakpoint() {
+        let (output, log, diagnostics) = debug(SOURCE, "break 3\ncontinue\nlocals\nprint sum * x\ncontinue\n");
+
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        assert_eq!(output, "3\n");
+        assert_eq!(pauses(&log), [1, 3]);
+        assert!(log.contains("a = 1\nb = 2\nsum = 3\n"), "{log}");
+        assert!(log.contains("(debug) 3\n"), "{log}");
+    }
+
+    #[test]
+    fn step() {
+        let (_, log, _) = debug(SOURCE, "break 6\ncontinue\nstep\nstep\nout\nnext\n");
+        assert_eq!(pauses(&log), [1, 6, 2, 3, 7]);
+
+        let (_, log, _) = debug(SOURCE, "break 6\ncontinue\nnext\ncontinue\n");
+        assert_eq!(pauses(&log), [1, 6, 7]);
+    }
+
+    #[test]
+    fn nested_block() {
+        let source = "var i = 0;\nwhile (i < 2) {\n    i = i + 1;\n    print i;\n}\n";
+        let (output, log, _) = debug(source, "break 3\ncontinue\ncontinue\ncontinue\n");
+
+        assert_eq!(output, "1\n2\n");
+        assert_eq!(pauses(&log), [1, 3, 3]);
+    }
+
+    #[test]
+    fn delete() {
+        let (_, log, _) = debug(SOURCE, "break 3\nbreak 7\ndelete 3\ndelete 4\ncontinue\ncontinue\n");
+
+        assert!(log.contains("No breakpoint at line 4"), "{log}");
+        assert_eq!(pauses(&log), [1, 7]);
+    }
+
+    #[test]
+    fn evaluate_method() {
+        let source = "class Point {\n    init(x) {\n        this.x = x;\n    }\n}\nPoint(4);\n";
+        let (_, log, diagnostics) = debug(source, "b 3\nc\np this\np x + 1\np missing\nc\n");
+
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        assert!(log.contains("(debug) <instance Point>\n"), "{log}");
+        assert!(log.contains("(debug) 5\n"), "{log}");
+        assert!(log.contains("Undefined variable 'missing'"), "{log}");
+    }
+
+    #[test]
+    fn module() {
+        let directory = env::temp_dir().join(format!("rocks-debugger-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("lib.rocks"), "fun run(x) {\n    print x;\n    return x + 1;\n}\n").unwrap();
+
+        let path = directory.join("main.rocks");
+        let source = "import \"lib.rocks\" as lib;\nprint 1;\nprint lib.run(2);\n";
+        fs::write(&path, source).unwrap();
+
+        // The breakpoint on line 2 of the script does not pause line 2 of the module.
+        let mut output = Vec::new();
+        let mut log = Vec::new();
+        let debugger = Debugger::new(source, "break 2\ncontinue\nnext\nstep\nbreak 3\ncontinue\ncontinue\n".as_bytes(), &mut log);
+        let mut rocks = rocks::new(&mut output).with_hook(debugger).unwrap().with_reporter(|_: &_| {});
+        let result = rocks.run_file(path.to_string_lossy().to_string());
+        assert!(result.is_ok(), "{result:?}");
+        drop(rocks);
+
+        let log = String::from_utf8(log).unwrap();
+        let pauses: Vec<&str> = log.lines().filter_map(|line| line.find("Paused at").map(|start| &line[start..])).collect();
+        assert_eq!(pauses, [
+            "Paused at line 1: import \"lib.rocks\" as lib;",
+            "Paused at line 2: print 1;",
+            "Paused at line 3: print lib.run(2);",
+            "Paused at line 2 of lib.rocks: print x;",
+            "Paused at line 3 of lib.rocks: return x + 1;",
+        ]);
+        assert_eq!(String::from_utf8(output).unwrap(), "1\n2\n3\n");
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn quit() {
+        let source = "try {\n    print 1;\n    print 2;\n} catch (error) {\n    print error;\n}\nprint 3;\n";
+        let (output, _, diagnostics) = debug(source, "next\nnext\nquit\n");
+
+        assert_eq!(output, "1\n");
+        assert_eq!(diagnostics, ["[line 3:5] Error at 'print': Execution was stopped"]);
+    }
+}
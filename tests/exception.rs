@@ -0,0 +1,123 @@
+#[macro_use]
+mod common;
+
+#[cfg(test)]
+mod exception {
+    tests! {
+        arity in exception is OK
+        "Expected 1 argument but got 2"
+        "Only instances have properties"
+    }
+
+    tests! {
+        catch_runtime_error in exception is OK
+        "before"
+        "Binary operation '+' is not supported between number type and string type"
+        "3"
+        "13"
+        "<error Binary operation '+' is not supported between number type and string type>"
+        "after"
+    }
+
+    tests! {
+        catch_scope in exception is OK
+        "global"
+        "local"
+    }
+
+    tests! {
+        error_property in exception is ERR
+        "[line 4:11] Error at 'unknown': Undefined property 'unknown'"
+    }
+
+    tests! {
+        finally in exception is OK
+        "body"
+        "finally"
+        "catch"
+        "finally"
+        "cleanup"
+        "returned"
+        "loop"
+        "1"
+        "loop"
+        "loop"
+    }
+
+    tests! {
+        finally_overrides in exception is OK
+        "finally"
+        "swallowed"
+    }
+
+    tests! {
+        finally_rethrow in exception is OK
+        "finally"
+        "caught inner"
+        "finally"
+        "caught second"
+    }
+
+    tests! {
+        missing_catch in exception is ERR
+        "[line 4:1] Error at 'print': Expected 'catch' or 'finally' after try block"
+    }
+
+    tests! {
+        missing_semicolon in exception is ERR
+        "[line 2:1] Error at end: Expected ';' after thrown value"
+    }
+
+    tests! {
+        nested_loops in exception is OK
+        "[0, 0]"
+        "xy"
+        "xy"
+        "x"
+        "[1, 0]"
+        "xy"
+        "xy"
+        "x"
+        "[2, 0]"
+        "xy"
+        "xy"
+        "x"
+    }
+
+    tests! {
+        rethrow in exception is OK
+        "inner"
+        "outer Only instances have properties"
+        "3"
+    }
+
+    tests! {
+        shadowed_finally in exception is OK
+        "function"
+        "try"
+    }
+
+    tests! {
+        through_calls in exception is OK
+        "bottom"
+        "empty stack"
+        "bad element"
+        "done"
+    }
+
+    tests! {
+        throw_value in exception is OK
+        "boom"
+        "boom"
+        "3"
+        "[1, 2]"
+        "true"
+        "[1, 2, 3]"
+        "null"
+    }
+
+    tests! {
+        uncaught in exception is ERR
+        "[line 2:1] Error at 'throw': boom"
+    }
+}
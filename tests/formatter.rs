@@ -0,0 +1,192 @@
+#[cfg(test)]
+mod formatter {
+    use rocks_lang::rocks;
+    use rocks_lang::formatter::Formatter;
+
+    /// Formats the source and checks that formatting the result again does not change it.
+    fn format(source: &str) -> String {
+        let formatted = Formatter::new().format(source).unwrap();
+        assert_eq!(Formatter::new().format(&formatted).unwrap(), formatted, "formatting is not stable");
+        formatted
+    }
+
+    fn run(source: &str) -> String {
+        let mut output = Vec::new();
+        let mut rocks = rocks::new(&mut output).with_reporter(|_: &_| {});
+        rocks.run(source.to_string()).unwrap();
+        drop(rocks);
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn statements() {
+        let source = "\
+class Point<Base{init(x,y){this.x=x;this.y=y;}
+sum(){return super.sum()+this.x*(this.y-1);}}
+fun  apply(f,x){return f(x);}
+var double=fun(x)=>x*2;
+var square = fun (x) { return x * x; };
+outer:for(var i=0;i<3;i=i+1){for(;;){if(i==1)continue outer;else break;}}
+while(!done and count<=10)count=count+1;
+try{throw \"error\";}catch(error){print error;}finally{print [1,2][0];}
+import \"lib.rocks\" as lib;
+from \"lib.rocks\" import a,b;
+if (a) { print 1; } else if (b) { print {\"a\": 1, 2: null}; } else { print -a; }
+";
+
+        assert_eq!(format(source), "\
+class Point < Base {
+    init(x, y) {
+        this.x = x;
+        this.y = y;
+    }
+    sum() {
+        return super.sum() + this.x * (this.y - 1);
+    }
+}
+fun apply(f, x) {
+    return f(x);
+}
+var double = fun (x) => x * 2;
+var square = fun (x) {
+    return x * x;
+};
+outer: for (var i = 0; i < 3; i = i + 1) {
+    for (;;) {
+        if (i == 1)
+            continue outer;
+        else
+            break;
+    }
+}
+while (!done and count <= 10)
+    count = count + 1;
+try {
+    throw \"error\";
+} catch (error) {
+    print error;
+} finally {
+    print [1, 2][0];
+}
+import \"lib.rocks\" as lib;
+from \"lib.rocks\" import a, b;
+if (a) {
+    print 1;
+} else if (b) {
+    print {\"a\": 1, 2: null};
+} else {
+    print -a;
+}
+");
+    }
+
+    #[test]
+    fn comments() {
+        let source = "\
+// header
+
+var a = 1; // one
+{ // block
+    // inside
+    print a;
+
+    // before close
+}
+fun empty() {
+    // nothing
+}
+class Empty {}
+// trailing";
+
+        assert_eq!(format(source), "\
+// header
+
+var a = 1; // one
+{ // block
+    // inside
+    print a;
+
+    // before close
+}
+fun empty() {
+    // nothing
+}
+class Empty {}
+// trailing
+");
+    }
+
+    #[test]
+    fn comments_before_else() {
+        let source = "\
+if (true) {
+    print 1;
+} // then
+// before else
+else {
+    print 2;
+}
+if (false) print 3;
+// before else
+else print 4;";
+
+        assert_eq!(format(source), "\
+if (true) {
+    print 1;
+} // then
+// before else
+else {
+    print 2;
+}
+if (false)
+    print 3;
+// before else
+else
+    print 4;
+");
+    }
+
+    #[test]
+    fn blocks_and_loops() {
+        let source = "{ var i = 0; for (;i < 2;) i = i + 1; }\nfor (var j = 0; j < 2; j = j + 1) {}\n";
+
+        assert_eq!(format(source), "\
+{
+    var i = 0;
+    for (; i < 2;)
+        i = i + 1;
+}
+for (var j = 0; j < 2; j = j + 1) {}
+");
+    }
+
+    #[test]
+    fn wrap_calls() {
+        let source = "print join(\"first argument\", \"second argument\", inner(\"third\", \"fourth\"));\n";
+
+        assert_eq!(Formatter::new().with_width(40).format(source).unwrap(), "\
+print join(
+    \"first argument\",
+    \"second argument\",
+    inner(\"third\", \"fourth\")
+);
+");
+        assert_eq!(format(source), source);
+    }
+
+    #[test]
+    fn same_behavior() {
+        let source = "\
+fun fib(n){if(n<2)return n;return fib(n-1)+fib(n-2);}
+var list=[];for(var i=0;i<10;i=i+1)list.push(fib(i));
+print list;";
+
+        assert_eq!(run(&format(source)), run(source));
+    }
+
+    #[test]
+    fn errors() {
+        let errors = Formatter::new().format("var a = ;").unwrap_err();
+        assert_eq!(errors[0].to_string(), "[line 1:9] Error at ';': Expected expression");
+    }
+}
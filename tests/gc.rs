@@ -0,0 +1,102 @@
+#[macro_use]
+mod common;
+
+#[cfg(test)]
+mod gc {
+    use rocks_lang::{rocks, Backend};
+    use rocks_lang::config::{InterpreterConfig, Limits};
+
+    use crate::common::{run, Run};
+
+    /// Returns the configuration that caps the number of objects at the given allocations.
+    fn capped(allocations: usize) -> InterpreterConfig {
+        let limits = Limits { allocations: Some(allocations), ..Limits::default() };
+        InterpreterConfig { limits, ..InterpreterConfig::default() }
+    }
+
+    #[test]
+    fn instance_cycle() {
+        let source = "class Node { name() { return \"node\"; } }\n{ var node = Node(); node.method = node.name; node.self = node; }";
+
+        for Run { diagnostics, collected, .. } in run(InterpreterConfig::default(), &[source]) {
+            assert!(diagnostics.is_empty(), "{diagnostics:?}");
+            assert!(collected > 0);
+        }
+    }
+
+    #[test]
+    fn closure_cycle() {
+        let source = "{ fun recurse(n) { if (n > 0) recurse(n - 1); } recurse(3); }";
+
+        for Run { diagnostics, collected, .. } in run(InterpreterConfig::default(), &[source]) {
+            assert!(diagnostics.is_empty(), "{diagnostics:?}");
+            assert!(collected > 0);
+        }
+    }
+
+    #[test]
+    fn method_cycle() {
+        let source = "{ var list = []; list.push(list.len); var map = {}; map[\"has\"] = map.has; }";
+
+        for Run { diagnostics, collected, .. } in run(InterpreterConfig::default(), &[source]) {
+            assert!(diagnostics.is_empty(), "{diagnostics:?}");
+            assert!(collected > 0);
+        }
+    }
+
+    #[test]
+    fn reachable_method_cycle() {
+        let source = "var list = [];\nvar push = list.push;\nlist.push(push); list.push(push); list.push(list.len);";
+
+        for backend in [Backend::Interpreter, Backend::Vm] {
+            let mut output = Vec::new();
+            let mut rocks = rocks::with_backend(&mut output, backend).with_reporter(|_: &_| {});
+
+            rocks.run(source.to_string()).unwrap();
+            assert_eq!(rocks.collect_garbage(), 0, "on {backend:?} backend");
+            rocks.run("push = null; list[0](1); print list[2]();".to_string()).unwrap();
+            assert_eq!(rocks.collect_garbage(), 0, "on {backend:?} backend");
+
+            drop(rocks);
+            assert_eq!(String::from_utf8(output).unwrap(), "4\n", "on {backend:?} backend");
+        }
+    }
+
+    #[test]
+    fn reachable_cycle() {
+        let source = "class Node {}\nvar node = Node(); node.list = [node]; node.name = \"root\";";
+
+        for backend in [Backend::Interpreter, Backend::Vm] {
+            let mut output = Vec::new();
+            let mut rocks = rocks::with_backend(&mut output, backend).with_reporter(|_: &_| {});
+
+            rocks.run(source.to_string()).unwrap();
+            assert_eq!(rocks.collect_garbage(), 0, "on {backend:?} backend");
+            rocks.run("print node.list[0].name;".to_string()).unwrap();
+
+            drop(rocks);
+            assert_eq!(String::from_utf8(output).unwrap(), "root\n", "on {backend:?} backend");
+        }
+    }
+
+    #[test]
+    fn collected_under_cap() {
+        let source = "class Node {}\nfor (var i = 0; i < 1000; i = i + 1) { var node = Node(); node.self = node; }\nprint \"done\";";
+
+        for Run { output, diagnostics, .. } in run(capped(100), &[source]) {
+            assert!(diagnostics.is_empty(), "{diagnostics:?}");
+            assert_eq!(output, "done\n");
+        }
+    }
+
+    #[test]
+    fn allocation_cap() {
+        let source = "var list = [];\ntry { while (true) list.push([]); } catch (error) { print \"caught\"; }\nprint \"after\";";
+
+        for Run { output, diagnostics, .. } in run(capped(100), &[source]) {
+            assert_eq!(output, "");
+            assert_eq!(diagnostics.len(), 1);
+            assert!(diagnostics[0].ends_with("Allocation cap of 100 objects exceeded"), "{}", diagnostics[0]);
+        }
+    }
+}
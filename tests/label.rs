@@ -0,0 +1,45 @@
+#[macro_use]
+mod common;
+
+#[cfg(test)]
+mod label {
+    tests! {
+        break_outer in label is OK
+        "[2, 6]"
+    }
+
+    tests! {
+        continue_outer in label is OK
+        "0"
+        "10"
+        "20"
+        "done"
+    }
+
+    tests! {
+        inside_function in label is ERR
+        "[line 4:16] Error at 'outer': Undefined label 'outer'"
+    }
+
+    tests! {
+        labeled_while in label is OK
+        "1"
+        "3"
+    }
+
+    tests! {
+        not_loop in label is ERR
+        "[line 1:8] Error at 'print': Expected loop after label"
+    }
+
+    tests! {
+        shadowed in label is OK
+        "0"
+        "10"
+    }
+
+    tests! {
+        undefined in label is ERR
+        "[line 3:11] Error at 'inner': Undefined label 'inner'"
+    }
+}
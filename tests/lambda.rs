@@ -0,0 +1,43 @@
+#[macro_use]
+mod common;
+
+#[cfg(test)]
+mod lambda {
+    tests! {
+        arrow in lambda is OK
+        "8"
+        "[2, 3, 4]"
+        "immediate"
+    }
+
+    tests! {
+        block_body in lambda is OK
+        "3"
+        "<function <lambda>>"
+    }
+
+    tests! {
+        break_inside in lambda is ERR
+        "[line 2:20] Error at 'break': Cannot break outside of a loop"
+    }
+
+    tests! {
+        closure in lambda is OK
+        "2"
+    }
+
+    tests! {
+        missing_body in lambda is ERR
+        "[line 1:17] Error at 'a': Expected '{' or '=>' before lambda body"
+    }
+
+    tests! {
+        return_value in lambda is OK
+        "null"
+    }
+
+    tests! {
+        statement in lambda is OK
+        "called"
+    }
+}
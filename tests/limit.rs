@@ -0,0 +1,148 @@
+#[macro_use]
+mod common;
+
+#[cfg(test)]
+mod limit {
+    use std::time::Duration;
+
+    use rocks_lang::config::{InterpreterConfig, Limits};
+
+    use crate::common::{run, Run};
+
+    /// Returns the configuration with the given limits.
+    fn limited(limits: Limits) -> InterpreterConfig {
+        InterpreterConfig { limits, ..InterpreterConfig::default() }
+    }
+
+    #[test]
+    fn stack_overflow() {
+        let source = "\
+fun recurse(n) {
+  return recurse(n + 1);
+}
+
+recurse(0);
+print \"after\";
+";
+
+        // The error only stops the statement that overflowed the stack.
+        for Run { output, diagnostics, .. } in run(InterpreterConfig::default(), &[source]) {
+            assert_eq!(output, "after\n");
+            assert_eq!(diagnostics, ["[line 2:23] Error at ')': Stack overflow"]);
+        }
+    }
+
+    #[test]
+    fn catch_stack_overflow() {
+        let source = "\
+fun recurse(n) {
+  return recurse(n + 1);
+}
+
+try {
+  recurse(0);
+} catch (e) {
+  print e.message;
+}
+
+fun depth(n) {
+  if (n == 0) return 0;
+  return 1 + depth(n - 1);
+}
+
+print depth(1000);
+";
+
+        for Run { output, diagnostics, .. } in run(InterpreterConfig::default(), &[source]) {
+            assert_eq!(output, "Stack overflow\n1000\n");
+            assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        }
+    }
+
+    #[test]
+    fn call_depth() {
+        let limits = Limits { call_depth: 10, ..Limits::default() };
+        let source = "fun depth(n) { if (n == 0) return 0; return 1 + depth(n - 1); }\nprint depth(5);\nprint depth(20);";
+
+        for Run { output, diagnostics, .. } in run(limited(limits), &[source]) {
+            assert_eq!(output, "5\n");
+            assert_eq!(diagnostics, ["[line 1:60] Error at ')': Stack overflow"]);
+        }
+    }
+
+    #[test]
+    fn exact_call_depth() {
+        let limits = Limits { call_depth: 10, ..Limits::default() };
+        let source = "\
+fun depth(n) { if (n == 0) return 0; return 1 + depth(n - 1); }
+print depth(9);
+print depth(10);
+print [1].map(fun (x) => depth(7));
+print [1].map(fun (x) => depth(8));
+";
+
+        // Both backends count the same calls, natives included, and the script itself does not.
+        for Run { output, diagnostics, .. } in run(limited(limits), &[source]) {
+            assert_eq!(output, "9\n[7]\n");
+            assert_eq!(diagnostics, [
+                "[line 1:60] Error at ')': Stack overflow",
+                "[line 5:34] Error at ')': Stack overflow",
+            ]);
+        }
+    }
+
+    #[test]
+    fn instruction_budget() {
+        let limits = Limits { instructions: Some(1000), ..Limits::default() };
+        let source = "print \"before\";\nwhile (true) {}\nprint \"after\";";
+
+        for Run { output, diagnostics, .. } in run(limited(limits), &[source]) {
+            assert_eq!(output, "before\n");
+            assert_eq!(diagnostics.len(), 1);
+            assert!(diagnostics[0].ends_with("Instruction budget of 1000 exceeded"), "{}", diagnostics[0]);
+        }
+    }
+
+    #[test]
+    fn budget_per_run() {
+        let limits = Limits { instructions: Some(1000), ..Limits::default() };
+        let source = "var i = 0; while (i < 50) i = i + 1; print i;";
+
+        for Run { output, diagnostics, .. } in run(limited(limits), &[source, source, source]) {
+            assert_eq!(output, "50\n50\n50\n");
+            assert!(diagnostics.is_empty());
+        }
+    }
+
+    #[test]
+    fn uncatchable() {
+        let limits = Limits { instructions: Some(1000), ..Limits::default() };
+        let source = "
+            while (true) {
+                try {
+                    while (true) {}
+                } catch (e) {
+                    print \"caught\";
+                } finally {
+                    print \"finally\";
+                }
+            }";
+
+        for Run { output, diagnostics, .. } in run(limited(limits), &[source]) {
+            assert_eq!(output, "");
+            assert_eq!(diagnostics.len(), 1);
+            assert!(diagnostics[0].ends_with("Instruction budget of 1000 exceeded"), "{}", diagnostics[0]);
+        }
+    }
+
+    #[test]
+    fn timeout() {
+        let limits = Limits { timeout: Some(Duration::from_millis(20)), ..Limits::default() };
+        let source = "fun spin() { while (true) {} }\nspin();";
+
+        for Run { diagnostics, .. } in run(limited(limits), &[source]) {
+            assert_eq!(diagnostics.len(), 1);
+            assert!(diagnostics[0].ends_with("Execution timed out after 20ms"), "{}", diagnostics[0]);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod linter {
+    use rocks_lang::linter::{Linter, Rule};
+
+    fn lint(source: &str) -> Vec<String> {
+        Linter::new().lint(source).iter().map(|diagnostic| diagnostic.to_string()).collect()
+    }
+
+    #[test]
+    fn unused() {
+        let source = "\
+fun used(a, b, _c) {
+    var d = 1;
+    var _e = 2;
+    return a;
+}
+fun unused() {}
+var global = used(1, 2, 3);
+";
+
+        assert_eq!(lint(source), [
+            "[line 1:13] Warning at 'b': Parameter 'b' is never used (unused-parameter)",
+            "[line 2:9] Warning at 'd': Variable 'd' is never used (unused-variable)",
+            "[line 6:5] Warning at 'unused': Function 'unused' is never used (unused-function)",
+        ]);
+    }
+
+    #[test]
+    fn shadowed_name() {
+        let source = "\
+fun outer(a) {
+    var b = a;
+    {
+        var b = 2;
+        print b;
+    }
+    return fun (a) => a + b;
+}
+print outer(1);
+";
+
+        assert_eq!(lint(source), [
+            "[line 4:13] Warning at 'b': 'b' shadows the variable declared on line 2 (shadowed-name)",
+            "[line 7:17] Warning at 'a': 'a' shadows the variable declared on line 1 (shadowed-name)",
+        ]);
+    }
+
+    #[test]
+    fn unreachable_code() {
+        let source = "\
+fun f(a) {
+    if (a) return 1; else { throw \"error\"; }
+    print a;
+    print a;
+}
+while (true) {
+    break;
+    print f(1);
+}
+";
+
+        assert_eq!(lint(source), [
+            "[line 3:5] Warning at 'print': Unreachable code (unreachable-code)",
+            "[line 8:5] Warning at 'print': Unreachable code (unreachable-code)",
+        ]);
+    }
+
+    #[test]
+    fn constant_comparison() {
+        let source = "\
+var a = 1;
+print a * 2 == \"1\";
+print !a != 1;
+print [1] == [1];
+print -a == 2;
+print a == null;
+";
+
+        assert_eq!(lint(source), [
+            "[line 2:13] Warning at '==': Comparison between a number and a string is always false (constant-comparison)",
+            "[line 3:10] Warning at '!=': Comparison between a bool and a number is always true (constant-comparison)",
+            "[line 4:11] Warning at '==': Comparison with a new list is always false (constant-comparison)",
+        ]);
+    }
+
+    #[test]
+    fn undeclared_assignment() {
+        let source = "\
+var declared;
+declared = 1;
+undeclared = 2;
+fun f() { clock = 3; }
+f();
+";
+
+        assert_eq!(lint(source), [
+            "[line 3:1] Warning at 'undeclared': Assignment to undeclared global 'undeclared' (undeclared-assignment)",
+        ]);
+    }
+
+    #[test]
+    fn method_field_collision() {
+        let source = "\
+class Counter {
+    init() {
+        this.count = 0;
+        this.increment = fun () => this.count = this.count + 1;
+    }
+
+    increment() {
+        this.count = this.count + 1;
+    }
+}
+Counter().increment();
+";
+
+        assert_eq!(lint(source), [
+            "[line 4:14] Warning at 'increment': Field 'increment' hides the method with the same name (method-field-collision)",
+        ]);
+    }
+
+    #[test]
+    fn toggle_rules() {
+        let source = "fun f(a) { return; print a; }\nf(1);";
+
+        assert_eq!(lint(source).len(), 1);
+        assert!(Linter::new().with_rule(Rule::UnreachableCode, false).lint(source).is_empty());
+        assert_eq!(Linter::new().with_rule(Rule::UnreachableCode, false).with_rule(Rule::UnreachableCode, true).lint(source).len(), 1);
+
+        assert_eq!("shadowed-name".parse::<Rule>(), Ok(Rule::ShadowedName));
+        assert_eq!("unknown".parse::<Rule>(), Err("Unknown lint rule 'unknown'".to_string()));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(lint("var a = ;"), ["[line 1:9] Error at ';': Expected expression"]);
+    }
+}
@@ -0,0 +1,85 @@
+#[macro_use]
+mod common;
+
+#[cfg(test)]
+mod list {
+    tests! {
+        index in list is OK
+        "1"
+        "3"
+        "[first, 2, 3]"
+        "5"
+    }
+
+    tests! {
+        index_non_list in list is ERR
+        "[line 2:2] Error at '[': Cannot index into number type"
+    }
+
+    tests! {
+        index_not_number in list is ERR
+        "[line 2:5] Error at '[': List index must be a number, not string"
+    }
+
+    tests! {
+        index_out_of_bounds in list is ERR
+        "[line 2:5] Error at '[': List index 2 is out of bounds for length 2"
+    }
+
+    tests! {
+        literal in list is OK
+        "[]"
+        "[1, 2, 3]"
+        "[1, two, true, null, [3]]"
+        "true"
+        "false"
+    }
+
+    tests! {
+        methods in list is OK
+        "3"
+        "3"
+        "[1, 2]"
+        "[2, 4]"
+        "[1, 3]"
+        "[1, 2, 10]"
+    }
+
+    tests! {
+        missing_bracket in list is ERR
+        "[line 1:12] Error at ';': Expected ']' after list elements"
+    }
+
+    tests! {
+        pop_empty in list is ERR
+        "[line 1:8] Error at ')': Cannot pop from an empty list"
+    }
+
+    tests! {
+        shared in list is OK
+        "[a, b]"
+    }
+
+    tests! {
+        undefined_method in list is ERR
+        "[line 1:5] Error at 'foo': Undefined property 'foo'"
+    }
+
+    #[test]
+    fn self_containing() {
+        use rocks_lang::{rocks, Backend};
+
+        let source = "var a = [1];\na.push(a);\nprint a;\nprint [a, a];\n";
+
+        for backend in [Backend::Interpreter, Backend::Vm] {
+            let mut output = Vec::new();
+            rocks::with_backend(&mut output, backend).run(source.to_string()).unwrap();
+
+            assert_eq!(
+                "[1, [...]]\n[[1, [...]], [1, [...]]]\n",
+                std::str::from_utf8(&output).unwrap(),
+                "on {backend:?} backend",
+            );
+        }
+    }
+}
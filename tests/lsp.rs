@@ -0,0 +1,153 @@
+#[cfg(test)]
+mod lsp {
+    use rocks_lang::lsp;
+    use serde_json::{json, Value};
+
+    const URI: &str = "file:///script.rocks";
+
+    const SOURCE: &str = "\
+class Point {
+    init(x) {
+        this.x = x;
+    }
+
+    double() {
+        return this.x * 2;
+    }
+}
+fun make(x) {
+    return Point(x);
+}
+var point = make(1);
+print point.double();
+";
+
+    /// Opens the source, sends the given requests and returns the messages that the server sent.
+    fn serve(source: &str, requests: Vec<(&str, Value)>) -> Vec<Value> {
+        let mut messages = vec![
+            json!({ "jsonrpc": "2.0", "id": 0, "method": "initialize", "params": {} }),
+            json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }),
+            json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/didOpen",
+                "params": { "textDocument": { "uri": URI, "languageId": "rocks", "version": 1, "text": source } },
+            }),
+        ];
+
+        for (id, (method, mut params)) in requests.into_iter().enumerate() {
+            params["textDocument"] = json!({ "uri": URI });
+            messages.push(json!({ "jsonrpc": "2.0", "id": id + 1, "method": method, "params": params }));
+        }
+        messages.push(json!({ "jsonrpc": "2.0", "method": "exit" }));
+
+        let input: String = messages.iter()
+            .map(|message| message.to_string())
+            .map(|body| format!("Content-Length: {}\r\n\r\n{body}", body.len()))
+            .collect();
+
+        let mut output = Vec::new();
+        lsp::serve(input.as_bytes(), &mut output);
+
+        String::from_utf8(output).unwrap()
+            .split("Content-Length: ")
+            .skip(1)
+            .map(|message| serde_json::from_str(message.split_once("\r\n\r\n").unwrap().1).unwrap())
+            .collect()
+    }
+
+    fn position(line: usize, character: usize) -> Value {
+        json!({ "position": { "line": line, "character": character } })
+    }
+
+    /// Returns the result of the request with the given id.
+    fn result(messages: &[Value], id: usize) -> &Value {
+        &messages.iter().find(|message| message["id"] == id).unwrap()["result"]
+    }
+
+    /// Returns the lines and characters of the starts of the given ranges.
+    fn starts(locations: &Value) -> Vec<(u64, u64)> {
+        locations.as_array().unwrap().iter()
+            .map(|location| {
+                let start = &location["range"]["start"];
+                (start["line"].as_u64().unwrap(), start["character"].as_u64().unwrap())
+            })
+            .collect()
+    }
+
+    fn diagnostics(source: &str) -> Vec<String> {
+        let messages = serve(source, vec![]);
+        let published = messages.iter().find(|message| message["method"] == "textDocument/publishDiagnostics").unwrap();
+
+        published["params"]["diagnostics"].as_array().unwrap().iter()
+            .map(|diagnostic| {
+                let start = &diagnostic["range"]["start"];
+                format!("{}:{} {}", start["line"], start["character"], diagnostic["message"].as_str().unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn publish_diagnostics() {
+        assert!(diagnostics(SOURCE).is_empty());
+        assert_eq!(diagnostics("{\n  var a = a;\n}\n"), ["1:10 Cannot read local variable in its own initializer"]);
+        assert_eq!(diagnostics("var a = 1 +;\nvar b = @;\n"), [
+            "1:8 Unexpected character '@'",
+            "0:11 Expected expression",
+            "1:9 Expected expression",
+        ]);
+    }
+
+    #[test]
+    fn definition() {
+        let messages = serve(SOURCE, vec![
+            ("textDocument/definition", position(12, 13)),
+            ("textDocument/definition", position(10, 17)),
+            ("textDocument/definition", position(13, 12)),
+        ]);
+
+        assert_eq!(result(&messages, 1)["range"]["start"], json!({ "line": 9, "character": 4 }));
+        assert_eq!(result(&messages, 2)["range"]["start"], json!({ "line": 9, "character": 9 }));
+        // Methods are looked up at runtime.
+        assert_eq!(result(&messages, 3), &Value::Null);
+    }
+
+    #[test]
+    fn references() {
+        let messages = serve(SOURCE, vec![
+            ("textDocument/references", json!({ "position": { "line": 0, "character": 7 }, "context": { "includeDeclaration": true } })),
+            ("textDocument/references", json!({ "position": { "line": 13, "character": 6 }, "context": { "includeDeclaration": false } })),
+        ]);
+
+        assert_eq!(starts(result(&messages, 1)), [(0, 6), (10, 11)]);
+        assert_eq!(starts(result(&messages, 2)), [(13, 6)]);
+    }
+
+    #[test]
+    fn hover() {
+        let messages = serve(SOURCE, vec![
+            ("textDocument/hover", position(10, 12)),
+            ("textDocument/hover", position(12, 13)),
+            ("textDocument/hover", position(2, 18)),
+            ("textDocument/hover", position(12, 4)),
+        ]);
+
+        assert_eq!(result(&messages, 1)["contents"]["value"], "(class) `Point`");
+        assert_eq!(result(&messages, 2)["contents"]["value"], "(function) `make`");
+        assert_eq!(result(&messages, 3)["contents"]["value"], "(parameter) `x`");
+        assert_eq!(result(&messages, 4)["contents"]["value"], "(variable) `point`");
+    }
+
+    #[test]
+    fn document_symbols() {
+        let messages = serve(SOURCE, vec![("textDocument/documentSymbol", json!({}))]);
+
+        let symbols = result(&messages, 1).as_array().unwrap();
+        let names: Vec<&str> = symbols.iter().map(|symbol| symbol["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["Point", "make"]);
+
+        let methods: Vec<&str> = symbols[0]["children"].as_array().unwrap().iter()
+            .map(|method| method["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(methods, ["init", "double"]);
+    }
+}
@@ -0,0 +1,86 @@
+#[macro_use]
+mod common;
+
+#[cfg(test)]
+mod map {
+    tests! {
+        index in map is OK
+        "1"
+        "{a: first, b: 2}"
+        "one"
+    }
+
+    tests! {
+        invalid_key in map is ERR
+        "[line 2:4] Error at '[': Map key must be a literal, not list"
+    }
+
+    tests! {
+        literal in map is OK
+        "{}"
+        "{a: 1, b: 2}"
+        "{1: one, true: yes, null: nothing}"
+        "true"
+        "false"
+    }
+
+    tests! {
+        methods in map is OK
+        "true"
+        "false"
+        "3"
+        "2"
+        "null"
+        "[a, c]"
+        "[1, 3]"
+        "a"
+        "1"
+        "c"
+        "3"
+    }
+
+    tests! {
+        missing_colon in map is ERR
+        "[line 1:16] Error at '1': Expected ':' after map key"
+    }
+
+    tests! {
+        nan_key in map is ERR
+        "[line 2:4] Error at '[': Map key cannot be NaN"
+    }
+
+    tests! {
+        undefined_key in map is ERR
+        "[line 2:4] Error at '[': Undefined key 'b'"
+    }
+
+    #[test]
+    fn self_containing() {
+        use rocks_lang::{rocks, Backend};
+
+        let source = "var m = {};\nm[\"self\"] = m;\nprint m;\nvar l = [m];\nm[\"list\"] = l;\nprint l;\n";
+
+        for backend in [Backend::Interpreter, Backend::Vm] {
+            let mut output = Vec::new();
+            rocks::with_backend(&mut output, backend).run(source.to_string()).unwrap();
+
+            assert_eq!(
+                "{self: {...}}\n[{self: {...}, list: [...]}]\n",
+                std::str::from_utf8(&output).unwrap(),
+                "on {backend:?} backend",
+            );
+        }
+    }
+
+    #[test]
+    fn key_before_value() {
+        use rocks_lang::config::InterpreterConfig;
+
+        let source = "fun value() {\n  print \"evaluated\";\n  return 1;\n}\nvar map = {[1]: value()};\n";
+
+        for run in crate::common::run(InterpreterConfig::default(), &[source]) {
+            assert_eq!("", run.output);
+            assert_eq!(vec!["[line 5:11] Error at '{': Map key must be a literal, not list"], run.diagnostics);
+        }
+    }
+}
@@ -3,6 +3,8 @@ mod common;
 
 #[cfg(test)]
 mod misc {
+    use rocks_lang::rocks;
+
     tests! {
         empty_file in misc is OK
     }
@@ -28,4 +30,17 @@ mod misc {
         unexpected_character in misc is ERR
         "[line 3:7] Error: Unexpected character '|'"
     }
+
+    #[test]
+    fn missing_file() {
+        let mut output = Vec::new();
+        let mut rocks = rocks::new(&mut output).with_reporter(|_: &_| {});
+
+        let errors = rocks.run_file("tests/target/misc/missing.rocks".to_string()).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors[0].message.starts_with("Could not read 'tests/target/misc/missing.rocks': "),
+            "{}", errors[0].message,
+        );
+    }
 }
@@ -0,0 +1,86 @@
+#[macro_use]
+mod common;
+
+#[cfg(test)]
+mod module {
+    tests! {
+        broken in module is ERR
+        "[line 1:8] Error at 'lib/broken.rocks': Could not load module 'broken.rocks': [line 1:9] Error at ';': Expected expression"
+    }
+
+    tests! {
+        cached in module is OK
+        "loading counter"
+        "1"
+        "2"
+        "3"
+        "3"
+        "true"
+    }
+
+    tests! {
+        cycle in module is ERR
+        "[line 1:8] Error at 'cycle_a.rocks': Import cycle detected: cycle_a.rocks -> cycle_b.rocks -> cycle_a.rocks"
+    }
+
+    tests! {
+        failure in module is ERR
+        "[line 2:13] Error at 'nil': Undefined variable 'nil'"
+    }
+
+    tests! {
+        from_import in module is OK
+        "Hello, world!"
+        "Hey, you"
+    }
+
+    tests! {
+        from_variable in module is OK
+        "still a variable"
+    }
+
+    tests! {
+        import_as in module is OK
+        "<module greeting>"
+        "greeting module"
+        "Hello, rocks!"
+        "Hi, there"
+    }
+
+    tests! {
+        missing in module is ERR
+        "[line 1:8] Error at 'lib/missing.rocks': Could not find module 'lib/missing.rocks'"
+    }
+
+    tests! {
+        missing_as in module is ERR
+        "[line 1:28] Error at ';': Expected 'as' after module path"
+    }
+
+    tests! {
+        missing_binding in module is ERR
+        "[line 1:41] Error at 'goodbye': Module 'greeting' has no binding 'goodbye'"
+    }
+
+    tests! {
+        module_scope in module is ERR
+        "[line 8:10] Error at 'main_only': Undefined variable 'main_only'"
+    }
+
+    tests! {
+        nested in module is OK
+        "12"
+        "10"
+        "2"
+    }
+
+    tests! {
+        not_top_level in module is ERR
+        "[line 2:3] Error at 'import': Can only import at the top level"
+    }
+
+    tests! {
+        self_import in module is ERR
+        "[line 1:8] Error at 'self_import.rocks': Import cycle detected: self_import.rocks -> self_import.rocks"
+    }
+}
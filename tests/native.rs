@@ -0,0 +1,140 @@
+#![allow(clippy::result_large_err)]
+
+#[macro_use]
+mod common;
+
+#[cfg(test)]
+mod native {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use rocks_lang::{rocks, Backend};
+    use rocks_lang::config::InterpreterConfig;
+    use rocks_lang::error::RuntimeError;
+    use rocks_lang::literal::Literal;
+    use rocks_lang::object::Arity;
+    use rocks_lang::stdlib::{Caller, Native, StdValue};
+    use rocks_lang::token::Token;
+
+    use crate::common::{run, Run};
+
+    #[test]
+    fn extra_arguments() {
+        for Run { output, diagnostics, .. } in run(InterpreterConfig::default(), &["clock(1);"]) {
+            assert_eq!(output, "");
+            assert_eq!(diagnostics, ["[line 1:8] Error at ')': Expected 0 arguments but got 1"]);
+        }
+    }
+
+    /// Adds up its arguments, which have to be numbers.
+    struct Sum;
+
+    impl Native for Sum {
+        fn call<V: StdValue>(&self, _: &mut dyn Caller<V>, arguments: Vec<V>) -> Result<V, RuntimeError> {
+            let mut sum = 0.0;
+            for argument in arguments {
+                match argument.literal() {
+                    Some(Literal::Number(number)) => sum += number,
+                    _ => return Err(RuntimeError {
+                        token: Token::from("sum"),
+                        message: "Expected numbers".to_string(),
+                        help: None,
+                        trace: vec![],
+                        value: None,
+                    }),
+                }
+            }
+            Ok(V::from(Literal::Number(sum)))
+        }
+    }
+
+    /// Calls its first argument with its second one.
+    struct Apply;
+
+    impl Native for Apply {
+        fn call<V: StdValue>(&self, caller: &mut dyn Caller<V>, mut arguments: Vec<V>) -> Result<V, RuntimeError> {
+            let argument = arguments.pop().unwrap();
+            let callee = arguments.pop().unwrap();
+            caller.call(callee, vec![argument], &Token::from("apply"))
+        }
+    }
+
+    #[test]
+    fn capturing() {
+        for backend in [Backend::Interpreter, Backend::Vm] {
+            let counter = Rc::new(Cell::new(0));
+
+            let mut output = Vec::new();
+            let mut rocks = rocks::with_backend(&mut output, backend).with_reporter(|_: &_| {});
+
+            let count = Rc::clone(&counter);
+            rocks.register_native("tick", Arity::Fixed(0), move |_| {
+                count.set(count.get() + 1);
+                Ok(Literal::Number(count.get() as f64))
+            });
+
+            rocks.run("tick(); tick(); print tick();".to_string()).unwrap();
+
+            drop(rocks);
+            assert_eq!(counter.get(), 3, "on {backend:?} backend");
+            assert_eq!(std::str::from_utf8(&output).unwrap(), "3\n", "on {backend:?} backend");
+        }
+    }
+
+    #[test]
+    fn closure_arguments() {
+        for backend in [Backend::Interpreter, Backend::Vm] {
+            let mut output = Vec::new();
+            let mut rocks = rocks::with_backend(&mut output, backend).with_reporter(|_: &_| {});
+            rocks.register_native("shout", Arity::Fixed(1), |arguments: Vec<Literal>| match &arguments[0] {
+                Literal::String(string) => Ok(Literal::String(string.to_uppercase())),
+                _ => Err("Expected a string".to_string()),
+            });
+
+            rocks.run("print shout(\"rocks\");".to_string()).unwrap();
+
+            let diagnostics = rocks.run("shout(1);".to_string()).unwrap_err();
+            assert_eq!(diagnostics[0].to_string(), "[line 1:8] Error at ')': Expected a string");
+
+            let diagnostics = rocks.run("shout([1]);".to_string()).unwrap_err();
+            assert_eq!(diagnostics[0].to_string(), "[line 1:10] Error at ')': Expected a literal but got list");
+
+            drop(rocks);
+            assert_eq!(std::str::from_utf8(&output).unwrap(), "ROCKS\n", "on {backend:?} backend");
+        }
+    }
+
+    #[test]
+    fn variadic() {
+        for backend in [Backend::Interpreter, Backend::Vm] {
+            let mut output = Vec::new();
+            let mut rocks = rocks::with_backend(&mut output, backend).with_reporter(|_: &_| {});
+            rocks.register_native("sum", Arity::Variadic(1), Sum);
+
+            rocks.run("print sum(1); print sum(1, 2, 3);".to_string()).unwrap();
+
+            let diagnostics = rocks.run("sum();".to_string()).unwrap_err();
+            assert_eq!(diagnostics[0].to_string(), "[line 1:5] Error at ')': Expected at least 1 argument but got 0");
+
+            let diagnostics = rocks.run("sum(1, \"2\");".to_string()).unwrap_err();
+            assert_eq!(diagnostics[0].to_string(), "[line 1:11] Error at ')': Expected numbers");
+
+            drop(rocks);
+            assert_eq!(std::str::from_utf8(&output).unwrap(), "1\n6\n", "on {backend:?} backend");
+        }
+    }
+
+    #[test]
+    fn callback() {
+        for backend in [Backend::Interpreter, Backend::Vm] {
+            let mut output = Vec::new();
+            let mut rocks = rocks::with_backend(&mut output, backend);
+            rocks.register_native("apply", Arity::Fixed(2), Apply);
+
+            rocks.run("fun double(x) { return x * 2; }\nprint apply(double, 21);".to_string()).unwrap();
+
+            drop(rocks);
+            assert_eq!(std::str::from_utf8(&output).unwrap(), "42\n", "on {backend:?} backend");
+        }
+    }
+}
@@ -0,0 +1,126 @@
+#[cfg(test)]
+mod recovery {
+    use rocks_lang::rocks;
+
+    /// Parses the source and returns the errors that it failed with.
+    fn errors(source: &str) -> Vec<String> {
+        let mut output = Vec::new();
+        let mut rocks = rocks::new(&mut output).with_reporter(|_: &_| {});
+
+        rocks.run(source.to_string())
+            .unwrap_err()
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn block() {
+        let source = "\
+fun first() {
+    print 1 +;
+}
+fun second() {
+    print 2
+}
+print 3 -;
+";
+
+        assert_eq!(errors(source), [
+            "[line 2:14] Error at ';': Expected expression",
+            "[line 6:1] Error at '}': Expected ';' after value",
+            "[line 7:10] Error at ';': Expected expression",
+        ]);
+    }
+
+    #[test]
+    fn class_members() {
+        let source = "\
+class Point {
+    init(x y) {
+        this.x = x;
+    }
+
+    var z = 1;
+
+    length() {
+        return this.x +;
+    }
+}
+print 1 +;
+";
+
+        assert_eq!(errors(source), [
+            "[line 2:12] Error at 'y': Expected ')' after parameters",
+            "[line 6:5] Error at 'var': Expected method name",
+            "[line 9:24] Error at ';': Expected expression",
+            "[line 12:10] Error at ';': Expected expression",
+        ]);
+    }
+
+    #[test]
+    fn arguments() {
+        assert_eq!(errors("print max(1 2, 3, 4 +);\nprint min(1, 2;\nprint 5 *;"), [
+            "[line 1:13] Error at '2': Expected ')' after arguments",
+            "[line 1:22] Error at ')': Expected expression",
+            "[line 2:15] Error at ';': Expected ')' after arguments",
+            "[line 3:10] Error at ';': Expected expression",
+        ]);
+    }
+
+    #[test]
+    fn statement_body() {
+        // The body of a broken statement is still checked for errors.
+        let source = "\
+while (x y) {
+    print *;
+}
+print 1;
+";
+
+        assert_eq!(errors(source), [
+            "[line 1:10] Error at 'y': Expected ')' after condition.",
+            "[line 2:11] Error at '*': Expected expression",
+        ]);
+    }
+
+    #[test]
+    fn statement_keywords() {
+        // Every statement keyword ends the broken statement before it.
+        let source = "\
+while (true) {
+    var a = 1 2
+    break print 1 +;
+    var b = 1 2
+    continue print 2 +;
+}
+var c = 1 2
+throw 3 +;
+var d = 1 2
+try {
+    print 4 +;
+} catch (e) {}
+";
+
+        assert_eq!(errors(source), [
+            "[line 2:15] Error at '2': Expected ';' after variable decleration",
+            "[line 3:11] Error at 'print': Expected ';' after break",
+            "[line 4:15] Error at '2': Expected ';' after variable decleration",
+            "[line 5:14] Error at 'print': Expected ';' after continue",
+            "[line 7:11] Error at '2': Expected ';' after variable decleration",
+            "[line 8:10] Error at ';': Expected expression",
+            "[line 9:11] Error at '2': Expected ';' after variable decleration",
+            "[line 11:14] Error at ';': Expected expression",
+        ]);
+    }
+
+    #[test]
+    fn keyword_suggestion() {
+        let mut output = Vec::new();
+        let mut rocks = rocks::new(&mut output).with_reporter(|_: &_| {});
+        let diagnostics = rocks.run("retrun 1;\npritn \"hello\";\ngreet hello;".to_string()).unwrap_err();
+
+        let help: Vec<Option<&str>> = diagnostics.iter().map(|diagnostic| diagnostic.help.as_deref()).collect();
+        assert_eq!(help, [Some("did you mean 'return'?"), Some("did you mean 'print'?"), None]);
+    }
+}
@@ -0,0 +1,158 @@
+#[cfg(test)]
+mod render {
+    use rocks_lang::rocks;
+    use rocks_lang::error::Diagnostic;
+    use rocks_lang::render;
+    use serde_json::Value;
+
+    /// Runs the source and returns the diagnostics that it failed with.
+    fn diagnostics(source: &str) -> Vec<Diagnostic> {
+        let mut output = Vec::new();
+        let mut rocks = rocks::new(&mut output).with_reporter(|_: &_| {});
+        rocks.run(source.to_string()).unwrap_err()
+    }
+
+    fn human(source: &str) -> String {
+        render::human(&diagnostics(source)[0], None, source, false)
+    }
+
+    #[test]
+    fn snippet() {
+        assert_eq!(human("var name = \"rocks\";\nprint name - 1;\n"), "\
+error: Binary operation '-' is not supported between string type and number type
+ --> line 2:12
+  |
+2 | print name - 1;
+  |            ^
+");
+
+        assert_eq!(human("var a = 1;\n\tprint undefined;\n"), "\
+error: Undefined variable 'undefined'
+ --> line 2:8
+  |
+2 | \tprint undefined;
+  | \t      ^^^^^^^^^
+");
+
+        // The span of a string includes its quotes, and the columns after a multi-line string start
+        // at its last line.
+        assert_eq!(human("print \"abc\" \"defgh\";"), "\
+error: Expected ';' after value
+ --> line 1:13
+  |
+1 | print \"abc\" \"defgh\";
+  |             ^^^^^^^
+");
+
+        assert_eq!(human("var s = \"a\nb\"; print s - 1;"), "\
+error: Binary operation '-' is not supported between string type and number type
+ --> line 2:13
+  |
+2 | b\"; print s - 1;
+  |             ^
+");
+    }
+
+    #[test]
+    fn help() {
+        assert_eq!(human("function greet() {}"), "\
+error: Expected ';' after expression
+ --> line 1:10
+  |
+1 | function greet() {}
+  |          ^^^^^
+  = help: did you mean 'fun'?
+");
+        assert_eq!(diagnostics("let a = 1;")[0].help.as_deref(), Some("did you mean 'var'?"));
+        assert_eq!(diagnostics("greet hello;")[0].help, None);
+    }
+
+    #[test]
+    fn trace() {
+        let source = "\
+fun fail() {
+    return 1 / nil;
+}
+fail();
+";
+
+        assert_eq!(human(source), "\
+error: Undefined variable 'nil'
+ --> line 2:16
+  |
+2 |     return 1 / nil;
+  |                ^^^
+    at fail (line 2)
+    at <script> (line 4)
+");
+    }
+
+    #[test]
+    fn file() {
+        let diagnostic = &diagnostics("print 1 +;")[0];
+
+        assert_eq!(render::human(diagnostic, Some("main.rocks"), "print 1 +;", false), "\
+error: Expected expression
+ --> main.rocks:1:10
+  |
+1 | print 1 +;
+  |          ^
+");
+        assert!(render::json(diagnostic, Some("main.rocks")).contains("\"file\":\"main.rocks\""));
+    }
+
+    #[test]
+    fn other_source() {
+        // The error points into the source of an earlier run.
+        let mut output = Vec::new();
+        let mut rocks = rocks::new(&mut output).with_reporter(|_: &_| {});
+        rocks.run("fun fail() { return -\"a\"; }".to_string()).unwrap();
+        let diagnostics = rocks.run("fail();".to_string()).unwrap_err();
+
+        assert_eq!(render::human(&diagnostics[0], None, "fail();", false), "\
+error: Unary operation '-' is not supported for string type
+ --> line 1:21
+    at fail (line 1)
+    at <script> (line 1)
+");
+    }
+
+    #[test]
+    fn color() {
+        let rendered = render::human(&diagnostics("print @;")[0], None, "print @;", true);
+        assert!(rendered.starts_with("\x1b[1;31merror\x1b[0m\x1b[1m: Unexpected character '@'\x1b[0m\n"));
+    }
+
+    #[test]
+    fn json() {
+        let diagnostic = &diagnostics("fun fail() { return nil; }\nfail();")[0];
+        let json: Value = serde_json::from_str(&render::json(diagnostic, None)).unwrap();
+
+        assert_eq!(json["severity"], "error");
+        assert_eq!(json["phase"], "runtime");
+        assert_eq!(json["message"], "Undefined variable 'nil'");
+        assert_eq!(json["file"], Value::Null);
+        assert_eq!(json["token"], "nil");
+        assert_eq!(json["span"]["start"], serde_json::json!({ "line": 1, "column": 21 }));
+        assert_eq!(json["span"]["end"], serde_json::json!({ "line": 1, "column": 24 }));
+        assert_eq!(json["trace"][1], serde_json::json!({ "name": "<script>", "file": null, "line": 2, "column": 6 }));
+    }
+
+    #[test]
+    fn unreadable_file() {
+        let mut output = Vec::new();
+        let mut rocks = rocks::new(&mut output).with_reporter(|_: &_| {});
+        let diagnostic = &rocks.run_file("tests/missing.rocks".to_string()).unwrap_err()[0];
+
+        assert_eq!(diagnostic.to_string(), "Error: Could not read 'tests/missing.rocks': No such file or directory (os error 2)");
+        assert_eq!(render::human(diagnostic, Some("missing.rocks"), "", false), "\
+error: Could not read 'tests/missing.rocks': No such file or directory (os error 2)
+ --> missing.rocks
+");
+
+        let json: Value = serde_json::from_str(&render::json(diagnostic, Some("missing.rocks"))).unwrap();
+        assert_eq!(json["phase"], "io");
+        assert_eq!(json["file"], "missing.rocks");
+        assert_eq!(json["span"], Value::Null);
+    }
+}
@@ -0,0 +1,259 @@
+#[cfg(test)]
+mod repl {
+    use std::{env, fs, process};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use rocks_lang::{rocks, render, Backend, Termination};
+    use rocks_lang::error::{Diagnostic, Reporter};
+    use rocks_lang::repl::{highlight, is_incomplete, Repl};
+
+    /// Feeds the lines to a REPL on both backends, checks that they print the same and returns
+    /// the output of the scripts followed by the output of the meta-commands.
+    fn session(lines: &[&str]) -> (String, String) {
+        let outputs: Vec<(String, String)> = [Backend::Interpreter, Backend::Vm].into_iter().map(|backend| {
+            let mut output = Vec::new();
+            let mut commands = Vec::new();
+
+            let mut rocks = rocks::with_backend(&mut output, backend).with_reporter(|_: &_| {});
+            let mut repl = Repl::new(&mut rocks);
+            for line in lines {
+                repl.line(line, &mut commands);
+            }
+
+            drop(rocks);
+            (String::from_utf8(output).unwrap(), String::from_utf8(commands).unwrap())
+        }).collect();
+
+        assert_eq!(outputs[0], outputs[1], "backends have different outputs");
+        outputs[0].clone()
+    }
+
+    #[test]
+    fn incomplete() {
+        assert!(is_incomplete("fun add(a, b) {"));
+        assert!(is_incomplete("print [1,"));
+        assert!(is_incomplete("print \"multi"));
+        assert!(!is_incomplete("print \"a {\";"));
+        assert!(!is_incomplete("}"));
+    }
+
+    #[test]
+    fn multi_line() {
+        let mut output = Vec::new();
+        let mut rocks = rocks::new(&mut output);
+        let mut repl = Repl::new(&mut rocks);
+
+        assert_eq!(repl.prompt(), "> ");
+        repl.line("fun add(a, b) {", &mut Vec::new());
+        assert_eq!(repl.prompt(), "... ");
+        repl.line("    return a + b;", &mut Vec::new());
+        repl.line("}", &mut Vec::new());
+        assert_eq!(repl.prompt(), "> ");
+        repl.line("print add(1, 2);", &mut Vec::new());
+
+        repl.line("print add(", &mut Vec::new());
+        assert!(repl.cancel());
+        assert!(!repl.cancel());
+        assert_eq!(repl.prompt(), "> ");
+
+        drop(rocks);
+        assert_eq!(String::from_utf8(output).unwrap(), "3\n");
+    }
+
+    #[test]
+    fn end_of_input() {
+        let mut output = Vec::new();
+        let mut reported = Vec::new();
+        let mut rocks = rocks::new(&mut output).with_reporter(|diagnostic: &_| reported.push(format!("{diagnostic}")));
+        let mut repl = Repl::new(&mut rocks);
+
+        assert_eq!(repl.finish(&mut Vec::new()), Termination::Completed);
+
+        // The unterminated input is reported instead of being dropped.
+        repl.line("print [1,", &mut Vec::new());
+        repl.line("2", &mut Vec::new());
+        repl.finish(&mut Vec::new());
+        assert_eq!(repl.prompt(), "> ");
+
+        drop(rocks);
+        assert_eq!(String::from_utf8(output).unwrap(), "");
+        assert_eq!(reported, ["[line 2:2] Error at ';': Expected ']' after list elements"]);
+    }
+
+    #[test]
+    fn echo() {
+        let (output, _) = session(&[
+            "1 + 2",
+            "var list = [1, 2];",
+            "list[0] = 3;",
+            "list",
+            "\"a\" + \"b\"; 4 // comment",
+        ]);
+
+        assert_eq!(output, "3\n[3, 2]\nab\n4\n");
+    }
+
+    #[test]
+    fn earlier_lines() {
+        let (output, _) = session(&[
+            "fun counter() { var i = 0; return fun () { i = i + 1; return i; }; }",
+            "var next = counter();",
+            "next()",
+            "class A { name() { return \"a\"; } }",
+            "class B < A { name() { return super.name() + \"b\"; } }",
+            "B().name()",
+            "next()",
+        ]);
+
+        assert_eq!(output, "1\nab\n2\n");
+    }
+
+    #[test]
+    fn env_and_reset() {
+        let (_, commands) = session(&[
+            "var b = \"two\";",
+            "fun a() {}",
+            ":env",
+            ":reset",
+            ":env",
+        ]);
+
+        assert_eq!(commands, "a = <function a>\nb = two\n");
+    }
+
+    #[test]
+    fn ast() {
+        let (_, commands) = session(&[":ast 1 + 2 * -x", ":ast 1 +"]);
+        assert_eq!(commands, "(+ 1 (* 2 (- x)))\n");
+    }
+
+    #[test]
+    fn commands() {
+        let (_, commands) = session(&[":time", ":time", ":nope"]);
+        assert_eq!(commands, "\
+Timing is on
+Timing is off
+Unknown command ':nope', type ':help' for a list of commands
+");
+
+        let (_, help) = session(&[":help"]);
+        assert_eq!(help.lines().count(), 6);
+        assert!(help.starts_with(":help         Shows this message\n"));
+    }
+
+    #[test]
+    fn load() {
+        let path = env::temp_dir().join(format!("rocks-repl-{}.rocks", process::id()));
+        fs::write(&path, "var loaded = 1;\nprint \"loading\";\nloaded + 1;\n").unwrap();
+
+        let load = format!(":load {}", path.display());
+        let (output, _) = session(&[&load, "loaded"]);
+        fs::remove_file(&path).unwrap();
+
+        // Files are run as they are, without printing their expressions.
+        assert_eq!(output, "loading\n1\n");
+
+        let (_, commands) = session(&[":load /missing.rocks"]);
+        assert!(commands.starts_with("Could not read '/missing.rocks': "));
+    }
+
+    /// Renders the diagnostics like the standard error reporter does, without color.
+    struct Rendered {
+        output: Rc<RefCell<String>>,
+        file: Option<String>,
+        source: String,
+    }
+
+    impl Reporter for Rendered {
+        fn report(&mut self, diagnostic: &Diagnostic) {
+            let rendered = render::human(diagnostic, self.file.as_deref(), &self.source, false);
+            self.output.borrow_mut().push_str(&rendered);
+        }
+
+        fn source(&mut self, file: Option<&str>, source: &str) {
+            self.file = file.map(str::to_string);
+            self.source = source.to_string();
+        }
+    }
+
+    #[test]
+    fn load_relative_to_file() {
+        let directory = env::temp_dir().join(format!("rocks-repl-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("lib.rocks"), "var answer = 42;\n").unwrap();
+        fs::write(directory.join("main.rocks"), "\
+import \"lib.rocks\" as lib;
+print lib.answer;
+fun fail() {
+  return 1 - \"a\";
+}
+fail();
+").unwrap();
+
+        for backend in [Backend::Interpreter, Backend::Vm] {
+            let mut output = Vec::new();
+            let mut commands = Vec::new();
+            let diagnostics = Rc::new(RefCell::new(String::new()));
+
+            let reporter = Rendered { output: Rc::clone(&diagnostics), file: None, source: String::new() };
+            let mut rocks = rocks::with_backend(&mut output, backend).with_reporter(reporter);
+            let mut repl = Repl::new(&mut rocks);
+            repl.line(&format!(":load {}", directory.join("main.rocks").display()), &mut commands);
+            repl.line("fail();", &mut commands);
+
+            drop(rocks);
+            let diagnostics = diagnostics.borrow();
+
+            // Imports are relative to the loaded file, and its errors point into it.
+            assert_eq!(String::from_utf8(output).unwrap(), "42\n", "on {backend:?} backend");
+            assert!(diagnostics.contains(" --> main.rocks:4:12\n"), "on {backend:?} backend: {diagnostics}");
+            assert!(diagnostics.contains("4 |   return 1 - \"a\";\n"), "on {backend:?} backend: {diagnostics}");
+
+            // The file is forgotten after it ran, later lines are not part of it.
+            assert!(diagnostics.contains("at <script> (line 1)\n"), "on {backend:?} backend: {diagnostics}");
+        }
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn highlighting() {
+        assert_eq!(
+            highlight("var name = \"rocks\"; // comment"),
+            "\x1b[35mvar\x1b[0m name = \x1b[32m\"rocks\"\x1b[0m; \x1b[90m// comment\x1b[0m",
+        );
+        assert_eq!(
+            highlight("if (true)\n  print null;"),
+            "\x1b[35mif\x1b[0m (\x1b[33mtrue\x1b[0m)\n  \x1b[35mprint\x1b[0m \x1b[33mnull\x1b[0m;",
+        );
+
+        // Broken input is still highlighted as far as it can be scanned.
+        assert_eq!(highlight("print \"open"), "\x1b[35mprint\x1b[0m \"open");
+    }
+
+    #[test]
+    fn completion() {
+        for backend in [Backend::Interpreter, Backend::Vm] {
+            let mut output = Vec::new();
+            let mut rocks = rocks::with_backend(&mut output, backend);
+            let mut repl = Repl::new(&mut rocks);
+
+            repl.line("class Point { init(x) { this.x = x; } length() { return this.x; } }", &mut Vec::new());
+            repl.line("var point = Point(1);", &mut Vec::new());
+            repl.line("fun scale(factor) {", &mut Vec::new());
+
+            let helper = repl.helper(false);
+            let complete = |line: &str| helper.candidates(line, line.len());
+
+            assert_eq!(complete("pri"), (0, vec!["print".to_string()]));
+            assert_eq!(complete("var p = poi"), (8, vec!["point".to_string()]));
+            assert_eq!(complete("print point."), (12, vec!["init".to_string(), "length".to_string(), "x".to_string()]));
+            assert_eq!(complete("print point.le"), (12, vec!["length".to_string()]));
+            // Names that are declared by the incomplete input are completed as well.
+            assert_eq!(complete("  return fac"), (9, vec!["factor".to_string()]));
+            assert_eq!(complete("var count = 1; print co"), (21, vec!["continue".to_string(), "count".to_string()]));
+            assert_eq!(complete("print "), (6, vec![]));
+        }
+    }
+}
@@ -0,0 +1,73 @@
+#[macro_use]
+mod common;
+
+#[cfg(test)]
+mod sandbox {
+    use rocks_lang::config::InterpreterConfig;
+    use rocks_lang::stdlib::{StdModule, Stdlib};
+
+    use crate::common::{run, Run};
+
+    #[test]
+    fn denied_stdin() {
+        for Run { diagnostics, .. } in run(InterpreterConfig::sandboxed(), &["input();"]) {
+            assert_eq!(diagnostics, ["[line 1:7] Error at ')': Cannot call 'input' without the 'stdin' capability"]);
+        }
+    }
+
+    #[test]
+    fn denied_clock() {
+        let source = "import \"time\" as time;\nclock();\ntime.now();";
+
+        for Run { diagnostics, .. } in run(InterpreterConfig::sandboxed(), &[source]) {
+            assert_eq!(diagnostics, [
+                "[line 2:7] Error at ')': Cannot call 'clock' without the 'clock' capability",
+                "[line 3:10] Error at ')': Cannot call 'now' without the 'clock' capability",
+            ]);
+        }
+    }
+
+    #[test]
+    fn denied_environment() {
+        let source = "import \"os\" as os;\nprint os.args();\nos.env(\"HOME\");\nos.exit(1);";
+
+        for Run { output, diagnostics, .. } in run(InterpreterConfig::sandboxed(), &[source]) {
+            assert_eq!(output, "[]\n");
+            assert_eq!(diagnostics, [
+                "[line 3:14] Error at ')': Cannot call 'env' without the 'environment' capability",
+                "[line 4:10] Error at ')': Cannot call 'exit' without the 'environment' capability",
+            ]);
+        }
+    }
+
+    #[test]
+    fn denied_filesystem() {
+        let source = "import \"io\" as io;\nio.read(\"Cargo.toml\");\nimport \"tests/target/module/lib/helper.rocks\" as lib;";
+
+        for Run { diagnostics, .. } in run(InterpreterConfig::sandboxed(), &[source]) {
+            assert_eq!(diagnostics, [
+                "[line 2:21] Error at ')': Cannot call 'read' without the 'filesystem' capability",
+                "[line 3:8] Error at 'tests/target/module/lib/helper.rocks': Cannot import 'tests/target/module/lib/helper.rocks' without the 'filesystem' capability",
+            ]);
+        }
+    }
+
+    #[test]
+    fn granted() {
+        let config = InterpreterConfig { clock: true, ..InterpreterConfig::sandboxed() };
+
+        for Run { output, diagnostics, .. } in run(config, &["import \"math\" as math;\nprint clock() > 0;\nprint math.sqrt(4);"]) {
+            assert!(diagnostics.is_empty());
+            assert_eq!(output, "true\n2\n");
+        }
+    }
+
+    #[test]
+    fn disabled_module() {
+        let config = InterpreterConfig { stdlib: Stdlib::new(&[StdModule::Math]), ..InterpreterConfig::default() };
+
+        for Run { diagnostics, .. } in run(config, &["import \"math\" as math;\nimport \"str\" as str;"]) {
+            assert_eq!(diagnostics, ["[line 2:8] Error at 'str': Could not find module 'str'"]);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+#[macro_use]
+mod common;
+
+#[cfg(test)]
+mod stdlib {
+    tests! {
+        argument_type in stdlib is ERR
+        "[line 3:17] Error at ')': Expected a number but got string"
+    }
+
+    tests! {
+        io in stdlib is OK
+        "true"
+        "false"
+        "[first, second]"
+        "first"
+        "second"
+        ""
+    }
+
+    tests! {
+        math in stdlib is OK
+        "4"
+        "2"
+        "3"
+        "3"
+        "3"
+        "1024"
+        "0"
+        "1"
+        "0"
+        "1"
+        "3"
+        "314"
+        "271"
+    }
+
+    tests! {
+        missing_member in stdlib is ERR
+        "[line 3:6] Error at 'tau': Module 'math' has no binding 'tau'"
+    }
+
+    tests! {
+        os in stdlib is OK
+        "[]"
+        "null"
+    }
+
+    tests! {
+        random in stdlib is OK
+        "true"
+        "true"
+        "true"
+    }
+
+    tests! {
+        read_missing in stdlib is ERR
+        "[line 3:42] Error at ')': Could not read file 'tests/target/stdlib/missing.txt': No such file or directory (os error 2)"
+    }
+
+    tests! {
+        string in stdlib is OK
+        "[a, b, c]"
+        "a-b-c"
+        "[a, b, c]"
+        "padded"
+        "ROCKS"
+        "rocks"
+        "5"
+        "2"
+        "-1"
+        "true"
+        "a+b+c"
+        "éll"
+    }
+
+    tests! {
+        substring_bounds in stdlib is ERR
+        "[line 3:26] Error at ')': Substring range 1..5 is out of bounds for a string of length 3"
+    }
+
+    tests! {
+        time in stdlib is OK
+        "true"
+        "true"
+    }
+
+    #[test]
+    fn exit() {
+        use rocks_lang::{rocks, Backend, Termination};
+
+        let source = "\
+import \"os\" as os;
+print \"before\";
+try {
+    [1, 2].map(fun (code) => os.exit(code + 2));
+} catch (e) {
+    print \"caught\";
+} finally {
+    print \"finally\";
+}
+print \"after\";
+";
+
+        for backend in [Backend::Interpreter, Backend::Vm] {
+            let mut output = Vec::new();
+            let mut rocks = rocks::with_backend(&mut output, backend);
+
+            assert_eq!(rocks.run(source.to_string()).unwrap(), Termination::Exit(3), "on {backend:?} backend");
+            assert_eq!(rocks.run("print \"again\";".to_string()).unwrap(), Termination::Completed, "on {backend:?} backend");
+
+            drop(rocks);
+            assert_eq!("before\nagain\n", std::str::from_utf8(&output).unwrap(), "on {backend:?} backend");
+        }
+    }
+
+    #[test]
+    fn exit_after_error() {
+        use rocks_lang::{rocks, Backend, Termination};
+
+        let source = "import \"os\" as os;\nprint missing;\nos.exit(4);\n";
+
+        for backend in [Backend::Interpreter, Backend::Vm] {
+            let mut output = Vec::new();
+            let mut reported = Vec::new();
+            let mut rocks = rocks::with_backend(&mut output, backend)
+                .with_reporter(|diagnostic: &_| reported.push(format!("{diagnostic}")));
+
+            assert_eq!(rocks.run(source.to_string()), Ok(Termination::Exit(4)), "on {backend:?} backend");
+
+            drop(rocks);
+            assert_eq!(reported, ["[line 2:7] Error at 'missing': Undefined variable 'missing'"], "on {backend:?} backend");
+        }
+    }
+}
@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod suggestion {
+    use rocks_lang::{rocks, Backend};
+
+    /// Runs the source on both backends, checks that they fail with the same help note and
+    /// returns it.
+    fn help(source: &str) -> Option<String> {
+        let help: Vec<Option<String>> = [Backend::Interpreter, Backend::Vm].into_iter().map(|backend| {
+            let mut output = Vec::new();
+            let mut rocks = rocks::with_backend(&mut output, backend).with_reporter(|_: &_| {});
+
+            let diagnostics = rocks.run(source.to_string()).unwrap_err();
+            diagnostics[0].help.clone()
+        }).collect();
+
+        assert_eq!(help[0], help[1], "backends have different help notes");
+        help[0].clone()
+    }
+
+    #[test]
+    fn global() {
+        assert_eq!(help("var counter = 1;\nprint conter;").as_deref(), Some("did you mean 'counter'?"));
+        assert_eq!(help("print clokc();").as_deref(), Some("did you mean 'clock'?"));
+        assert_eq!(help("var total = 0;\ntotl = 1;").as_deref(), Some("did you mean 'total'?"));
+    }
+
+    #[test]
+    fn local() {
+        let source = "\
+fun outer() {
+    var length = 1;
+    fun inner(width) {
+        return lenght * widht;
+    }
+    return inner(2);
+}
+outer();
+";
+
+        assert_eq!(help(source).as_deref(), Some("did you mean 'length'?"));
+    }
+
+    #[test]
+    fn property() {
+        let source = "\
+class Shape {
+    area() { return 0; }
+}
+class Square < Shape {
+    init(side) { this.side = side; }
+}
+var square = Square(2);
+";
+
+        assert_eq!(help(&format!("{source}print square.sied;")).as_deref(), Some("did you mean 'side'?"));
+        assert_eq!(help(&format!("{source}print square.aera();")).as_deref(), Some("did you mean 'area'?"));
+    }
+
+    #[test]
+    fn super_method() {
+        let source = "\
+class Shape {
+    area() { return 0; }
+}
+class Square < Shape {
+    area() { return super.aera(); }
+}
+Square().area();
+";
+
+        assert_eq!(help(source).as_deref(), Some("did you mean 'area'?"));
+    }
+
+    #[test]
+    fn no_similar_name() {
+        assert_eq!(help("var apple = 1;\nprint orange;"), None);
+        assert_eq!(help("print x;"), None);
+    }
+}
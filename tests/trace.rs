@@ -0,0 +1,126 @@
+#[cfg(test)]
+mod trace {
+    use std::{env, fs, process};
+
+    use rocks_lang::{rocks, Backend};
+    use rocks_lang::error::Diagnostic;
+
+    /// Runs the source on both backends, checks that they fail with the same diagnostics and
+    /// returns the trace of the first one.
+    fn trace(run: impl Fn(&mut rocks) -> Vec<Diagnostic>) -> Vec<String> {
+        let traces: Vec<Vec<String>> = [Backend::Interpreter, Backend::Vm].into_iter().map(|backend| {
+            let mut output = Vec::new();
+            let mut rocks = rocks::with_backend(&mut output, backend).with_reporter(|_: &_| {});
+
+            let diagnostics = run(&mut rocks);
+            diagnostics[0].trace.iter().map(|frame| frame.to_string()).collect()
+        }).collect();
+
+        assert_eq!(traces[0], traces[1], "backends have different traces");
+        traces[0].clone()
+    }
+
+    fn run(source: &str) -> Vec<String> {
+        trace(|rocks| rocks.run(source.to_string()).unwrap_err())
+    }
+
+    #[test]
+    fn nested_calls() {
+        let source = "\
+class Point {
+    init(x) {
+        this.x = x;
+    }
+
+    length() {
+        return this.x * undefined;
+    }
+}
+fun measure(point) {
+    return point.length();
+}
+print measure(Point(1));
+";
+
+        assert_eq!(run(source), [
+            "at length (line 7)",
+            "at measure (line 11)",
+            "at <script> (line 13)",
+        ]);
+    }
+
+    #[test]
+    fn initializer() {
+        let source = "\
+class Point {
+    init(x) {
+        this.x = x + \"!\";
+    }
+}
+var point = Point(1);
+";
+
+        assert_eq!(run(source), ["at Point (line 3)", "at <script> (line 6)"]);
+    }
+
+    #[test]
+    fn native() {
+        let source = "import \"math\" as math;\nfun root(x) { return math.sqrt(x); }\nroot(\"a\");";
+
+        assert_eq!(run(source), ["at sqrt (native)", "at root (line 2)", "at <script> (line 3)"]);
+    }
+
+    #[test]
+    fn callback() {
+        let source = "\
+fun divide(list) {
+    return list.map(fun (x) => x / \"a\");
+}
+divide([1, 2, 3]);
+";
+
+        assert_eq!(run(source), [
+            "at <lambda> (line 2)",
+            "at map (line 2)",
+            "at divide (line 2)",
+            "at <script> (line 4)",
+        ]);
+    }
+
+    #[test]
+    fn top_level() {
+        assert_eq!(run("print 1;\nprint -\"a\";"), ["at <script> (line 2)"]);
+    }
+
+    #[test]
+    fn caught_and_thrown() {
+        let source = "\
+fun fail() { throw \"error\"; }
+fun retry() {
+    try {
+        fail();
+    } catch (error) {
+        throw error;
+    }
+}
+retry();
+";
+
+        // Rethrown errors keep the trace of where they were first thrown.
+        assert_eq!(run(source), ["at fail (line 1)", "at retry (line 4)", "at <script> (line 9)"]);
+    }
+
+    #[test]
+    fn modules() {
+        let directory = env::temp_dir().join(format!("rocks-trace-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("lib.rocks"), "fun fail(x) {\n    return x.missing;\n}\n").unwrap();
+        fs::write(directory.join("main.rocks"), "from \"lib.rocks\" import fail;\nfail(1);\n").unwrap();
+
+        let main = directory.join("main.rocks").to_string_lossy().to_string();
+        let trace = trace(|rocks| rocks.run_file(main.clone()).unwrap_err());
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(trace, ["at fail (lib.rocks:2)", "at <script> (main.rocks:2)"]);
+    }
+}